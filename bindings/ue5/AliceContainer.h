@@ -33,6 +33,7 @@ enum class AliceContainerState : uint8_t
     Running  = 1,
     Paused   = 2,
     Stopped  = 3,
+    Exited   = 4,
     Invalid  = 255,
 };
 
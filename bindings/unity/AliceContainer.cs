@@ -91,6 +91,7 @@ namespace Alice.Container
         Running  = 1,
         Paused   = 2,
         Stopped  = 3,
+        Exited   = 4,
         Invalid  = 255,
     }
 
@@ -0,0 +1,1489 @@
+//! Tar Archives
+//!
+//! A minimal POSIX tar reader and writer used to copy files into and out of
+//! running containers, and to export and import root filesystems.
+//!
+//! ## Format
+//!
+//! Archives are written as `ustar`, with a `pax` extended header for paths,
+//! link targets, sizes and IDs that do not fit the fixed-size fields, and
+//! for extended attributes (`SCHILY.xattr.<name>` records, as GNU tar and
+//! libarchive write them). The reader also accepts GNU long name entries and
+//! base-256 numbers.
+//!
+//! | Entry | Type flag |
+//! |-------|-----------|
+//! | Regular file | `0` |
+//! | Hard link | `1` |
+//! | Symbolic link | `2` |
+//! | Character device | `3` |
+//! | Block device | `4` |
+//! | Directory | `5` |
+//! | FIFO | `6` |
+//!
+//! ## Ownership
+//!
+//! Archives hold IDs as seen inside the container. [`Owners`] translates
+//! them to and from host IDs through the container's user namespace; IDs
+//! on the host side of a copy are kept as they are.
+
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Component, Path, PathBuf};
+
+use crate::namespace::{IdMap, IdMapping, OVERFLOW_ID};
+
+/// Size of a header or data block
+const BLOCK: usize = 512;
+
+// ============================================================================
+// Entries
+// ============================================================================
+
+/// Kind of an archive entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryKind {
+    /// Regular file, followed by its content
+    File,
+    /// Directory
+    Dir,
+    /// Symbolic link to `Entry::link`
+    Symlink,
+    /// Another name for the archive entry `Entry::link`
+    Hardlink,
+    /// Character device `Entry::device`
+    CharDevice,
+    /// Block device `Entry::device`
+    BlockDevice,
+    /// Named pipe
+    Fifo,
+}
+
+impl EntryKind {
+    const fn typeflag(self) -> u8 {
+        match self {
+            Self::File => b'0',
+            Self::Hardlink => b'1',
+            Self::Symlink => b'2',
+            Self::CharDevice => b'3',
+            Self::BlockDevice => b'4',
+            Self::Dir => b'5',
+            Self::Fifo => b'6',
+        }
+    }
+
+    const fn from_typeflag(flag: u8) -> Option<Self> {
+        match flag {
+            // NUL is the pre-POSIX regular file, 7 a contiguous file
+            b'0' | b'\0' | b'7' => Some(Self::File),
+            b'1' => Some(Self::Hardlink),
+            b'2' => Some(Self::Symlink),
+            b'3' => Some(Self::CharDevice),
+            b'4' => Some(Self::BlockDevice),
+            b'5' => Some(Self::Dir),
+            b'6' => Some(Self::Fifo),
+            _ => None,
+        }
+    }
+}
+
+/// Metadata of one archive entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// Path relative to the archive root
+    pub(crate) path: PathBuf,
+    pub(crate) kind: EntryKind,
+    /// Permission bits, including setuid, setgid and sticky
+    pub(crate) mode: u32,
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    /// Modification time in seconds since the epoch
+    pub(crate) mtime: i64,
+    /// Size of the content that follows the header
+    pub(crate) size: u64,
+    /// Target of a symbolic or hard link
+    pub(crate) link: PathBuf,
+    /// Major and minor number of a device node
+    pub(crate) device: (u32, u32),
+    /// Extended attributes by name
+    pub(crate) xattrs: Vec<(String, Vec<u8>)>,
+}
+
+impl Entry {
+    fn new(path: PathBuf, kind: EntryKind) -> Self {
+        Self {
+            path,
+            kind,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            mtime: 0,
+            size: 0,
+            link: PathBuf::new(),
+            device: (0, 0),
+            xattrs: Vec::new(),
+        }
+    }
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Writes entries to a tar stream
+pub(crate) struct TarWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub(crate) const fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Append `entry`, reading its `size` bytes of content from `data`
+    ///
+    /// Content that ends early is padded with zeros, so a file shrinking
+    /// while it is archived still yields a valid archive.
+    pub(crate) fn append(&mut self, entry: &Entry, data: &mut dyn Read) -> io::Result<()> {
+        let mut path = entry.path.as_os_str().as_bytes().to_vec();
+        if entry.kind == EntryKind::Dir && !path.ends_with(b"/") {
+            path.push(b'/');
+        }
+        let link = entry.link.as_os_str().as_bytes();
+
+        let mut header = [0u8; BLOCK];
+        let mut pax = Vec::new();
+        if !put_bytes(&mut header[0..100], &path) {
+            pax_record(&mut pax, "path", &path);
+        }
+        put_octal(&mut header[100..108], u64::from(entry.mode & 0o7777));
+        for (field, key, value) in [
+            (108..116, "uid", u64::from(entry.uid)),
+            (116..124, "gid", u64::from(entry.gid)),
+            (124..136, "size", entry.size),
+        ] {
+            if !put_octal(&mut header[field], value) {
+                pax_record(&mut pax, key, value.to_string().as_bytes());
+            }
+        }
+        let mtime = u64::try_from(entry.mtime).unwrap_or(0);
+        if entry.mtime < 0 || !put_octal(&mut header[136..148], mtime) {
+            pax_record(&mut pax, "mtime", entry.mtime.to_string().as_bytes());
+        }
+        header[156] = entry.kind.typeflag();
+        if !put_bytes(&mut header[157..257], link) {
+            pax_record(&mut pax, "linkpath", link);
+        }
+        put_octal(&mut header[329..337], u64::from(entry.device.0));
+        put_octal(&mut header[337..345], u64::from(entry.device.1));
+        for (name, value) in &entry.xattrs {
+            pax_record(&mut pax, &format!("SCHILY.xattr.{name}"), value);
+        }
+
+        if !pax.is_empty() {
+            let mut name = b"PaxHeaders/".to_vec();
+            name.extend(path.iter().rev().take(80).rev());
+            let mut pax_header = [0u8; BLOCK];
+            put_bytes(&mut pax_header[0..100], &name);
+            put_octal(&mut pax_header[100..108], 0o644);
+            put_octal(&mut pax_header[124..136], pax.len() as u64);
+            pax_header[156] = b'x';
+            self.write_header(pax_header)?;
+            self.inner.write_all(&pax)?;
+            self.pad(pax.len() as u64)?;
+        }
+
+        self.write_header(header)?;
+        let copied = io::copy(&mut data.take(entry.size), &mut self.inner)?;
+        io::copy(
+            &mut io::repeat(0).take(entry.size - copied),
+            &mut self.inner,
+        )?;
+        self.pad(entry.size)
+    }
+
+    /// Write the end-of-archive marker and return the underlying writer
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0u8; 2 * BLOCK])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_header(&mut self, mut header: [u8; BLOCK]) -> io::Result<()> {
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..156].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+        self.inner.write_all(&header)
+    }
+
+    /// Pad content of `len` bytes to the next block boundary
+    fn pad(&mut self, len: u64) -> io::Result<()> {
+        let rest = (BLOCK - (len % BLOCK as u64) as usize) % BLOCK;
+        self.inner.write_all(&[0u8; BLOCK][..rest])
+    }
+}
+
+/// Store `value` NUL-padded in `field`, `false` if it does not fit
+fn put_bytes(field: &mut [u8], value: &[u8]) -> bool {
+    if value.len() > field.len() {
+        return false;
+    }
+    field[..value.len()].copy_from_slice(value);
+    true
+}
+
+/// Store `value` as NUL-terminated octal in `field`, `false` if it does not fit
+fn put_octal(field: &mut [u8], value: u64) -> bool {
+    let digits = field.len() - 1;
+    let text = format!("{value:0digits$o}");
+    if text.len() > digits {
+        return false;
+    }
+    field[..digits].copy_from_slice(text.as_bytes());
+    field[digits] = 0;
+    true
+}
+
+/// Append a `<length> <key>=<value>\n` record, the length counting itself
+fn pax_record(pax: &mut Vec<u8>, key: &str, value: &[u8]) {
+    let body = key.len() + value.len() + 3;
+    let mut len = body + 1;
+    while len != body + len.to_string().len() {
+        len = body + len.to_string().len();
+    }
+    pax.extend_from_slice(format!("{len} {key}=").as_bytes());
+    pax.extend_from_slice(value);
+    pax.push(b'\n');
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Reads entries from a tar stream
+///
+/// After `next_entry()`, reading from the reader yields the content of that
+/// entry. Content that is not read is skipped by the next call.
+pub(crate) struct TarReader<R: Read> {
+    inner: R,
+    /// Unread content of the current entry
+    remaining: u64,
+    /// Padding after the current entry
+    padding: u64,
+}
+
+impl<R: Read> TarReader<R> {
+    pub(crate) const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    /// Read the next entry header, `None` at the end of the archive
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidData` for corrupt headers and unknown entry types.
+    pub(crate) fn next_entry(&mut self) -> io::Result<Option<Entry>> {
+        let mut path_override = None;
+        let mut link_override = None;
+        let mut pax: Vec<(String, Vec<u8>)> = Vec::new();
+
+        loop {
+            self.skip_rest()?;
+            let Some(header) = self.read_header()? else {
+                return Ok(None);
+            };
+            let size = parse_numeric(&header[124..136])?;
+            let typeflag = header[156];
+            match typeflag {
+                b'x' => pax = parse_pax(&self.read_content(size)?)?,
+                b'g' => self.start_content(size),
+                b'L' => path_override = Some(trim_nul(&self.read_content(size)?).to_vec()),
+                b'K' => link_override = Some(trim_nul(&self.read_content(size)?).to_vec()),
+                _ => {
+                    let kind = EntryKind::from_typeflag(typeflag).ok_or_else(|| {
+                        invalid(format!("unsupported tar entry type {:?}", typeflag as char))
+                    })?;
+                    let mut entry = Entry::new(PathBuf::new(), kind);
+                    let mut path = header_path(&header);
+                    let mut link = trim_nul(&header[157..257]).to_vec();
+                    entry.mode = parse_numeric(&header[100..108])? as u32 & 0o7777;
+                    entry.uid = parse_numeric(&header[108..116])? as u32;
+                    entry.gid = parse_numeric(&header[116..124])? as u32;
+                    entry.mtime = parse_numeric(&header[136..148])? as i64;
+                    entry.size = size;
+                    entry.device = (
+                        parse_numeric(&header[329..337])? as u32,
+                        parse_numeric(&header[337..345])? as u32,
+                    );
+
+                    path = path_override.take().unwrap_or(path);
+                    link = link_override.take().unwrap_or(link);
+                    for (key, value) in pax.drain(..) {
+                        let number = || {
+                            std::str::from_utf8(&value)
+                                .ok()
+                                .and_then(|v| v.split('.').next()?.parse::<i64>().ok())
+                                .ok_or_else(|| invalid(format!("bad pax {key} record")))
+                        };
+                        match key.as_str() {
+                            "path" => path = value.clone(),
+                            "linkpath" => link = value.clone(),
+                            "size" => entry.size = number()? as u64,
+                            "uid" => entry.uid = number()? as u32,
+                            "gid" => entry.gid = number()? as u32,
+                            "mtime" => entry.mtime = number()?,
+                            _ => {
+                                if let Some(name) = key.strip_prefix("SCHILY.xattr.") {
+                                    entry.xattrs.push((name.to_string(), value));
+                                }
+                            }
+                        }
+                    }
+
+                    while kind == EntryKind::Dir && path.len() > 1 && path.ends_with(b"/") {
+                        path.pop();
+                    }
+                    entry.path = PathBuf::from(OsString::from_vec(path));
+                    entry.link = PathBuf::from(OsString::from_vec(link));
+                    // Only files have content, but skip whatever follows others
+                    self.start_content(entry.size);
+                    return Ok(Some(entry));
+                }
+            }
+        }
+    }
+
+    /// Read one header block, `None` at an end-of-archive marker or EOF
+    fn read_header(&mut self) -> io::Result<Option<[u8; BLOCK]>> {
+        let mut header = [0u8; BLOCK];
+        let mut filled = 0;
+        while filled < BLOCK {
+            match self.inner.read(&mut header[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                n => filled += n,
+            }
+        }
+        if header.iter().all(|&b| b == 0) {
+            return Ok(None);
+        }
+
+        let stored = parse_numeric(&header[148..156])?;
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum();
+        if stored != sum {
+            return Err(invalid("tar header checksum mismatch".into()));
+        }
+        Ok(Some(header))
+    }
+
+    fn start_content(&mut self, size: u64) {
+        self.remaining = size;
+        self.padding = (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64;
+    }
+
+    /// Read the content of a metadata entry into memory
+    fn read_content(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        if size > 1 << 20 {
+            return Err(invalid("tar metadata entry too large".into()));
+        }
+        self.start_content(size);
+        let mut content = Vec::with_capacity(size as usize);
+        self.read_to_end(&mut content)?;
+        if content.len() as u64 != size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(content)
+    }
+
+    /// Skip the unread content and padding of the current entry
+    fn skip_rest(&mut self) -> io::Result<()> {
+        let rest = self.remaining + self.padding;
+        let skipped = io::copy(&mut (&mut self.inner).take(rest), &mut io::sink())?;
+        self.remaining = 0;
+        self.padding = 0;
+        if skipped == rest {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::UnexpectedEof.into())
+        }
+    }
+}
+
+impl<R: Read> Read for TarReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if max == 0 {
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Bytes of `field` up to the first NUL
+fn trim_nul(field: &[u8]) -> &[u8] {
+    field.split(|&b| b == 0).next().unwrap_or_default()
+}
+
+/// Entry name, joined with the `ustar` prefix field
+fn header_path(header: &[u8; BLOCK]) -> Vec<u8> {
+    let name = trim_nul(&header[0..100]);
+    let prefix = trim_nul(&header[345..500]);
+    if &header[257..262] != b"ustar" || prefix.is_empty() {
+        return name.to_vec();
+    }
+    [prefix, b"/", name].concat()
+}
+
+/// Parse an octal or base-256 numeric field
+fn parse_numeric(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |n, &b| (n << 8) | u64::from(b)));
+    }
+    let text = std::str::from_utf8(trim_nul(field))
+        .map_err(|_| invalid("bad tar numeric field".into()))?
+        .trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid(format!("bad tar numeric field {text:?}")))
+}
+
+/// Parse `<length> <key>=<value>\n` records
+fn parse_pax(content: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut records = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() && rest[0] != 0 {
+        let bad = || invalid("bad pax record".into());
+        let space = rest.iter().position(|&b| b == b' ').ok_or_else(bad)?;
+        let len: usize = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .filter(|&l| l > space + 1 && l <= rest.len())
+            .ok_or_else(bad)?;
+        let record = &rest[space + 1..len - 1];
+        let eq = record.iter().position(|&b| b == b'=').ok_or_else(bad)?;
+        let key = String::from_utf8_lossy(&record[..eq]).into_owned();
+        records.push((key, record[eq + 1..].to_vec()));
+        rest = &rest[len..];
+    }
+    Ok(records)
+}
+
+// ============================================================================
+// Ownership
+// ============================================================================
+
+/// Translation between IDs in an archive and IDs on disk
+#[derive(Debug, Clone)]
+pub(crate) struct Owners {
+    uids: IdMap,
+    gids: IdMap,
+}
+
+impl Owners {
+    /// IDs on disk are the IDs of the archive, as on the host
+    pub(crate) fn identity() -> Self {
+        Self {
+            uids: IdMap::identity(),
+            gids: IdMap::identity(),
+        }
+    }
+
+    /// IDs of the archive are those inside a user namespace with these mappings
+    pub(crate) fn from_mappings(uid_map: IdMapping, gid_map: IdMapping) -> Self {
+        Self {
+            uids: IdMap::single(uid_map),
+            gids: IdMap::single(gid_map),
+        }
+    }
+
+    /// IDs of the archive are those inside the user namespace of `pid`
+    pub(crate) fn of_process(pid: u32) -> Self {
+        Self {
+            uids: IdMap::read(pid, "uid_map"),
+            gids: IdMap::read(pid, "gid_map"),
+        }
+    }
+
+    /// Archive IDs of a file owned by `uid`:`gid`
+    ///
+    /// Host IDs without a mapping appear as the overflow ID, as they do to
+    /// processes in the container.
+    fn to_archive(&self, uid: u32, gid: u32) -> (u32, u32) {
+        (
+            self.uids.to_inner(uid).unwrap_or(OVERFLOW_ID),
+            self.gids.to_inner(gid).unwrap_or(OVERFLOW_ID),
+        )
+    }
+
+    /// Owner on disk for archive IDs `uid`:`gid`
+    fn to_disk(&self, uid: u32, gid: u32) -> io::Result<(u32, u32)> {
+        let uid = self
+            .uids
+            .to_outer(uid)
+            .ok_or_else(|| invalid(format!("UID {uid} is not mapped in the container")))?;
+        let gid = self
+            .gids
+            .to_outer(gid)
+            .ok_or_else(|| invalid(format!("GID {gid} is not mapped in the container")))?;
+        Ok((uid, gid))
+    }
+}
+
+// ============================================================================
+// Packing
+// ============================================================================
+
+/// Appends files and directory trees to an archive
+///
+/// Trees are walked by descriptor: every entry is opened relative to its
+/// parent directory without following symbolic links, so a tree that is
+/// changed while it is read cannot lead the walk out of it. Symbolic links
+/// are archived as links. Files with several links are archived once and
+/// then as hard links to the first path. Sockets are skipped.
+pub(crate) struct Packer<'a> {
+    owners: &'a Owners,
+    /// Do not descend into directories on other filesystems
+    one_file_system: bool,
+    /// Archive path of multiply linked files, by device and inode
+    links: HashMap<(u64, u64), PathBuf>,
+}
+
+impl<'a> Packer<'a> {
+    pub(crate) fn new(owners: &'a Owners) -> Self {
+        Self {
+            owners,
+            one_file_system: false,
+            links: HashMap::new(),
+        }
+    }
+
+    /// Archive mount points below the source as empty directories
+    pub(crate) const fn one_file_system(mut self) -> Self {
+        self.one_file_system = true;
+        self
+    }
+
+    /// Append `source` of the directory `dir` as `name`, and for a directory
+    /// everything below it
+    ///
+    /// `source` is a single path component, or `.` for `dir` itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` or a file below it cannot be read.
+    pub(crate) fn pack<W: Write>(
+        &mut self,
+        tar: &mut TarWriter<W>,
+        dir: &File,
+        source: &OsStr,
+        name: &Path,
+    ) -> io::Result<()> {
+        let dev = open_at(dir, source, libc::O_PATH)?.metadata()?.dev();
+        self.pack_entry(tar, dir, source, name, dev)
+    }
+
+    fn pack_entry<W: Write>(
+        &mut self,
+        tar: &mut TarWriter<W>,
+        dir: &File,
+        source: &OsStr,
+        name: &Path,
+        root_dev: u64,
+    ) -> io::Result<()> {
+        // Everything below is read through this descriptor, never by path
+        let node = open_at(dir, source, libc::O_PATH)?;
+        let meta = node.metadata()?;
+        let file_type = meta.file_type();
+        let kind = if file_type.is_file() {
+            EntryKind::File
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_char_device() {
+            EntryKind::CharDevice
+        } else if file_type.is_block_device() {
+            EntryKind::BlockDevice
+        } else if file_type.is_fifo() {
+            EntryKind::Fifo
+        } else {
+            return Ok(());
+        };
+
+        let mut entry = Entry::new(name.to_path_buf(), kind);
+        entry.mode = meta.mode() & 0o7777;
+        (entry.uid, entry.gid) = self.owners.to_archive(meta.uid(), meta.gid());
+        entry.mtime = meta.mtime();
+
+        if kind != EntryKind::Dir && meta.nlink() > 1 {
+            if let Some(first) = self.links.get(&(meta.dev(), meta.ino())) {
+                entry.kind = EntryKind::Hardlink;
+                entry.link.clone_from(first);
+                return tar.append(&entry, &mut io::empty());
+            }
+            self.links
+                .insert((meta.dev(), meta.ino()), name.to_path_buf());
+        }
+        entry.xattrs = read_xattrs(&fd_path(dir).join(source))?;
+
+        match kind {
+            EntryKind::File => {
+                // Reopening through /proc keeps the inode checked above
+                let mut file = File::open(fd_path(&node))?;
+                entry.size = meta.len();
+                tar.append(&entry, &mut file)
+            }
+            EntryKind::Symlink => {
+                entry.link = read_link(&node)?;
+                tar.append(&entry, &mut io::empty())
+            }
+            EntryKind::CharDevice | EntryKind::BlockDevice => {
+                let rdev = meta.rdev();
+                entry.device = (libc::major(rdev), libc::minor(rdev));
+                tar.append(&entry, &mut io::empty())
+            }
+            EntryKind::Dir => {
+                tar.append(&entry, &mut io::empty())?;
+                if self.one_file_system && meta.dev() != root_dev {
+                    return Ok(());
+                }
+                let mut children = fs::read_dir(fd_path(&node))?
+                    .map(|child| child.map(|c| c.file_name()))
+                    .collect::<io::Result<Vec<_>>>()?;
+                children.sort();
+                for child in children {
+                    self.pack_entry(tar, &node, &child, &name.join(&child), root_dev)?;
+                }
+                Ok(())
+            }
+            EntryKind::Fifo | EntryKind::Hardlink => tar.append(&entry, &mut io::empty()),
+        }
+    }
+}
+
+/// Extended attributes of `path`, not following symlinks
+///
+/// Names that are not UTF-8 are skipped, as pax records cannot hold them.
+fn read_xattrs(path: &Path) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let path_c = cstring(path)?;
+    let mut names = vec![0u8; 1024];
+    let len = loop {
+        // SAFETY: path_c is NUL-terminated and names is a writable buffer of the given size.
+        let len =
+            unsafe { libc::llistxattr(path_c.as_ptr(), names.as_mut_ptr().cast(), names.len()) };
+        if len >= 0 {
+            break len as usize;
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ERANGE) => names.resize(names.len() * 4, 0),
+            Some(libc::ENOTSUP) => return Ok(Vec::new()),
+            _ => return Err(err),
+        }
+    };
+
+    let mut xattrs = Vec::new();
+    for name in names[..len].split(|&b| b == 0).filter(|n| !n.is_empty()) {
+        let Ok(name_str) = std::str::from_utf8(name) else {
+            continue;
+        };
+        let name_c = CString::new(name).map_err(|_| invalid("bad xattr name".into()))?;
+        let mut value = vec![0u8; 256];
+        loop {
+            // SAFETY: both strings are NUL-terminated and value is a writable buffer of the
+            // given size.
+            let len = unsafe {
+                libc::lgetxattr(
+                    path_c.as_ptr(),
+                    name_c.as_ptr(),
+                    value.as_mut_ptr().cast(),
+                    value.len(),
+                )
+            };
+            if len >= 0 {
+                value.truncate(len as usize);
+                break;
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ERANGE) {
+                return Err(err);
+            }
+            value.resize(value.len() * 4, 0);
+        }
+        xattrs.push((name_str.to_string(), value));
+    }
+    Ok(xattrs)
+}
+
+// ============================================================================
+// Unpacking
+// ============================================================================
+
+/// Extract every entry of `tar` below the directory `dest`
+///
+/// Entry paths are taken relative to `dest`; leading `/` are dropped and
+/// paths containing `..` are rejected. Every entry is created relative to a
+/// descriptor of its parent directory, which is reached from `dest` one
+/// component at a time without following symbolic links, so neither links
+/// from the archive nor links swapped in while it is extracted can redirect
+/// a write out of `dest`. Existing files are replaced, existing directories
+/// merged. Extended attributes are dropped where the filesystem does not
+/// support them.
+///
+/// # Errors
+///
+/// Returns an error for corrupt archives, unsafe paths, IDs without a
+/// mapping, or if a file cannot be written.
+pub(crate) fn unpack<R: Read>(
+    tar: &mut TarReader<R>,
+    dest: &File,
+    owners: &Owners,
+) -> io::Result<()> {
+    // Directory modes and times are applied last, so that read-only
+    // directories can be filled and their times are not touched again
+    let mut dirs = Vec::new();
+
+    while let Some(entry) = tar.next_entry()? {
+        let relative = sanitize(&entry.path)?;
+        let (parent, name) = match relative.file_name() {
+            Some(name) => (open_parent(dest, &relative, true)?, name),
+            // The root entry ("./") describes `dest` itself
+            None if entry.kind == EntryKind::Dir => (dest.try_clone()?, OsStr::new(".")),
+            None => return Err(invalid("archive root is not a directory".into())),
+        };
+
+        if entry.kind == EntryKind::Hardlink {
+            let original = sanitize(&entry.link)?;
+            let original_name = original
+                .file_name()
+                .ok_or_else(|| invalid("empty hard link target in archive".into()))?;
+            let original_parent = open_parent(dest, &original, true)?;
+            remove_non_dir(&parent, name)?;
+            link_at(&original_parent, original_name, &parent, name)?;
+            continue;
+        }
+
+        let (uid, gid) = owners.to_disk(entry.uid, entry.gid)?;
+        match entry.kind {
+            EntryKind::Dir => {
+                match open_at(&parent, name, libc::O_PATH).and_then(|node| node.metadata()) {
+                    Ok(meta) if meta.is_dir() => {}
+                    Ok(_) => {
+                        unlink_at(&parent, name)?;
+                        make_dir_at(&parent, name)?;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => make_dir_at(&parent, name)?,
+                    Err(e) => return Err(e),
+                }
+                chown_at(&parent, name, uid, gid)?;
+                write_xattrs(&fd_path(&parent).join(name), &entry.xattrs)?;
+                dirs.push((relative, entry.mode, entry.mtime));
+                continue;
+            }
+            EntryKind::File => {
+                remove_non_dir(&parent, name)?;
+                let mut file =
+                    open_at(&parent, name, libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL)?;
+                io::copy(tar, &mut file)?;
+            }
+            EntryKind::Symlink => {
+                remove_non_dir(&parent, name)?;
+                symlink_at(&entry.link, &parent, name)?;
+            }
+            EntryKind::CharDevice | EntryKind::BlockDevice | EntryKind::Fifo => {
+                remove_non_dir(&parent, name)?;
+                let file_type = match entry.kind {
+                    EntryKind::CharDevice => libc::S_IFCHR,
+                    EntryKind::BlockDevice => libc::S_IFBLK,
+                    _ => libc::S_IFIFO,
+                };
+                let (major, minor) = entry.device;
+                mknod_at(
+                    &parent,
+                    name,
+                    file_type | 0o600,
+                    libc::makedev(major, minor),
+                )?;
+            }
+            EntryKind::Hardlink => unreachable!("handled above"),
+        }
+
+        // Ownership first: chown(2) clears setuid bits and file capabilities
+        chown_at(&parent, name, uid, gid)?;
+        if entry.kind != EntryKind::Symlink {
+            chmod_at(&parent, name, entry.mode)?;
+        }
+        write_xattrs(&fd_path(&parent).join(name), &entry.xattrs)?;
+        set_mtime_at(&parent, name, entry.mtime)?;
+    }
+
+    for (relative, mode, mtime) in dirs.into_iter().rev() {
+        let parent = open_parent(dest, &relative, false)?;
+        let name = relative.file_name().unwrap_or(OsStr::new("."));
+        chmod_at(&parent, name, mode)?;
+        set_mtime_at(&parent, name, mtime)?;
+    }
+    Ok(())
+}
+
+/// Set extended attributes of `path`, not following symlinks
+///
+/// Used with `fd_path()` of the parent directory, so only the last
+/// component is looked up by name.
+fn write_xattrs(path: &Path, xattrs: &[(String, Vec<u8>)]) -> io::Result<()> {
+    if xattrs.is_empty() {
+        return Ok(());
+    }
+    let path_c = cstring(path)?;
+    for (name, value) in xattrs {
+        let name_c = CString::new(name.as_str()).map_err(|_| invalid("bad xattr name".into()))?;
+        // SAFETY: both strings are NUL-terminated and value is valid for value.len() bytes.
+        let ret = unsafe {
+            libc::lsetxattr(
+                path_c.as_ptr(),
+                name_c.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ENOTSUP) {
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!("set {name} on {}: {err}", path.display()),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+}
+
+/// `path` without leading `/` and `.` components
+///
+/// # Errors
+///
+/// Returns `InvalidData` if `path` contains `..`.
+fn sanitize(path: &Path) -> io::Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(invalid(format!(
+                    "unsafe path in archive: {}",
+                    path.display()
+                )));
+            }
+        }
+    }
+    Ok(relative)
+}
+
+/// Open the parent directory of `relative` below `root`
+///
+/// Each component is opened relative to the previous one and must be a
+/// directory, not a symbolic link. With `create`, missing directories are
+/// created.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if a parent is a symbolic link or not a directory.
+fn open_parent(root: &File, relative: &Path, create: bool) -> io::Result<File> {
+    let mut dir = root.try_clone()?;
+    let mut walked = PathBuf::new();
+    let parents = relative
+        .parent()
+        .map(Path::components)
+        .into_iter()
+        .flatten();
+    for component in parents {
+        let name = component.as_os_str();
+        walked.push(name);
+        let flags = libc::O_PATH | libc::O_DIRECTORY;
+        dir = match open_at(&dir, name, flags) {
+            Ok(next) => next,
+            Err(e) if create && e.kind() == io::ErrorKind::NotFound => {
+                make_dir_at(&dir, name)?;
+                open_at(&dir, name, flags)?
+            }
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ENOTDIR | libc::ELOOP)) => {
+                return Err(invalid(format!(
+                    "cannot extract below {}: not a directory",
+                    walked.display()
+                )));
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    Ok(dir)
+}
+
+/// Remove whatever but a directory is at `name` in `dir`
+fn remove_non_dir(dir: &File, name: &OsStr) -> io::Result<()> {
+    match open_at(dir, name, libc::O_PATH).and_then(|node| node.metadata()) {
+        Ok(meta) if meta.is_dir() => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("cannot replace directory {}", Path::new(name).display()),
+        )),
+        Ok(_) => unlink_at(dir, name),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+// ============================================================================
+// Descriptor-Relative Calls
+// ============================================================================
+
+/// Path through which `file` can be used with path-based calls
+pub(crate) fn fd_path(file: &File) -> PathBuf {
+    PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+}
+
+/// `name` as a C string; `name` must be a single path component
+fn cname(name: &OsStr) -> io::Result<CString> {
+    cstring(Path::new(name))
+}
+
+/// Map a `-1` return value to the current `errno`
+fn cvt(ret: libc::c_int) -> io::Result<()> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Open `name` in `dir` without following a symbolic link
+///
+/// With `O_PATH`, a symbolic link is opened itself; with other flags it
+/// fails with `ELOOP`. Files created with `O_CREAT` get mode `0600`.
+fn open_at(dir: &File, name: &OsStr, flags: libc::c_int) -> io::Result<File> {
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and name a valid NUL-terminated string.
+    let fd = unsafe {
+        libc::openat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            flags | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            0o600 as libc::c_uint,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openat(2) returned a new descriptor owned by nobody else.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// Target of the symbolic link opened as `link` with `O_PATH`
+fn read_link(link: &File) -> io::Result<PathBuf> {
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+    // SAFETY: the empty path is NUL-terminated and buf is writable for its length.
+    let len = unsafe {
+        libc::readlinkat(
+            link.as_raw_fd(),
+            c"".as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(len as usize);
+    Ok(PathBuf::from(OsString::from_vec(buf)))
+}
+
+fn make_dir_at(dir: &File, name: &OsStr) -> io::Result<()> {
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and name a valid NUL-terminated string.
+    cvt(unsafe { libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), 0o700) })
+}
+
+fn unlink_at(dir: &File, name: &OsStr) -> io::Result<()> {
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and name a valid NUL-terminated string.
+    cvt(unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), 0) })
+}
+
+fn symlink_at(target: &Path, dir: &File, name: &OsStr) -> io::Result<()> {
+    let target = cstring(target)?;
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and both strings are NUL-terminated.
+    cvt(unsafe { libc::symlinkat(target.as_ptr(), dir.as_raw_fd(), name.as_ptr()) })
+}
+
+/// Hard link `original` in `original_dir` as `name` in `dir`
+///
+/// A symbolic link `original` is linked itself, not its target.
+fn link_at(original_dir: &File, original: &OsStr, dir: &File, name: &OsStr) -> io::Result<()> {
+    let original = cname(original)?;
+    let name = cname(name)?;
+    // SAFETY: both directories are open descriptors and both names NUL-terminated.
+    cvt(unsafe {
+        libc::linkat(
+            original_dir.as_raw_fd(),
+            original.as_ptr(),
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            0,
+        )
+    })
+}
+
+fn mknod_at(dir: &File, name: &OsStr, mode: libc::mode_t, dev: libc::dev_t) -> io::Result<()> {
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and name a valid NUL-terminated string.
+    cvt(unsafe { libc::mknodat(dir.as_raw_fd(), name.as_ptr(), mode, dev) })
+}
+
+/// Change the owner of `name` in `dir`, not following symlinks
+fn chown_at(dir: &File, name: &OsStr, uid: u32, gid: u32) -> io::Result<()> {
+    let name = cname(name)?;
+    // SAFETY: dir is an open descriptor and name a valid NUL-terminated string.
+    cvt(unsafe {
+        libc::fchownat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            uid,
+            gid,
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    })
+}
+
+/// Change the mode of `name` in `dir`, which must not be a symbolic link
+///
+/// `fchmodat(2)` always follows symbolic links before Linux 6.6, so the
+/// entry is pinned with an `O_PATH` descriptor and changed through it.
+fn chmod_at(dir: &File, name: &OsStr, mode: u32) -> io::Result<()> {
+    let node = open_at(dir, name, libc::O_PATH)?;
+    if node.metadata()?.file_type().is_symlink() {
+        return Err(invalid(format!(
+            "cannot change mode of symbolic link {}",
+            Path::new(name).display()
+        )));
+    }
+    fs::set_permissions(fd_path(&node), fs::Permissions::from_mode(mode))
+}
+
+/// Set access and modification time of `name` in `dir`, not following symlinks
+fn set_mtime_at(dir: &File, name: &OsStr, mtime: i64) -> io::Result<()> {
+    let name = cname(name)?;
+    let time = libc::timespec {
+        tv_sec: mtime as libc::time_t,
+        tv_nsec: 0,
+    };
+    // SAFETY: dir is an open descriptor, name a valid NUL-terminated string and the times
+    // array has two entries.
+    cvt(unsafe {
+        libc::utimensat(
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            [time, time].as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    })
+}
+
+// ============================================================================
+// Copying
+// ============================================================================
+
+/// Copy `source` of `source_dir` to `name` in `dest_dir` as a tar stream
+///
+/// The archive is written on a separate thread and extracted as it arrives,
+/// so trees of any size are copied without buffering them.
+///
+/// # Errors
+///
+/// Returns the first error of packing or unpacking.
+pub(crate) fn copy_tree(
+    source_dir: &File,
+    source: &OsStr,
+    source_owners: &Owners,
+    dest_dir: &File,
+    name: &OsStr,
+    dest_owners: &Owners,
+) -> io::Result<()> {
+    let (reader, writer) = io::pipe()?;
+    std::thread::scope(|scope| {
+        let packer = scope.spawn(move || {
+            let mut tar = TarWriter::new(writer);
+            Packer::new(source_owners).pack(&mut tar, source_dir, source, Path::new(name))?;
+            tar.finish().map(drop)
+        });
+        let unpacked = unpack(&mut TarReader::new(reader), dest_dir, dest_owners);
+        let packed = packer
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("archive writer panicked")));
+        match (unpacked, packed) {
+            // The writer only fails because extraction stopped reading
+            (Err(e), Err(p)) if p.kind() == io::ErrorKind::BrokenPipe => Err(e),
+            (unpacked, packed) => packed.and(unpacked),
+        }
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "alice-container-archive-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Owner of the files the tests create
+    fn own_owners() -> (u32, u32) {
+        // SAFETY: geteuid(2) and getegid(2) cannot fail.
+        unsafe { (libc::geteuid(), libc::getegid()) }
+    }
+
+    #[test]
+    fn test_round_trip_with_pax_records() {
+        let long_path = PathBuf::from(format!("{}/file.txt", "d".repeat(150)));
+        let mut file = Entry::new(long_path.clone(), EntryKind::File);
+        file.mode = 0o4755;
+        file.uid = 3_000_000;
+        file.gid = 100;
+        file.mtime = 1_700_000_000;
+        file.size = 5;
+        let mut link = Entry::new("link".into(), EntryKind::Symlink);
+        link.link = PathBuf::from(format!("/{}", "t".repeat(120)));
+        let dir = Entry::new("dir".into(), EntryKind::Dir);
+
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append(&dir, &mut io::empty()).unwrap();
+        tar.append(&file, &mut &b"hello"[..]).unwrap();
+        tar.append(&link, &mut io::empty()).unwrap();
+        let bytes = tar.finish().unwrap();
+        assert_eq!(bytes.len() % BLOCK, 0);
+
+        let mut reader = TarReader::new(&bytes[..]);
+        assert_eq!(reader.next_entry().unwrap(), Some(dir));
+        assert_eq!(reader.next_entry().unwrap(), Some(file));
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "hello");
+        assert_eq!(reader.next_entry().unwrap(), Some(link));
+        assert_eq!(reader.next_entry().unwrap(), None);
+    }
+
+    #[test]
+    fn test_short_content_is_padded_and_skipped() {
+        let mut file = Entry::new("a".into(), EntryKind::File);
+        file.size = 1000;
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append(&file, &mut &b"abc"[..]).unwrap();
+        tar.append(&Entry::new("b".into(), EntryKind::Dir), &mut io::empty())
+            .unwrap();
+        let bytes = tar.finish().unwrap();
+
+        let mut reader = TarReader::new(&bytes[..]);
+        assert_eq!(reader.next_entry().unwrap().unwrap().size, 1000);
+        // Content left unread is skipped
+        let next = reader.next_entry().unwrap().unwrap();
+        assert_eq!(next.path, Path::new("b"));
+    }
+
+    #[test]
+    fn test_corrupt_header_is_rejected() {
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append(&Entry::new("a".into(), EntryKind::Dir), &mut io::empty())
+            .unwrap();
+        let mut bytes = tar.finish().unwrap();
+        bytes[0] = b'b';
+        let err = TarReader::new(&bytes[..]).next_entry().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_numeric() {
+        assert_eq!(parse_numeric(b"0000644\0").unwrap(), 0o644);
+        assert_eq!(parse_numeric(b"  755 \0").unwrap(), 0o755);
+        assert_eq!(parse_numeric(b"\0\0\0").unwrap(), 0);
+        assert_eq!(parse_numeric(&[0x80, 0, 0, 1, 0]).unwrap(), 256);
+        assert!(parse_numeric(b"9\0").is_err());
+    }
+
+    #[test]
+    fn test_copy_tree_preserves_metadata() {
+        let src = temp_dir("copy-src");
+        let dest = temp_dir("copy-dest");
+        fs::create_dir_all(src.join("tree/sub")).unwrap();
+        fs::write(src.join("tree/sub/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(
+            src.join("tree/sub/run.sh"),
+            fs::Permissions::from_mode(0o750),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("sub/run.sh", src.join("tree/start")).unwrap();
+        fs::set_permissions(src.join("tree/sub"), fs::Permissions::from_mode(0o700)).unwrap();
+        let sub = File::open(src.join("tree/sub")).unwrap();
+        set_mtime_at(&sub, OsStr::new("run.sh"), 1_600_000_000).unwrap();
+
+        let owners = Owners::identity();
+        copy_tree(
+            &File::open(&src).unwrap(),
+            OsStr::new("tree"),
+            &owners,
+            &File::open(&dest).unwrap(),
+            OsStr::new("copy"),
+            &owners,
+        )
+        .unwrap();
+
+        let script = fs::metadata(dest.join("copy/sub/run.sh")).unwrap();
+        let sub = fs::metadata(dest.join("copy/sub")).unwrap();
+        let link = fs::read_link(dest.join("copy/start")).unwrap();
+        let content = fs::read_to_string(dest.join("copy/start")).unwrap();
+        let _ = fs::remove_dir_all(&src);
+        let _ = fs::remove_dir_all(&dest);
+
+        assert_eq!(script.mode() & 0o7777, 0o750);
+        assert_eq!(script.mtime(), 1_600_000_000);
+        assert_eq!((script.uid(), script.gid()), own_owners());
+        assert_eq!(sub.mode() & 0o7777, 0o700);
+        assert_eq!(link, Path::new("sub/run.sh"));
+        assert_eq!(content, "#!/bin/sh\n");
+    }
+
+    #[test]
+    fn test_copy_tree_reports_missing_source() {
+        let dest = temp_dir("copy-missing");
+        let owners = Owners::identity();
+        let dir = File::open(&dest).unwrap();
+        let err = copy_tree(
+            &dir,
+            OsStr::new("nope"),
+            &owners,
+            &dir,
+            OsStr::new("nope"),
+            &owners,
+        )
+        .unwrap_err();
+        let _ = fs::remove_dir_all(&dest);
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_unpack_rejects_escapes() {
+        let dest = temp_dir("unpack-escape");
+        let dest_dir = File::open(&dest).unwrap();
+        let (uid, gid) = own_owners();
+        let owned = |mut entry: Entry| {
+            entry.uid = uid;
+            entry.gid = gid;
+            entry
+        };
+
+        // `..` in a path
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append(
+            &owned(Entry::new("../evil".into(), EntryKind::Dir)),
+            &mut io::empty(),
+        )
+        .unwrap();
+        let bytes = tar.finish().unwrap();
+        let err = unpack(
+            &mut TarReader::new(&bytes[..]),
+            &dest_dir,
+            &Owners::identity(),
+        );
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // A file written through a symlink from the same archive
+        let mut link = owned(Entry::new("escape".into(), EntryKind::Symlink));
+        link.link = std::env::temp_dir();
+        let mut file = owned(Entry::new("escape/evil".into(), EntryKind::File));
+        file.size = 4;
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append(&link, &mut io::empty()).unwrap();
+        tar.append(&file, &mut &b"evil"[..]).unwrap();
+        let bytes = tar.finish().unwrap();
+        let err = unpack(
+            &mut TarReader::new(&bytes[..]),
+            &dest_dir,
+            &Owners::identity(),
+        );
+        let escaped = std::env::temp_dir().join("evil").exists();
+        let _ = fs::remove_dir_all(&dest);
+
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(!escaped);
+    }
+
+    #[test]
+    fn test_unpack_ignores_symlinks_in_destination() {
+        let dest = temp_dir("unpack-swapped");
+        let outside = temp_dir("unpack-outside");
+        fs::write(outside.join("secret"), "host").unwrap();
+        fs::set_permissions(&outside, fs::Permissions::from_mode(0o755)).unwrap();
+        // Links a process in the container could have put there
+        std::os::unix::fs::symlink(&outside, dest.join("abs")).unwrap();
+        std::os::unix::fs::symlink("../outside", dest.join("rel")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret"), dest.join("secret")).unwrap();
+
+        let (uid, gid) = own_owners();
+        let archive = |entries: Vec<Entry>| {
+            let mut tar = TarWriter::new(Vec::new());
+            for mut entry in entries {
+                entry.uid = uid;
+                entry.gid = gid;
+                let content = vec![b'x'; entry.size as usize];
+                tar.append(&entry, &mut &content[..]).unwrap();
+            }
+            tar.finish().unwrap()
+        };
+        let file = |path: &str, mode| {
+            let mut entry = Entry::new(path.into(), EntryKind::File);
+            entry.mode = mode;
+            entry.size = 1;
+            entry
+        };
+        let mut dir = Entry::new("abs".into(), EntryKind::Dir);
+        dir.mode = 0o777;
+        let mut link = Entry::new("copy".into(), EntryKind::Hardlink);
+        link.link = PathBuf::from("abs/secret");
+
+        let dest_dir = File::open(&dest).unwrap();
+        let owners = Owners::identity();
+        let unpack_all = |entries| {
+            let bytes = archive(entries);
+            unpack(&mut TarReader::new(&bytes[..]), &dest_dir, &owners)
+        };
+        let below_abs = unpack_all(vec![file("abs/evil", 0o644)]);
+        let below_rel = unpack_all(vec![file("rel/evil", 0o644)]);
+        let through_link = unpack_all(vec![link]);
+        // Entries named like a link replace the link itself
+        let replaced = unpack_all(vec![file("secret", 0o777), dir]);
+
+        let entries: Vec<_> = fs::read_dir(&outside)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        let secret = fs::read_to_string(outside.join("secret")).unwrap();
+        let secret_mode = fs::metadata(outside.join("secret")).unwrap().mode() & 0o7777;
+        let outside_mode = fs::metadata(&outside).unwrap().mode() & 0o7777;
+        let abs = fs::symlink_metadata(dest.join("abs")).unwrap();
+        let copied = fs::read_to_string(dest.join("secret"));
+        let _ = fs::remove_dir_all(&dest);
+        let _ = fs::remove_dir_all(&outside);
+
+        for err in [below_abs, below_rel, through_link] {
+            assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+        replaced.unwrap();
+        assert_eq!(entries, [OsString::from("secret")]);
+        assert_eq!(secret, "host");
+        assert_ne!(secret_mode, 0o777);
+        assert_eq!(outside_mode, 0o755);
+        assert!(abs.is_dir());
+        assert_eq!(copied.unwrap(), "x");
+    }
+
+    #[test]
+    fn test_pack_does_not_follow_symlinks() {
+        let src = temp_dir("pack-links");
+        fs::create_dir(src.join("data")).unwrap();
+        std::os::unix::fs::symlink("/", src.join("data/up")).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", src.join("data/host")).unwrap();
+
+        let owners = Owners::identity();
+        let mut tar = TarWriter::new(Vec::new());
+        Packer::new(&owners)
+            .pack(
+                &mut tar,
+                &File::open(&src).unwrap(),
+                OsStr::new("data"),
+                Path::new("data"),
+            )
+            .unwrap();
+        let bytes = tar.finish().unwrap();
+        let _ = fs::remove_dir_all(&src);
+
+        let mut reader = TarReader::new(&bytes[..]);
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().unwrap() {
+            entries.push((entry.path, entry.kind, entry.link));
+        }
+        assert_eq!(
+            entries,
+            [
+                ("data".into(), EntryKind::Dir, PathBuf::new()),
+                (
+                    "data/host".into(),
+                    EntryKind::Symlink,
+                    "/etc/hostname".into()
+                ),
+                ("data/up".into(), EntryKind::Symlink, "/".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_owners_translation() {
+        let owners = Owners {
+            uids: IdMap::parse("0 100000 65536\n"),
+            gids: IdMap::parse("0 200000 65536\n"),
+        };
+        assert_eq!(owners.to_archive(101_000, 200_050), (1000, 50));
+        assert_eq!(owners.to_archive(0, 0), (OVERFLOW_ID, OVERFLOW_ID));
+        assert_eq!(owners.to_disk(1000, 50).unwrap(), (101_000, 200_050));
+        assert!(owners.to_disk(70_000, 0).is_err());
+    }
+}
@@ -0,0 +1,571 @@
+//! Linux Capabilities
+//!
+//! Capability sets of the container init process and of commands run with
+//! `Container::exec()`.
+//!
+//! ## Sets
+//!
+//! | Set | Meaning |
+//! |-----|---------|
+//! | bounding | upper limit for capabilities gained on `execve(2)` |
+//! | effective | capabilities checked by the kernel |
+//! | permitted | capabilities the process may make effective |
+//! | inheritable | capabilities preserved across `execve(2)` with file capabilities |
+//! | ambient | capabilities preserved across `execve(2)` of unprivileged programs |
+//!
+//! ## Application
+//!
+//! ```text
+//! PR_CAPBSET_DROP (every capability outside `bounding`)
+//!   → setresgid / setresuid with PR_SET_KEEPCAPS
+//!   → capset(effective, permitted, inheritable)
+//!   → PR_CAP_AMBIENT_RAISE (each capability in `ambient`)
+//!   → execve
+//! ```
+//!
+//! The default mirrors Docker: 14 capabilities in the bounding, effective
+//! and permitted sets, no inheritable or ambient capabilities.
+
+use core::fmt;
+
+#[cfg(target_os = "linux")]
+use crate::container::ContainerError;
+
+// ============================================================================
+// Capability
+// ============================================================================
+
+macro_rules! capabilities {
+    ($($(#[$doc:meta])* $variant:ident = $value:literal, $name:literal;)*) => {
+        /// A Linux capability
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[repr(u8)]
+        pub enum Capability {
+            $($(#[$doc])* $variant = $value,)*
+        }
+
+        impl Capability {
+            /// Every capability known to this crate, by number
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// Name as used by OCI and `capabilities(7)`, e.g. `CAP_NET_ADMIN`
+            #[must_use]
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+capabilities! {
+    /// Change file ownership
+    Chown = 0, "CAP_CHOWN";
+    /// Bypass file permission checks
+    DacOverride = 1, "CAP_DAC_OVERRIDE";
+    /// Bypass file read and directory search checks
+    DacReadSearch = 2, "CAP_DAC_READ_SEARCH";
+    /// Bypass checks requiring the file owner
+    Fowner = 3, "CAP_FOWNER";
+    /// Keep set-user-ID and set-group-ID bits on modification
+    Fsetid = 4, "CAP_FSETID";
+    /// Send signals to any process
+    Kill = 5, "CAP_KILL";
+    /// Change group IDs
+    Setgid = 6, "CAP_SETGID";
+    /// Change user IDs
+    Setuid = 7, "CAP_SETUID";
+    /// Modify capability sets
+    Setpcap = 8, "CAP_SETPCAP";
+    /// Set immutable and append-only file attributes
+    LinuxImmutable = 9, "CAP_LINUX_IMMUTABLE";
+    /// Bind to ports below 1024
+    NetBindService = 10, "CAP_NET_BIND_SERVICE";
+    /// Broadcast and listen to multicast
+    NetBroadcast = 11, "CAP_NET_BROADCAST";
+    /// Configure network interfaces, routes and firewalls
+    NetAdmin = 12, "CAP_NET_ADMIN";
+    /// Use raw and packet sockets
+    NetRaw = 13, "CAP_NET_RAW";
+    /// Lock memory
+    IpcLock = 14, "CAP_IPC_LOCK";
+    /// Bypass IPC permission checks
+    IpcOwner = 15, "CAP_IPC_OWNER";
+    /// Load kernel modules
+    SysModule = 16, "CAP_SYS_MODULE";
+    /// Perform raw I/O
+    SysRawio = 17, "CAP_SYS_RAWIO";
+    /// Use `chroot(2)`
+    SysChroot = 18, "CAP_SYS_CHROOT";
+    /// Trace any process
+    SysPtrace = 19, "CAP_SYS_PTRACE";
+    /// Configure process accounting
+    SysPacct = 20, "CAP_SYS_PACCT";
+    /// Perform system administration
+    SysAdmin = 21, "CAP_SYS_ADMIN";
+    /// Reboot the system
+    SysBoot = 22, "CAP_SYS_BOOT";
+    /// Raise priorities and change scheduling
+    SysNice = 23, "CAP_SYS_NICE";
+    /// Override resource limits
+    SysResource = 24, "CAP_SYS_RESOURCE";
+    /// Set the system clock
+    SysTime = 25, "CAP_SYS_TIME";
+    /// Configure terminals
+    SysTtyConfig = 26, "CAP_SYS_TTY_CONFIG";
+    /// Create device nodes
+    Mknod = 27, "CAP_MKNOD";
+    /// Take file leases
+    Lease = 28, "CAP_LEASE";
+    /// Write to the kernel audit log
+    AuditWrite = 29, "CAP_AUDIT_WRITE";
+    /// Configure kernel auditing
+    AuditControl = 30, "CAP_AUDIT_CONTROL";
+    /// Set file capabilities
+    Setfcap = 31, "CAP_SETFCAP";
+    /// Override mandatory access control
+    MacOverride = 32, "CAP_MAC_OVERRIDE";
+    /// Configure mandatory access control
+    MacAdmin = 33, "CAP_MAC_ADMIN";
+    /// Configure the kernel log
+    Syslog = 34, "CAP_SYSLOG";
+    /// Trigger wake-up alarms
+    WakeAlarm = 35, "CAP_WAKE_ALARM";
+    /// Block system suspend
+    BlockSuspend = 36, "CAP_BLOCK_SUSPEND";
+    /// Read the kernel audit log
+    AuditRead = 37, "CAP_AUDIT_READ";
+    /// Use performance monitoring
+    Perfmon = 38, "CAP_PERFMON";
+    /// Use BPF
+    Bpf = 39, "CAP_BPF";
+    /// Checkpoint and restore processes
+    CheckpointRestore = 40, "CAP_CHECKPOINT_RESTORE";
+}
+
+impl Capability {
+    /// Look up a capability by name
+    ///
+    /// Accepts `CAP_NET_ADMIN` as well as `NET_ADMIN` or `net_admin`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("CAP_"))
+            .map_or(name, |_| &name[4..]);
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|cap| cap.name()[4..].eq_ignore_ascii_case(name))
+    }
+
+    /// Capability number
+    #[must_use]
+    pub const fn number(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// ============================================================================
+// Capability Set
+// ============================================================================
+
+/// Set of capabilities as a bit mask
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// No capabilities
+    pub const EMPTY: Self = Self(0);
+
+    /// Set containing `caps`
+    #[must_use]
+    pub const fn from_slice(caps: &[Capability]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < caps.len() {
+            bits |= 1 << caps[i] as u8;
+            i += 1;
+        }
+        Self(bits)
+    }
+
+    /// Every capability known to this crate
+    #[must_use]
+    pub const fn all() -> Self {
+        Self::from_slice(Capability::ALL)
+    }
+
+    /// Set from a raw mask, ignoring unknown capability numbers
+    #[must_use]
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits & Self::all().0)
+    }
+
+    /// Raw mask, bit `n` standing for capability number `n`
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Whether `cap` is in the set
+    #[must_use]
+    pub const fn contains(self, cap: Capability) -> bool {
+        self.0 & (1 << cap as u8) != 0
+    }
+
+    /// Whether the set is empty
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Add a capability
+    pub fn insert(&mut self, cap: Capability) {
+        self.0 |= 1 << cap as u8;
+    }
+
+    /// Remove a capability
+    pub fn remove(&mut self, cap: Capability) {
+        self.0 &= !(1 << cap as u8);
+    }
+
+    /// Capabilities in the set, by number
+    pub fn iter(self) -> impl Iterator<Item = Capability> {
+        Capability::ALL
+            .iter()
+            .copied()
+            .filter(move |&cap| self.contains(cap))
+    }
+}
+
+impl FromIterator<Capability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        let mut set = Self::EMPTY;
+        for cap in iter {
+            set.insert(cap);
+        }
+        set
+    }
+}
+
+// ============================================================================
+// Process Capabilities
+// ============================================================================
+
+/// Capabilities granted by Docker by default
+pub const DEFAULT_CAPABILITIES: &[Capability] = &[
+    Capability::Chown,
+    Capability::DacOverride,
+    Capability::Fsetid,
+    Capability::Fowner,
+    Capability::Mknod,
+    Capability::NetRaw,
+    Capability::Setgid,
+    Capability::Setuid,
+    Capability::Setfcap,
+    Capability::Setpcap,
+    Capability::NetBindService,
+    Capability::SysChroot,
+    Capability::Kill,
+    Capability::AuditWrite,
+];
+
+/// Capability sets of a container process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Upper limit for capabilities gained on `execve(2)`
+    pub bounding: CapabilitySet,
+    /// Capabilities checked by the kernel
+    pub effective: CapabilitySet,
+    /// Capabilities the process may make effective
+    pub permitted: CapabilitySet,
+    /// Capabilities preserved across `execve(2)` with file capabilities
+    pub inheritable: CapabilitySet,
+    /// Capabilities preserved across `execve(2)` of unprivileged programs
+    pub ambient: CapabilitySet,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::from_set(CapabilitySet::from_slice(DEFAULT_CAPABILITIES))
+    }
+}
+
+impl Capabilities {
+    /// `set` as bounding, effective and permitted capabilities
+    #[must_use]
+    pub const fn from_set(set: CapabilitySet) -> Self {
+        Self {
+            bounding: set,
+            effective: set,
+            permitted: set,
+            inheritable: CapabilitySet::EMPTY,
+            ambient: CapabilitySet::EMPTY,
+        }
+    }
+
+    /// Every capability (privileged container)
+    #[must_use]
+    pub const fn all() -> Self {
+        Self::from_set(CapabilitySet::all())
+    }
+
+    /// No capabilities at all
+    #[must_use]
+    pub const fn none() -> Self {
+        Self::from_set(CapabilitySet::EMPTY)
+    }
+
+    /// Grant `cap` in the bounding, effective and permitted sets
+    #[must_use]
+    pub fn with(mut self, cap: Capability) -> Self {
+        self.bounding.insert(cap);
+        self.effective.insert(cap);
+        self.permitted.insert(cap);
+        self
+    }
+
+    /// Remove `cap` from every set
+    #[must_use]
+    pub fn without(mut self, cap: Capability) -> Self {
+        for set in [
+            &mut self.bounding,
+            &mut self.effective,
+            &mut self.permitted,
+            &mut self.inheritable,
+            &mut self.ambient,
+        ] {
+            set.remove(cap);
+        }
+        self
+    }
+
+    /// Keep `cap` across `execve(2)` of non-root programs
+    ///
+    /// Ambient capabilities must also be permitted and inheritable.
+    #[must_use]
+    pub fn with_ambient(mut self, cap: Capability) -> Self {
+        self = self.with(cap);
+        self.inheritable.insert(cap);
+        self.ambient.insert(cap);
+        self
+    }
+
+    /// Drop every capability outside the bounding set
+    ///
+    /// Must run before switching to a non-root user, while `CAP_SETPCAP`
+    /// is still effective.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `prctl(PR_CAPBSET_DROP)` fails.
+    #[cfg(target_os = "linux")]
+    pub fn drop_bounding(&self) -> Result<(), ContainerError> {
+        for number in 0..64u8 {
+            if self.bounding.0 & (1 << number) != 0 {
+                continue;
+            }
+            // SAFETY: PR_CAPBSET_DROP takes a capability number and no pointers.
+            if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, libc::c_ulong::from(number)) } < 0 {
+                match last_errno() {
+                    // Past the last capability of this kernel
+                    libc::EINVAL => break,
+                    errno => {
+                        return Err(ContainerError::ProcessError(format!(
+                            "prctl(PR_CAPBSET_DROP, {number}): errno {errno}"
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the effective, permitted, inheritable and ambient sets
+    ///
+    /// Runs after the user switch, which must have kept the permitted set
+    /// with `PR_SET_KEEPCAPS`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `capset(2)` or `prctl(PR_CAP_AMBIENT)` fails,
+    /// e.g. when asking for capabilities the process does not have.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self) -> Result<(), ContainerError> {
+        let header = CapHeader {
+            version: LINUX_CAPABILITY_VERSION_3,
+            pid: 0,
+        };
+        let split = |set: CapabilitySet| [set.0 as u32, (set.0 >> 32) as u32];
+        let (effective, permitted, inheritable) = (
+            split(self.effective),
+            split(self.permitted),
+            split(self.inheritable),
+        );
+        let data = [0, 1].map(|i| CapData {
+            effective: effective[i],
+            permitted: permitted[i],
+            inheritable: inheritable[i],
+        });
+
+        // SAFETY: header and data are valid for the duration of the call; version 3 expects
+        // an array of two `CapData` entries.
+        if unsafe { libc::syscall(libc::SYS_capset, &header, data.as_ptr()) } < 0 {
+            return Err(ContainerError::ProcessError(format!(
+                "capset: errno {}",
+                last_errno()
+            )));
+        }
+
+        // SAFETY: PR_CAP_AMBIENT takes integer arguments only.
+        let cleared = unsafe {
+            libc::prctl(
+                libc::PR_CAP_AMBIENT,
+                libc::PR_CAP_AMBIENT_CLEAR_ALL as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+            )
+        };
+        // EINVAL: the kernel predates ambient capabilities (< 4.3)
+        if cleared < 0 && (!self.ambient.is_empty() || last_errno() != libc::EINVAL) {
+            return Err(ContainerError::ProcessError(format!(
+                "prctl(PR_CAP_AMBIENT_CLEAR_ALL): errno {}",
+                last_errno()
+            )));
+        }
+
+        for cap in self.ambient.iter() {
+            // SAFETY: see above.
+            let ret = unsafe {
+                libc::prctl(
+                    libc::PR_CAP_AMBIENT,
+                    libc::PR_CAP_AMBIENT_RAISE as libc::c_ulong,
+                    libc::c_ulong::from(cap.number()),
+                    0 as libc::c_ulong,
+                    0 as libc::c_ulong,
+                )
+            };
+            if ret < 0 {
+                return Err(ContainerError::ProcessError(format!(
+                    "prctl(PR_CAP_AMBIENT_RAISE, {cap}): errno {}",
+                    last_errno()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `_LINUX_CAPABILITY_VERSION_3`, 64-bit capability sets
+#[cfg(target_os = "linux")]
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+/// `struct __user_cap_header_struct`
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+/// `struct __user_cap_data_struct`
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+#[cfg(target_os = "linux")]
+fn last_errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_names() {
+        assert_eq!(Capability::ALL.len(), 41);
+        for (number, cap) in Capability::ALL.iter().enumerate() {
+            assert_eq!(usize::from(cap.number()), number);
+            assert_eq!(Capability::from_name(cap.name()), Some(*cap));
+        }
+        assert_eq!(Capability::NetAdmin.to_string(), "CAP_NET_ADMIN");
+        assert_eq!(
+            Capability::from_name("net_admin"),
+            Some(Capability::NetAdmin)
+        );
+        assert_eq!(
+            Capability::from_name("SYS_ADMIN"),
+            Some(Capability::SysAdmin)
+        );
+        assert_eq!(Capability::from_name("CAP_"), None);
+        assert_eq!(Capability::from_name("CAP_FLY"), None);
+    }
+
+    #[test]
+    fn test_capability_set() {
+        let mut set = CapabilitySet::from_slice(&[Capability::Chown, Capability::Bpf]);
+        assert!(set.contains(Capability::Bpf));
+        assert!(!set.contains(Capability::Kill));
+        assert_eq!(set.bits(), 1 | 1 << 39);
+        assert_eq!(CapabilitySet::from_bits(u64::MAX), CapabilitySet::all());
+
+        set.insert(Capability::Kill);
+        set.remove(Capability::Chown);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![Capability::Kill, Capability::Bpf]
+        );
+        assert_eq!(set.iter().collect::<CapabilitySet>(), set);
+        assert_eq!(CapabilitySet::all().iter().count(), 41);
+        assert!(CapabilitySet::EMPTY.is_empty());
+    }
+
+    #[test]
+    fn test_default_capabilities() {
+        let caps = Capabilities::default();
+        assert_eq!(caps.bounding.iter().count(), 14);
+        assert_eq!(caps.effective, caps.bounding);
+        assert_eq!(caps.permitted, caps.bounding);
+        assert!(caps.inheritable.is_empty());
+        assert!(caps.ambient.is_empty());
+        assert!(caps.bounding.contains(Capability::NetBindService));
+        assert!(!caps.bounding.contains(Capability::SysAdmin));
+        assert!(!caps.bounding.contains(Capability::NetAdmin));
+    }
+
+    #[test]
+    fn test_with_without_ambient() {
+        let caps = Capabilities::default()
+            .with(Capability::NetAdmin)
+            .without(Capability::NetRaw)
+            .with_ambient(Capability::NetBindService);
+        assert!(caps.effective.contains(Capability::NetAdmin));
+        assert!(!caps.bounding.contains(Capability::NetRaw));
+        assert!(!caps.permitted.contains(Capability::NetRaw));
+        assert!(caps.ambient.contains(Capability::NetBindService));
+        assert!(caps.inheritable.contains(Capability::NetBindService));
+
+        let none = Capabilities::none();
+        assert!(none.bounding.is_empty() && none.permitted.is_empty());
+        assert_eq!(Capabilities::all().bounding, CapabilitySet::all());
+    }
+}
@@ -11,6 +11,9 @@
 //! | `memory.max` | Memory limit | `268435456` (256MB) |
 //! | `memory.current` | Current memory usage | Read-only |
 //! | `io.max` | I/O bandwidth limit | `8:0 rbps=1048576 wbps=1048576` |
+//! | `pids.max` | Process count limit | `512` |
+//! | `cpuset.cpus` | Allowed CPUs | `0-3,6` |
+//! | `cpuset.mems` | Allowed memory nodes | `0` |
 //! | `cgroup.procs` | Process membership | Write PID to add |
 //! | `cgroup.controllers` | Available controllers | Read-only |
 
@@ -211,6 +214,157 @@ impl IoConfig {
     }
 }
 
+/// PIDs controller configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PidsConfig {
+    /// Maximum number of tasks (`u64::MAX` for unlimited)
+    pub max: u64,
+}
+
+impl Default for PidsConfig {
+    fn default() -> Self {
+        Self { max: u64::MAX }
+    }
+}
+
+impl PidsConfig {
+    /// Format for pids.max file
+    #[must_use]
+    pub fn to_pids_max(&self) -> String {
+        if self.max == u64::MAX {
+            "max".to_string()
+        } else {
+            self.max.to_string()
+        }
+    }
+}
+
+/// Cpuset controller configuration
+///
+/// Lists use the kernel format, e.g. `0-3,6`; an empty list inherits the
+/// parent's set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpusetConfig {
+    /// Allowed CPUs (cpuset.cpus)
+    pub cpus: String,
+    /// Allowed memory nodes (cpuset.mems)
+    pub mems: String,
+}
+
+impl CpusetConfig {
+    /// Create cpuset config for CPUs and memory nodes
+    #[must_use]
+    pub fn new(cpus: &str, mems: &str) -> Self {
+        Self {
+            cpus: cpus.to_string(),
+            mems: mems.to_string(),
+        }
+    }
+}
+
+/// Limits changed together by `CgroupController::update()`
+///
+/// Controllers left at `None` are not touched.
+#[derive(Debug, Clone, Default)]
+pub struct Resources {
+    /// CPU quota and weight
+    pub cpu: Option<CpuConfig>,
+    /// Memory limits
+    pub memory: Option<MemoryConfig>,
+    /// I/O limits of one device
+    pub io: Option<IoConfig>,
+    /// Task limit
+    pub pids: Option<PidsConfig>,
+    /// CPU and memory node placement
+    pub cpuset: Option<CpusetConfig>,
+}
+
+impl Resources {
+    /// Empty update
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set CPU limits
+    #[must_use]
+    pub const fn cpu(mut self, cpu: CpuConfig) -> Self {
+        self.cpu = Some(cpu);
+        self
+    }
+
+    /// Set memory limits
+    #[must_use]
+    pub const fn memory(mut self, memory: MemoryConfig) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Set I/O limits
+    #[must_use]
+    pub fn io(mut self, io: IoConfig) -> Self {
+        self.io = Some(io);
+        self
+    }
+
+    /// Set the task limit
+    #[must_use]
+    pub const fn pids_max(mut self, max: u64) -> Self {
+        self.pids = Some(PidsConfig { max });
+        self
+    }
+
+    /// Set CPU and memory node placement
+    #[must_use]
+    pub fn cpuset(mut self, cpuset: CpusetConfig) -> Self {
+        self.cpuset = Some(cpuset);
+        self
+    }
+
+    /// Whether no controller is changed
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.cpu.is_none()
+            && self.memory.is_none()
+            && self.io.is_none()
+            && self.pids.is_none()
+            && self.cpuset.is_none()
+    }
+}
+
+impl fmt::Display for Resources {
+    /// Interface-file notation, e.g. `cpu.max=50000 100000, pids.max=64`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = |value: u64| {
+            if value == u64::MAX {
+                "max".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let mut entries = Vec::new();
+        if let Some(cpu) = &self.cpu {
+            entries.push(format!("cpu.max={}", cpu.to_cpu_max()));
+            entries.push(format!("cpu.weight={}", cpu.weight));
+        }
+        if let Some(memory) = &self.memory {
+            entries.push(format!("memory.max={}", limit(memory.max)));
+            entries.push(format!("memory.high={}", limit(memory.high)));
+        }
+        if let Some(io) = &self.io {
+            entries.push(format!("io.max={}", io.to_io_max()));
+        }
+        if let Some(pids) = &self.pids {
+            entries.push(format!("pids.max={}", pids.to_pids_max()));
+        }
+        if let Some(cpuset) = &self.cpuset {
+            entries.push(format!("cpuset.cpus={}", cpuset.cpus));
+            entries.push(format!("cpuset.mems={}", cpuset.mems));
+        }
+        f.write_str(&entries.join(", "))
+    }
+}
+
 // ============================================================================
 // Cgroup Controller
 // ============================================================================
@@ -278,7 +432,7 @@ impl CgroupController {
         })
     }
 
-    /// Enable CPU, memory, and I/O controllers
+    /// Enable CPU, memory, I/O, pids and cpuset controllers
     #[allow(clippy::unused_self)]
     fn enable_controllers(&self) -> Result<(), CgroupError> {
         // Write to parent's cgroup.subtree_control
@@ -286,12 +440,16 @@ impl CgroupController {
         let subtree_control = parent.join("cgroup.subtree_control");
 
         if subtree_control.exists() {
-            // Enable controllers: +cpu +memory +io
-            Self::write_file(&subtree_control, "+cpu +memory +io").or_else(|_| {
+            // Enable controllers: +cpu +memory +io +pids +cpuset
+            Self::write_file(&subtree_control, "+cpu +memory +io +pids +cpuset").or_else(|_| {
                 // Try enabling one by one if combined fails
                 Self::write_file(&subtree_control, "+cpu")?;
                 Self::write_file(&subtree_control, "+memory")?;
-                Self::write_file(&subtree_control, "+io")
+                Self::write_file(&subtree_control, "+io")?;
+                // Optional: only needed when pids or cpuset limits are set
+                let _ = Self::write_file(&subtree_control, "+pids");
+                let _ = Self::write_file(&subtree_control, "+cpuset");
+                Ok::<(), CgroupError>(())
             })?;
         }
 
@@ -473,6 +631,141 @@ impl CgroupController {
         Ok(())
     }
 
+    /// Set the task limit
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pids controller is not enabled or the write fails.
+    pub fn set_pids(&self, config: &PidsConfig) -> Result<(), CgroupError> {
+        Self::write_file(&self.path.join("pids.max"), &config.to_pids_max())
+    }
+
+    /// Set CPU and memory node placement
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cpuset controller is not enabled or the kernel
+    /// rejects a list.
+    pub fn set_cpuset(&self, config: &CpusetConfig) -> Result<(), CgroupError> {
+        Self::write_file(&self.path.join("cpuset.cpus"), &config.cpus)?;
+        Self::write_file(&self.path.join("cpuset.mems"), &config.mems)
+    }
+
+    /// Apply several limits at once, or none of them
+    ///
+    /// The current values of all affected interface files are saved first.
+    /// If a write fails, the files written so far are restored and the
+    /// error is returned. Writes are batched through io_uring when the
+    /// `io_uring` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the failed read or write.
+    pub fn update(&self, resources: &Resources) -> Result<(), CgroupError> {
+        let writes = self.resource_writes(resources);
+        let previous = writes
+            .iter()
+            .map(|(file, content)| {
+                let current = Self::read_file(&self.path.join(file)).map_err(|e| match e {
+                    CgroupError::NotFound(_) => {
+                        CgroupError::ControllerNotEnabled(controller_of(file).to_string())
+                    }
+                    e => e,
+                })?;
+                Ok(if *file == "io.max" {
+                    previous_io_max(&current, content)
+                } else {
+                    current.trim().to_string()
+                })
+            })
+            .collect::<Result<Vec<_>, CgroupError>>()?;
+
+        self.write_batch(&writes).map_err(|(written, e)| {
+            // Best effort: the original error is what the caller needs
+            for ((file, _), old) in writes[..written].iter().zip(&previous).rev() {
+                let _ = Self::write_file(&self.path.join(file), old);
+            }
+            e
+        })
+    }
+
+    /// Interface file writes that apply `resources`, in order
+    ///
+    /// Optional files are skipped when absent, as in `set_cpu()` and
+    /// `set_memory()`.
+    fn resource_writes(&self, resources: &Resources) -> Vec<(&'static str, String)> {
+        let limit = |value: u64| {
+            if value == u64::MAX {
+                "max".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let exists = |file: &str| self.path.join(file).exists();
+        let mut writes = Vec::new();
+
+        if let Some(cpu) = &resources.cpu {
+            writes.push(("cpu.max", cpu.to_cpu_max()));
+            if exists("cpu.weight") {
+                writes.push(("cpu.weight", cpu.weight.to_string()));
+            }
+        }
+        if let Some(memory) = &resources.memory {
+            writes.push(("memory.max", limit(memory.max)));
+            if exists("memory.high") {
+                writes.push(("memory.high", limit(memory.high)));
+            }
+            if exists("memory.min") {
+                writes.push(("memory.min", memory.min.to_string()));
+            }
+            if exists("memory.oom.group") {
+                writes.push(("memory.oom.group", u8::from(memory.oom_kill).to_string()));
+            }
+        }
+        if let Some(io) = &resources.io {
+            writes.push(("io.max", io.to_io_max()));
+        }
+        if let Some(pids) = &resources.pids {
+            writes.push(("pids.max", pids.to_pids_max()));
+        }
+        if let Some(cpuset) = &resources.cpuset {
+            writes.push(("cpuset.cpus", cpuset.cpus.clone()));
+            writes.push(("cpuset.mems", cpuset.mems.clone()));
+        }
+        writes
+    }
+
+    /// Write files in order, stopping at the first failure
+    ///
+    /// On failure, returns how many writes succeeded along with the error.
+    fn write_batch(&self, writes: &[(&str, String)]) -> Result<(), (usize, CgroupError)> {
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if let Ok(mut batch) = crate::io_uring::IoUringCgroup::new(&self.path) {
+            use crate::io_uring::IoUringError;
+
+            for (file, content) in writes {
+                batch.queue_write(file, content.clone());
+            }
+            // Operations are numbered from 1 in queue order
+            return batch.sync_batch_write().map_err(|e| match e {
+                IoUringError::OperationFailed { user_data, errno } => {
+                    let failed = usize::try_from(user_data).map_or(0, |n| n.saturating_sub(1));
+                    let file = writes.get(failed).map_or("?", |(file, _)| file);
+                    (
+                        failed,
+                        CgroupError::IoError(format!("{file}: errno {errno}")),
+                    )
+                }
+                other => (0, CgroupError::IoError(other.to_string())),
+            });
+        }
+
+        for (i, (file, content)) in writes.iter().enumerate() {
+            Self::write_file(&self.path.join(file), content).map_err(|e| (i, e))?;
+        }
+        Ok(())
+    }
+
     /// Add a process to this cgroup
     ///
     /// # Errors
@@ -522,6 +815,21 @@ impl CgroupController {
         Err(CgroupError::InvalidParameter("usage_usec not found".into()))
     }
 
+    /// Read an interface file of this cgroup, e.g. `memory.stat`
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if the file does not exist, e.g. because its
+    /// controller is not enabled.
+    pub(crate) fn read_interface(&self, file: &str) -> Result<String, CgroupError> {
+        if file.contains('/') {
+            return Err(CgroupError::InvalidParameter(format!(
+                "Not an interface file: {file}"
+            )));
+        }
+        Self::read_file(&self.path.join(file))
+    }
+
     /// Get list of processes in this cgroup
     ///
     /// # Errors
@@ -539,6 +847,45 @@ impl CgroupController {
         Ok(pids)
     }
 
+    /// Get list of processes in this cgroup and all its descendants
+    ///
+    /// Child cgroups that are removed while the tree is walked are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn all_processes(&self) -> Result<Vec<u32>, CgroupError> {
+        let mut pids = self.processes()?;
+        let mut pending = vec![self.path.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(CgroupError::IoError(e.to_string())),
+            };
+            for entry in entries.flatten() {
+                if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    continue;
+                }
+                let child = entry.path();
+                match Self::read_file(&child.join("cgroup.procs")) {
+                    Ok(content) => {
+                        pids.extend(
+                            content
+                                .lines()
+                                .filter_map(|line| line.trim().parse::<u32>().ok()),
+                        );
+                    }
+                    Err(CgroupError::NotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                }
+                pending.push(child);
+            }
+        }
+        Ok(pids)
+    }
+
     /// Freeze all processes in this cgroup
     ///
     /// # Errors
@@ -598,6 +945,92 @@ impl CgroupController {
         Err(CgroupError::CgroupV2NotAvailable)
     }
 
+    /// Check whether any process is left in this cgroup (or its children)
+    ///
+    /// Reads `populated` from `cgroup.events`, falling back to `cgroup.procs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn is_populated(&self) -> Result<bool, CgroupError> {
+        match Self::read_file(&self.path.join("cgroup.events")) {
+            Ok(content) => parse_populated(&content)
+                .ok_or_else(|| CgroupError::InvalidParameter("populated not found".into())),
+            Err(CgroupError::NotFound(_)) => Ok(!self.processes()?.is_empty()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wait until no process is left in this cgroup
+    ///
+    /// Sleeps on `cgroup.events` notifications (`POLLPRI`) and re-checks at
+    /// least every 100ms. Returns `false` if the cgroup is still populated
+    /// when `timeout` expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(target_os = "linux")]
+    pub fn wait_unpopulated(&self, timeout: std::time::Duration) -> Result<bool, CgroupError> {
+        use std::os::unix::fs::FileExt;
+        use std::os::unix::io::AsRawFd;
+        use std::time::{Duration, Instant};
+
+        let deadline = Instant::now() + timeout;
+        // Notifications are only re-armed by reads through the same descriptor
+        let events = File::open(self.path.join("cgroup.events")).ok();
+
+        loop {
+            let populated = match &events {
+                Some(file) => {
+                    let mut buf = [0u8; 256];
+                    let n = file
+                        .read_at(&mut buf, 0)
+                        .map_err(|e| CgroupError::IoError(e.to_string()))?;
+                    parse_populated(&String::from_utf8_lossy(&buf[..n])).unwrap_or(true)
+                }
+                None => self.is_populated()?,
+            };
+            if !populated {
+                return Ok(true);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            let slice = remaining.min(Duration::from_millis(100));
+
+            match &events {
+                Some(file) => {
+                    let mut fds = libc::pollfd {
+                        fd: file.as_raw_fd(),
+                        events: libc::POLLPRI,
+                        revents: 0,
+                    };
+                    // SAFETY: fds is a single valid pollfd; the timeout is bounded by 100ms.
+                    unsafe {
+                        libc::poll(&mut fds, 1, slice.as_millis() as libc::c_int);
+                    }
+                }
+                None => std::thread::sleep(slice),
+            }
+        }
+    }
+
+    /// Wait until no process is left (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub const fn wait_unpopulated(
+        &self,
+        _timeout: std::time::Duration,
+    ) -> Result<bool, CgroupError> {
+        Err(CgroupError::CgroupV2NotAvailable)
+    }
+
     /// Destroy this cgroup
     ///
     /// Kills all processes and removes the cgroup directory.
@@ -681,6 +1114,37 @@ impl CgroupController {
     }
 }
 
+/// Controller owning an interface file, e.g. `pids` for `pids.max`
+#[cfg(feature = "std")]
+fn controller_of(file: &str) -> &str {
+    file.split_once('.')
+        .map_or(file, |(controller, _)| controller)
+}
+
+/// Line restoring the `io.max` entry of the device written by `new_line`
+///
+/// A device without an entry had no limits, which is written as `max`.
+#[cfg(feature = "std")]
+fn previous_io_max(current: &str, new_line: &str) -> String {
+    let device = new_line.split_whitespace().next().unwrap_or_default();
+    current
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(device))
+        .map_or_else(
+            || format!("{device} rbps=max wbps=max riops=max wiops=max"),
+            |line| line.trim().to_string(),
+        )
+}
+
+/// Parse the `populated` key of `cgroup.events`
+#[cfg(feature = "std")]
+pub(crate) fn parse_populated(events: &str) -> Option<bool> {
+    events.lines().find_map(|line| {
+        let value = line.strip_prefix("populated ")?;
+        Some(value.trim() != "0")
+    })
+}
+
 #[cfg(feature = "std")]
 impl Drop for CgroupController {
     fn drop(&mut self) {
@@ -735,6 +1199,13 @@ mod tests {
         assert!(io_max.contains("wbps=524288"));
     }
 
+    #[test]
+    fn test_parse_populated() {
+        assert_eq!(parse_populated("populated 1\nfrozen 0\n"), Some(true));
+        assert_eq!(parse_populated("populated 0\nfrozen 0\n"), Some(false));
+        assert_eq!(parse_populated("frozen 1\n"), None);
+    }
+
     #[test]
     fn test_cgroup_error_display() {
         let err = CgroupError::NotFound("/sys/fs/cgroup/test".into());
@@ -944,6 +1415,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_all_processes_walks_child_cgroups() {
+        let path = std::env::temp_dir().join(format!(
+            "alice-container-cgroup-tree-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(path.join("app/workers")).unwrap();
+        std::fs::write(path.join("cgroup.procs"), "1\n").unwrap();
+        std::fs::write(path.join("app/cgroup.procs"), "20\n21\n").unwrap();
+        std::fs::write(path.join("app/workers/cgroup.procs"), "300\n").unwrap();
+
+        let cgroup = CgroupController {
+            path: path.clone(),
+            container_id: "tree".into(),
+        };
+        let mut pids = cgroup.all_processes().unwrap();
+        pids.sort_unstable();
+        assert_eq!(pids, [1, 20, 21, 300]);
+        assert_eq!(cgroup.processes().unwrap(), [1]);
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pids_and_cpuset_config() {
+        assert_eq!(PidsConfig::default().to_pids_max(), "max");
+        assert_eq!(PidsConfig { max: 512 }.to_pids_max(), "512");
+        let cpuset = CpusetConfig::new("0-3", "0");
+        assert_eq!((cpuset.cpus.as_str(), cpuset.mems.as_str()), ("0-3", "0"));
+        assert!(Resources::new().is_empty());
+        assert!(!Resources::new().pids_max(10).is_empty());
+        assert_eq!(
+            Resources::new()
+                .cpu(CpuConfig::from_percent(50))
+                .pids_max(64)
+                .to_string(),
+            "cpu.max=50000 100000, cpu.weight=100, pids.max=64"
+        );
+    }
+
+    #[test]
+    fn test_previous_io_max() {
+        let current = "8:0 rbps=1024 wbps=max riops=max wiops=max\n8:16 rbps=max wbps=2048 riops=max wiops=max\n";
+        assert_eq!(
+            previous_io_max(current, "8:16 wbps=4096"),
+            "8:16 rbps=max wbps=2048 riops=max wiops=max"
+        );
+        assert_eq!(
+            previous_io_max(current, "259:0 rbps=1"),
+            "259:0 rbps=max wbps=max riops=max wiops=max"
+        );
+        assert_eq!(controller_of("memory.oom.group"), "memory");
+    }
+
+    /// Controller over a plain directory standing in for a cgroup
+    fn fake_cgroup(name: &str, files: &[(&str, &str)]) -> CgroupController {
+        let path = std::env::temp_dir().join(format!(
+            "alice-container-cgroup-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        for (file, content) in files {
+            fs::write(path.join(file), content).unwrap();
+        }
+        CgroupController {
+            path,
+            container_id: name.to_string(),
+        }
+    }
+
+    fn read(cgroup: &CgroupController, file: &str) -> String {
+        fs::read_to_string(cgroup.path().join(file)).unwrap()
+    }
+
+    #[test]
+    fn test_update_writes_all_files() {
+        let cgroup = fake_cgroup(
+            "update",
+            &[
+                ("cpu.max", "max 100000\n"),
+                ("memory.max", "max\n"),
+                ("memory.high", "max\n"),
+                ("pids.max", "max\n"),
+            ],
+        );
+        let resources = Resources::new()
+            .cpu(CpuConfig::from_percent(50))
+            .memory(MemoryConfig::with_limit(1000))
+            .pids_max(64);
+        cgroup.update(&resources).unwrap();
+
+        assert_eq!(read(&cgroup, "cpu.max"), "50000 100000");
+        assert_eq!(read(&cgroup, "memory.max"), "1000");
+        assert_eq!(read(&cgroup, "memory.high"), "900");
+        assert_eq!(read(&cgroup, "pids.max"), "64");
+        // Absent optional files are not created
+        assert!(!cgroup.path().join("cpu.weight").exists());
+
+        let err = cgroup
+            .update(&Resources::new().cpuset(CpusetConfig::new("0", "0")))
+            .unwrap_err();
+        assert_eq!(err, CgroupError::ControllerNotEnabled("cpuset".into()));
+        let _ = fs::remove_dir_all(cgroup.path());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_update_rolls_back_on_failure() {
+        let cgroup = fake_cgroup(
+            "rollback",
+            &[("cpu.max", "max 100000\n"), ("memory.max", "4096\n")],
+        );
+        // Readable, but every write fails
+        std::os::unix::fs::symlink("/proc/version", cgroup.path().join("pids.max")).unwrap();
+
+        let resources = Resources::new()
+            .cpu(CpuConfig::from_percent(20))
+            .memory(MemoryConfig {
+                max: 2048,
+                ..MemoryConfig::default()
+            })
+            .pids_max(10);
+        assert!(cgroup.update(&resources).is_err());
+
+        assert_eq!(read(&cgroup, "cpu.max"), "max 100000");
+        assert_eq!(read(&cgroup, "memory.max"), "4096");
+        let _ = fs::remove_dir_all(cgroup.path());
+    }
+
     #[test]
     fn test_cgroup_error_not_found_contains_path() {
         let path = "/sys/fs/cgroup/alice/mycontainer";
@@ -0,0 +1,534 @@
+//! Container Console
+//!
+//! Pseudo-terminals for container processes that run with `terminal` set.
+//!
+//! ```text
+//! runtime                                  container process
+//!   posix_openpt(3) ── master              slave ── stdin, stdout, stderr
+//!     │                                          ── controlling tty (setsid + TIOCSCTTY)
+//!     ├── Container::take_console()
+//!     └── console socket (SCM_RIGHTS)
+//! ```
+//!
+//! Both ends are opened by the runtime before the process is cloned, so the
+//! slave does not have to be reachable through the container's `/dev/pts`.
+//!
+//! ## Console Socket
+//!
+//! Like runc's `--console-socket`, the master can be handed to another
+//! process listening on a Unix stream socket. The runtime connects, sends a
+//! single message whose payload is the slave path and whose `SCM_RIGHTS`
+//! ancillary data carries the master descriptor, and disconnects.
+//! [`ConsoleSocket`] is the receiving end.
+//!
+//! ## Window Size
+//!
+//! `Console::resize()` sets the size on the master; the kernel then sends
+//! `SIGWINCH` to the foreground process group of the terminal. Callers
+//! typically forward the size of their own terminal on every `SIGWINCH`
+//! they receive with `Console::resize_from()`.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use crate::container::ContainerError;
+
+/// Maximum length of the slave path sent with the master
+const MAX_NAME: usize = 4096;
+
+// ============================================================================
+// Window Size
+// ============================================================================
+
+/// Size of a terminal in character cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowSize {
+    /// Number of rows
+    pub rows: u16,
+    /// Number of columns
+    pub cols: u16,
+}
+
+impl WindowSize {
+    /// Size of `rows` × `cols` cells
+    #[must_use]
+    pub const fn new(rows: u16, cols: u16) -> Self {
+        Self { rows, cols }
+    }
+
+    /// Current size of the terminal `tty`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `tty` is not a terminal.
+    pub fn of(tty: impl AsFd) -> Result<Self, ContainerError> {
+        // SAFETY: winsize is a plain C struct for which all-zero is a valid value.
+        let mut size: libc::winsize = unsafe { core::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ writes a winsize to the valid out-pointer.
+        if unsafe { libc::ioctl(tty.as_fd().as_raw_fd(), libc::TIOCGWINSZ, &mut size) } < 0 {
+            return Err(tty_error("TIOCGWINSZ"));
+        }
+        Ok(Self::new(size.ws_row, size.ws_col))
+    }
+
+    /// Apply this size to the terminal `tty`
+    fn apply(self, tty: BorrowedFd<'_>) -> Result<(), ContainerError> {
+        let size = libc::winsize {
+            ws_row: self.rows,
+            ws_col: self.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCSWINSZ reads a winsize from the valid pointer.
+        if unsafe { libc::ioctl(tty.as_raw_fd(), libc::TIOCSWINSZ, &size) } < 0 {
+            return Err(tty_error("TIOCSWINSZ"));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Pseudo-Terminal
+// ============================================================================
+
+/// Both ends of a newly allocated pseudo-terminal
+#[derive(Debug)]
+pub(crate) struct Pty {
+    /// Master, kept by the runtime or handed to the caller
+    pub(crate) master: Console,
+    /// Slave, installed as the container process's standard streams
+    pub(crate) slave: OwnedFd,
+}
+
+impl Pty {
+    /// Allocate a pseudo-terminal, optionally with an initial size
+    ///
+    /// Both ends are close-on-exec; the child installs the slave on its
+    /// standard streams, which clears the flag for those copies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no pseudo-terminal can be allocated.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open(size: Option<WindowSize>) -> Result<Self, ContainerError> {
+        // SAFETY: posix_openpt(3) takes flags and returns a new descriptor or -1.
+        let master = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC) };
+        if master < 0 {
+            return Err(tty_error("posix_openpt"));
+        }
+        // SAFETY: master is a new descriptor that nothing else owns.
+        let master = unsafe { OwnedFd::from_raw_fd(master) };
+
+        // SAFETY: master is an open pseudo-terminal master.
+        if unsafe { libc::grantpt(master.as_raw_fd()) } < 0 {
+            return Err(tty_error("grantpt"));
+        }
+        // SAFETY: see above.
+        if unsafe { libc::unlockpt(master.as_raw_fd()) } < 0 {
+            return Err(tty_error("unlockpt"));
+        }
+
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: name is a writable buffer of the given length.
+        if unsafe { libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len()) } != 0 {
+            return Err(tty_error("ptsname_r"));
+        }
+        // SAFETY: ptsname_r(3) succeeded and stored a NUL-terminated path.
+        let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+
+        // SAFETY: name is a valid NUL-terminated path.
+        let slave = unsafe {
+            libc::open(
+                name.as_ptr(),
+                libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+            )
+        };
+        if slave < 0 {
+            return Err(tty_error("open pty slave"));
+        }
+        // SAFETY: slave is a new descriptor that nothing else owns.
+        let slave = unsafe { OwnedFd::from_raw_fd(slave) };
+
+        if let Some(size) = size {
+            size.apply(slave.as_fd())?;
+        }
+
+        Ok(Self {
+            master: Console {
+                master: File::from(master),
+                path: PathBuf::from(name.to_string_lossy().into_owned()),
+            },
+            slave,
+        })
+    }
+
+    /// Allocate a pseudo-terminal (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn open(_size: Option<WindowSize>) -> Result<Self, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Consoles require Linux".into(),
+        ))
+    }
+}
+
+/// Start a new session with `slave` as controlling terminal and standard streams
+///
+/// Runs in the container process between clone and exec.
+#[cfg(target_os = "linux")]
+pub(crate) fn attach_slave(slave: RawFd) -> Result<(), ContainerError> {
+    // SAFETY: setsid(2) takes no arguments; it fails only if we already lead a process group.
+    if unsafe { libc::setsid() } < 0 {
+        return Err(tty_error("setsid"));
+    }
+    // SAFETY: slave is an open terminal and TIOCSCTTY takes an integer argument.
+    if unsafe { libc::ioctl(slave, libc::TIOCSCTTY, 0) } < 0 {
+        return Err(tty_error("TIOCSCTTY"));
+    }
+    for stream in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: dup2(2) on two valid descriptor numbers; the copy is not close-on-exec.
+        if unsafe { libc::dup2(slave, stream) } < 0 {
+            return Err(tty_error("dup2"));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Console
+// ============================================================================
+
+/// Master side of a container process's terminal
+///
+/// Reading returns the process's output, writing feeds its input. Reads
+/// fail with `EIO` once every process has closed the slave.
+#[derive(Debug)]
+pub struct Console {
+    master: File,
+    /// Slave path in the runtime's `/dev/pts`
+    path: PathBuf,
+}
+
+impl Console {
+    /// Path of the terminal's slave side, as seen by the runtime
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Current window size
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size cannot be read.
+    pub fn window_size(&self) -> Result<WindowSize, ContainerError> {
+        WindowSize::of(&self.master)
+    }
+
+    /// Change the window size, signalling `SIGWINCH` to the container process
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size cannot be set.
+    pub fn resize(&self, size: WindowSize) -> Result<(), ContainerError> {
+        size.apply(self.master.as_fd())
+    }
+
+    /// Copy the window size of the terminal `tty`, e.g. the caller's stdin
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `tty` is not a terminal or the size cannot be set.
+    pub fn resize_from(&self, tty: impl AsFd) -> Result<WindowSize, ContainerError> {
+        let size = WindowSize::of(tty)?;
+        self.resize(size)?;
+        Ok(size)
+    }
+
+    /// Hand the master to the process listening on the Unix socket `socket`
+    ///
+    /// The local descriptor is closed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be reached or the send fails.
+    pub fn send_to(self, socket: &Path) -> Result<(), ContainerError> {
+        let stream = UnixStream::connect(socket).map_err(|e| {
+            ContainerError::IoError(format!("console socket {}: {e}", socket.display()))
+        })?;
+        send_fd(
+            &stream,
+            self.path.as_os_str().as_encoded_bytes(),
+            self.master.as_raw_fd(),
+        )
+    }
+
+    /// Underlying master file
+    #[must_use]
+    pub fn into_file(self) -> File {
+        self.master
+    }
+}
+
+impl Read for Console {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.master.read(buf)
+    }
+}
+
+impl Write for Console {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.master.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.master.flush()
+    }
+}
+
+impl AsFd for Console {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.master.as_fd()
+    }
+}
+
+impl AsRawFd for Console {
+    fn as_raw_fd(&self) -> RawFd {
+        self.master.as_raw_fd()
+    }
+}
+
+impl From<Console> for OwnedFd {
+    fn from(console: Console) -> Self {
+        console.master.into()
+    }
+}
+
+// ============================================================================
+// Console Socket
+// ============================================================================
+
+/// Listening end of a console socket
+///
+/// Pass its path as the container's `console_socket` and call `accept()`
+/// while the container starts. The socket file is removed on drop.
+#[derive(Debug)]
+pub struct ConsoleSocket {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl ConsoleSocket {
+    /// Listen on a new Unix socket at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be created, e.g. because
+    /// `path` exists.
+    pub fn bind(path: impl Into<PathBuf>) -> Result<Self, ContainerError> {
+        let path = path.into();
+        let listener = UnixListener::bind(&path).map_err(|e| {
+            ContainerError::IoError(format!("console socket {}: {e}", path.display()))
+        })?;
+        Ok(Self { listener, path })
+    }
+
+    /// Socket path
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait for the runtime to send a console
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection fails or carries no descriptor.
+    pub fn accept(&self) -> Result<Console, ContainerError> {
+        let (stream, _) = self
+            .listener
+            .accept()
+            .map_err(|e| ContainerError::IoError(format!("console socket: {e}")))?;
+        let (name, master) = recv_fd(&stream)?;
+        Ok(Console {
+            master: File::from(master),
+            path: PathBuf::from(String::from_utf8_lossy(&name).into_owned()),
+        })
+    }
+}
+
+impl Drop for ConsoleSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// ============================================================================
+// SCM_RIGHTS
+// ============================================================================
+
+/// Space for the control message carrying one descriptor
+const FD_CMSG_SPACE: usize = 64;
+
+/// Send `payload` with a copy of `fd` attached
+fn send_fd(stream: &UnixStream, payload: &[u8], fd: RawFd) -> Result<(), ContainerError> {
+    let mut iov = libc::iovec {
+        iov_base: payload.as_ptr().cast_mut().cast(),
+        iov_len: payload.len(),
+    };
+    // u64 elements keep the buffer aligned for cmsghdr
+    let mut control = [0u64; FD_CMSG_SPACE / 8];
+
+    // SAFETY: msghdr is a plain C struct for which all-zero is a valid value.
+    let mut msg: libc::msghdr = unsafe { core::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    // SAFETY: CMSG_SPACE only computes a size.
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(size_of::<RawFd>() as u32) } as _;
+
+    // SAFETY: msg_control points to a zeroed buffer of at least msg_controllen bytes, so
+    // CMSG_FIRSTHDR returns a valid header into it, and CMSG_DATA has room for one fd.
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<RawFd>() as u32) as _;
+        core::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), fd);
+    }
+
+    loop {
+        // SAFETY: msg describes the live payload and control buffers above.
+        let sent = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) };
+        if sent >= 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(ContainerError::IoError(format!("send console: {err}")));
+        }
+    }
+}
+
+/// Receive a payload and the descriptor attached to it
+fn recv_fd(stream: &UnixStream) -> Result<(Vec<u8>, OwnedFd), ContainerError> {
+    let mut payload = vec![0u8; MAX_NAME];
+    let mut iov = libc::iovec {
+        iov_base: payload.as_mut_ptr().cast(),
+        iov_len: payload.len(),
+    };
+    let mut control = [0u64; FD_CMSG_SPACE / 8];
+
+    // SAFETY: msghdr is a plain C struct for which all-zero is a valid value.
+    let mut msg: libc::msghdr = unsafe { core::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = FD_CMSG_SPACE as _;
+
+    let received = loop {
+        // SAFETY: msg describes the live payload and control buffers above.
+        let n = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+        if n >= 0 {
+            break n as usize;
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(ContainerError::IoError(format!("receive console: {err}")));
+        }
+    };
+
+    let mut fd = None;
+    // SAFETY: recvmsg(2) filled msg_control with msg_controllen bytes of well-formed
+    // control messages, which CMSG_FIRSTHDR/CMSG_NXTHDR walk within bounds.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let raw = core::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>());
+                // The kernel installed a new descriptor for us
+                fd = Some(OwnedFd::from_raw_fd(raw));
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    let fd = fd.ok_or_else(|| {
+        ContainerError::IoError("console socket message carried no descriptor".into())
+    })?;
+    payload.truncate(received);
+    Ok((payload, fd))
+}
+
+/// Error for a failed terminal call, from errno
+fn tty_error(call: &str) -> ContainerError {
+    ContainerError::ProcessError(format!("{call}: {}", std::io::Error::last_os_error()))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pty_window_size_and_echo() {
+        let Ok(mut pty) = Pty::open(Some(WindowSize::new(24, 80))) else {
+            // No devpts in this environment
+            return;
+        };
+        assert_eq!(pty.master.window_size().unwrap(), WindowSize::new(24, 80));
+        assert_eq!(WindowSize::of(&pty.slave).unwrap(), WindowSize::new(24, 80));
+
+        pty.master.resize(WindowSize::new(50, 132)).unwrap();
+        assert_eq!(
+            WindowSize::of(&pty.slave).unwrap(),
+            WindowSize::new(50, 132)
+        );
+
+        // Output written to the slave arrives at the master
+        let mut slave = File::from(pty.slave);
+        slave.write_all(b"ok").unwrap();
+        let mut buf = [0u8; 2];
+        pty.master.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ok");
+    }
+
+    #[test]
+    fn test_console_socket_passes_master() {
+        let Ok(pty) = Pty::open(None) else {
+            return;
+        };
+        let path = std::env::temp_dir().join(format!(
+            "alice-container-console-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let socket = ConsoleSocket::bind(&path).unwrap();
+
+        let slave_path = pty.master.path().to_path_buf();
+        let sender = std::thread::spawn(move || pty.master.send_to(&path));
+        let mut console = socket.accept().unwrap();
+        sender.join().unwrap().unwrap();
+        assert_eq!(console.path(), slave_path);
+
+        // The received descriptor is the same terminal
+        let mut slave = File::from(pty.slave);
+        slave.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        console.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        let socket_path = socket.path().to_path_buf();
+        drop(socket);
+        assert!(!socket_path.exists());
+    }
+}
@@ -19,10 +19,11 @@
 //! | Running | Init process active |
 //! | Paused | All processes frozen |
 //! | Stopped | All processes terminated |
+//! | Exited | Init process exited on its own |
 //!
 //! ## Fork Safety
 //!
-//! `Container::start()` (legacy path via `spawn_init`) calls `fork(2)` internally.
+//! `Container::start()` (legacy path via `clone_init`) calls `clone(2)` internally.
 //! **Fork in a multi-threaded process is inherently unsafe**: only the calling thread
 //! is cloned into the child, while all other threads are silently killed.  Any mutexes
 //! or condition variables held by those threads at the moment of the fork will remain
@@ -40,23 +41,73 @@
 //!    process is already multi-threaded, all lock-holding code paths must be quiesced
 //!    before `fork(2)` via `pthread_atfork(prepare, parent, child)` handlers.
 //!
-//! 3. **Child process must only call async-signal-safe functions.**  After `fork(2)`
-//!    the child in `spawn_init` calls only `libc::pause()` and `std::process::exit(0)`,
-//!    both of which are async-signal-safe, so no additional precautions are needed for
-//!    the current implementation.
+//! 3. **Child process should avoid locks held by other threads.**  The exec arguments
+//!    (argv, envp, `PATH` candidates) are prepared in the parent before cloning.  The
+//!    child waits on the init channel (see `handshake`) until the runtime has written the
+//!    ID maps, placed it in the cgroup and set up networking, then performs the mount,
+//!    `pivot_root(2)`, hostname and `chdir(2)` setup and replaces itself with the workload
+//!    via `execve(2)`.  Setup failures are sent back to `start()` over the channel.
 //!
-//! 4. **Prefer `clone3` + `CLONE_INTO_CGROUP`.**  The preferred path (`spawn_init_clone3`,
+//! 4. **Prefer `clone3` + `CLONE_INTO_CGROUP`.**  The preferred path (`clone_init3`,
 //!    enabled by the `clone3` feature on Linux 5.7+) avoids `fork(2)` entirely and is
 //!    safe in multi-threaded programs.  Enable the `clone3` Cargo feature whenever
 //!    possible.
 
 use core::fmt;
 
+#[cfg(target_os = "linux")]
+use core::convert::Infallible;
+
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+
+#[cfg(target_os = "linux")]
+use std::os::unix::io::OwnedFd;
+use std::os::unix::io::RawFd;
+
 #[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 
-use crate::cgroup::{CgroupController, CgroupError, CpuConfig, IoConfig, MemoryConfig};
-use crate::namespace::{NamespaceError, NamespaceFlags};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::capability::{Capabilities, Capability};
+use crate::cgroup::{
+    CgroupController, CgroupError, CpuConfig, CpusetConfig, IoConfig, MemoryConfig, PidsConfig,
+    Resources,
+};
+#[cfg(feature = "std")]
+use crate::console::{Console, WindowSize};
+#[cfg(feature = "std")]
+use crate::events::{ContainerEvent, EventMonitor, EventSender, Notice};
+#[cfg(feature = "std")]
+use crate::exec::{ExecOptions, ExecProcess};
+#[cfg(feature = "std")]
+use crate::health::{
+    HealthCheck, HealthProbe, HealthState, HealthStatus, PendingProbe, ProbeTarget,
+};
+#[cfg(feature = "std")]
+use crate::hooks::{Hook, HookPhase, Hooks};
+#[cfg(feature = "std")]
+use crate::logs::{LogCapture, LogConfig, Logs, SharedLogWriter};
+use crate::namespace::{IdMapping, NamespaceError, NamespaceFlags};
+use crate::network::{NetworkConfig, NetworkError};
+#[cfg(feature = "std")]
+use crate::pidfd::PidFd;
+use crate::rlimit::{Rlimit, RlimitResource};
+use crate::rootfs::RootFsError;
+#[cfg(target_os = "linux")]
+use crate::seccomp::{SeccompAction, SeccompProfile};
+use crate::signed_state_change::ContainerEventKind;
+#[cfg(feature = "std")]
+use crate::state::StateRecord;
+#[cfg(feature = "std")]
+use crate::stats::ContainerStats;
+#[cfg(feature = "std")]
+use crate::top::ProcessInfo;
 
 // ============================================================================
 // Container State
@@ -73,6 +124,8 @@ pub enum ContainerState {
     Paused,
     /// Container is stopped
     Stopped,
+    /// Init process exited on its own (`code` is shell-style, `128 + signal` if killed)
+    Exited { code: i32 },
 }
 
 impl fmt::Display for ContainerState {
@@ -82,6 +135,55 @@ impl fmt::Display for ContainerState {
             Self::Running => write!(f, "running"),
             Self::Paused => write!(f, "paused"),
             Self::Stopped => write!(f, "stopped"),
+            Self::Exited { code } => write!(f, "exited ({code})"),
+        }
+    }
+}
+
+/// How the init process terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Exited normally with the given code
+    Exited(i32),
+    /// Terminated by the given signal
+    Signaled(i32),
+    /// Exited, but the status went to another parent (e.g. after `Container::load`)
+    Unknown,
+}
+
+impl ExitStatus {
+    /// Shell-style exit code (`128 + signal` if terminated by a signal, -1 if unknown)
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        match self {
+            Self::Exited(code) => code,
+            Self::Signaled(signal) => 128 + signal,
+            Self::Unknown => -1,
+        }
+    }
+
+    /// Terminating signal, if any
+    #[must_use]
+    pub const fn signal(self) -> Option<i32> {
+        match self {
+            Self::Exited(_) | Self::Unknown => None,
+            Self::Signaled(signal) => Some(signal),
+        }
+    }
+
+    /// Whether the process exited with code 0
+    #[must_use]
+    pub const fn success(self) -> bool {
+        matches!(self, Self::Exited(0))
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(code) => write!(f, "exit code {code}"),
+            Self::Signaled(signal) => write!(f, "signal {signal}"),
+            Self::Unknown => write!(f, "unknown status"),
         }
     }
 }
@@ -90,6 +192,161 @@ impl fmt::Display for ContainerState {
 // Container Configuration
 // ============================================================================
 
+/// User and group identity of a container process
+///
+/// Names are looked up in the container's `/etc/passwd` and `/etc/group`
+/// when a process is started, see [`ProcessUser::resolve`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessUser {
+    /// User ID
+    pub uid: u32,
+    /// Primary group ID
+    pub gid: u32,
+    /// Supplementary group IDs
+    pub additional_gids: Vec<u32>,
+    /// User name, overriding `uid` and `gid`
+    pub name: Option<String>,
+    /// Primary group name, overriding `gid`
+    pub group: Option<String>,
+    /// Supplementary group names, added to `additional_gids`
+    pub additional_groups: Vec<String>,
+    /// File mode creation mask (inherited from the runtime if `None`)
+    pub umask: Option<u32>,
+}
+
+impl ProcessUser {
+    /// Create an identity with no supplementary groups
+    #[must_use]
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self {
+            uid,
+            gid,
+            additional_gids: Vec::new(),
+            name: None,
+            group: None,
+            additional_groups: Vec::new(),
+            umask: None,
+        }
+    }
+
+    /// Whether this is the root identity without supplementary groups
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        self.uid == 0 && self.gid == 0 && self.additional_gids.is_empty() && !self.has_names()
+    }
+
+    /// Whether any user or group is given by name
+    #[must_use]
+    pub fn has_names(&self) -> bool {
+        self.name.is_some() || self.group.is_some() || !self.additional_groups.is_empty()
+    }
+
+    /// Resolve names against `/etc/passwd` and `/etc/group` below `root`
+    ///
+    /// A user name sets the UID and primary GID from its passwd entry and
+    /// adds every group listing the user as a member; a group name then
+    /// overrides the primary GID. Numeric names are accepted even without
+    /// an entry. The result carries IDs only.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if a name is not found, or an I/O error if the
+    /// account files cannot be read.
+    #[cfg(feature = "std")]
+    pub fn resolve(&self, root: &Path) -> Result<Self, ContainerError> {
+        if !self.has_names() {
+            return Ok(self.clone());
+        }
+        self.resolve_in(&crate::passwd::AccountDb::load(root)?)
+    }
+
+    #[cfg(feature = "std")]
+    fn resolve_in(&self, db: &crate::passwd::AccountDb) -> Result<Self, ContainerError> {
+        let no_user = |name: &str| {
+            ContainerError::ConfigError(format!("User {name:?} not found in /etc/passwd"))
+        };
+        let no_group = |name: &str| {
+            ContainerError::ConfigError(format!("Group {name:?} not found in /etc/group"))
+        };
+
+        let mut resolved = Self {
+            umask: self.umask,
+            ..Self::new(self.uid, self.gid)
+        };
+        let mut gids = Vec::new();
+
+        if let Some(name) = &self.name {
+            if let Some(entry) = db.user(name) {
+                resolved.uid = entry.uid;
+                resolved.gid = entry.gid;
+                gids.extend(db.member_gids(&entry.name));
+            } else {
+                resolved.uid = name.parse().map_err(|_| no_user(name))?;
+            }
+        }
+        if let Some(group) = &self.group {
+            resolved.gid = db.group_id(group).ok_or_else(|| no_group(group))?;
+        }
+
+        gids.extend(&self.additional_gids);
+        for group in &self.additional_groups {
+            gids.push(db.group_id(group).ok_or_else(|| no_group(group))?);
+        }
+        for gid in gids {
+            if !resolved.additional_gids.contains(&gid) {
+                resolved.additional_gids.push(gid);
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+/// When the supervisor restarts a container whose init process exited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Never restart
+    #[default]
+    Never,
+    /// Always restart, and start stopped containers when the supervisor recovers
+    Always,
+    /// Restart after a non-zero exit, at most `max_retries` times in a row
+    OnFailure { max_retries: u32 },
+    /// Always restart, but leave containers stopped by the user alone on recovery
+    UnlessStopped,
+}
+
+impl RestartPolicy {
+    /// Whether an init that exited with `status` should be restarted
+    ///
+    /// `attempts` is the number of consecutive restarts so far. An unknown
+    /// status (init reaped by another parent) counts as a failure.
+    #[must_use]
+    pub const fn should_restart(self, status: ExitStatus, attempts: u32) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Always | Self::UnlessStopped => true,
+            Self::OnFailure { max_retries } => !status.success() && attempts < max_retries,
+        }
+    }
+
+    /// Whether a container stopped by the user is started on supervisor recovery
+    #[must_use]
+    pub const fn start_on_recovery(self) -> bool {
+        matches!(self, Self::Always)
+    }
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Never => write!(f, "no"),
+            Self::Always => write!(f, "always"),
+            Self::OnFailure { max_retries } => write!(f, "on-failure:{max_retries}"),
+            Self::UnlessStopped => write!(f, "unless-stopped"),
+        }
+    }
+}
+
 /// Container resource and isolation configuration
 #[derive(Debug, Clone)]
 pub struct ContainerConfig {
@@ -99,20 +356,62 @@ pub struct ContainerConfig {
     pub hostname: String,
     /// Working directory inside container
     pub workdir: PathBuf,
+    /// Command and arguments executed as the container init process
+    pub args: Vec<String>,
     /// Environment variables
     pub env: Vec<(String, String)>,
+    /// User and groups the init process runs as
+    pub user: ProcessUser,
+    /// Run the init process on a new pseudo-terminal
+    pub terminal: bool,
+    /// Unix socket that receives the terminal master (see `console`)
+    pub console_socket: Option<PathBuf>,
+    /// Initial terminal size
+    pub console_size: Option<WindowSize>,
+    /// Capture stdout and stderr of the init process (see `logs`)
+    pub log: Option<LogConfig>,
+    /// Run a minimal init as PID 1 that forwards signals and reaps zombies
+    pub mini_init: bool,
+    /// Descriptors of the runtime passed on to the workload under the same number
+    pub preserve_fds: Vec<RawFd>,
+    /// Signal sent to the init process when the runtime exits (`None` to outlive it)
+    pub parent_death_signal: Option<i32>,
+    /// Capability sets of the init process and exec'd commands
+    pub capabilities: Capabilities,
+    /// Resource limits of the init process and exec'd commands
+    pub rlimits: Vec<Rlimit>,
     /// Namespace flags
     pub namespaces: NamespaceFlags,
+    /// UID mapping for a new user namespace (defaults to root → current user)
+    pub uid_map: Option<IdMapping>,
+    /// GID mapping for a new user namespace (defaults to root → current group)
+    pub gid_map: Option<IdMapping>,
     /// CPU configuration
     pub cpu: CpuConfig,
     /// Memory configuration
     pub memory: MemoryConfig,
     /// I/O configuration (optional)
     pub io: Option<IoConfig>,
+    /// Task limit
+    pub pids: PidsConfig,
+    /// CPU and memory node placement (optional)
+    pub cpuset: Option<CpusetConfig>,
     /// Read-only root filesystem
     pub readonly_rootfs: bool,
     /// Enable networking
     pub network: bool,
+    /// veth/bridge settings (defaults to one derived from the container ID)
+    pub network_config: Option<NetworkConfig>,
+    /// Directory holding persisted container state
+    pub state_root: PathBuf,
+    /// Free-form key/value labels for selecting containers
+    pub labels: Vec<(String, String)>,
+    /// Restart policy applied by the supervisor
+    pub restart_policy: RestartPolicy,
+    /// Health check run while the container is running
+    pub health_check: Option<HealthCheck>,
+    /// OCI lifecycle hooks
+    pub hooks: Hooks,
 }
 
 impl Default for ContainerConfig {
@@ -121,6 +420,7 @@ impl Default for ContainerConfig {
             rootfs: PathBuf::from("/"),
             hostname: "container".to_string(),
             workdir: PathBuf::from("/"),
+            args: vec!["/bin/sh".to_string()],
             env: vec![
                 (
                     "PATH".to_string(),
@@ -128,12 +428,32 @@ impl Default for ContainerConfig {
                 ),
                 ("HOME".to_string(), "/root".to_string()),
             ],
+            user: ProcessUser::default(),
+            terminal: false,
+            console_socket: None,
+            console_size: None,
+            log: None,
+            mini_init: false,
+            preserve_fds: Vec::new(),
+            parent_death_signal: Some(libc::SIGKILL),
+            capabilities: Capabilities::default(),
+            rlimits: vec![Rlimit::new(RlimitResource::Core, 0, 0)],
             namespaces: NamespaceFlags::CONTAINER,
+            uid_map: None,
+            gid_map: None,
             cpu: CpuConfig::default(),
             memory: MemoryConfig::default(),
             io: None,
+            pids: PidsConfig::default(),
+            cpuset: None,
             readonly_rootfs: false,
             network: false,
+            network_config: None,
+            state_root: PathBuf::from(crate::STATE_ROOT),
+            labels: Vec::new(),
+            restart_policy: RestartPolicy::Never,
+            health_check: None,
+            hooks: Hooks::default(),
         }
     }
 }
@@ -144,6 +464,64 @@ impl ContainerConfig {
     pub fn builder() -> ContainerConfigBuilder {
         ContainerConfigBuilder::new()
     }
+
+    /// Check the configuration before a container is created
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if the root filesystem does not exist or a
+    /// resource limit is invalid.
+    pub fn validate(&self) -> Result<(), ContainerError> {
+        if !self.rootfs.exists() {
+            return Err(ContainerError::ConfigError(format!(
+                "Root filesystem does not exist: {}",
+                self.rootfs.display()
+            )));
+        }
+        if let Some(umask) = self.user.umask.filter(|&m| m > 0o777) {
+            return Err(ContainerError::ConfigError(format!(
+                "Invalid umask {umask:#o}"
+            )));
+        }
+        if let Some(fd) = self.preserve_fds.iter().find(|&&fd| fd <= 2) {
+            return Err(ContainerError::ConfigError(format!(
+                "Invalid preserved descriptor {fd}, stdin, stdout and stderr are always kept"
+            )));
+        }
+        crate::rlimit::validate(&self.rlimits)
+    }
+
+    /// Get the limit set for `resource`
+    #[must_use]
+    pub fn rlimit(&self, resource: RlimitResource) -> Option<&Rlimit> {
+        self.rlimits.iter().find(|l| l.resource == resource)
+    }
+
+    /// UID and GID mappings of the user namespace, `None` without one
+    ///
+    /// Without explicit mappings, root in the container is mapped to the
+    /// user running the runtime.
+    #[must_use]
+    pub fn id_mappings(&self) -> Option<(IdMapping, IdMapping)> {
+        if !self.namespaces.contains(NamespaceFlags::NEWUSER) {
+            return None;
+        }
+        // SAFETY: getuid(2)/getgid(2) cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Some((
+            self.uid_map.unwrap_or_else(|| IdMapping::root_to_user(uid)),
+            self.gid_map.unwrap_or_else(|| IdMapping::root_to_user(gid)),
+        ))
+    }
+
+    /// Get the value of a label
+    #[must_use]
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
 }
 
 /// Builder for `ContainerConfig`
@@ -182,6 +560,17 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Set the command and arguments of the init process
+    #[must_use]
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Add environment variable
     #[must_use]
     pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
@@ -189,6 +578,154 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Run the init process as the given user and group
+    #[must_use]
+    pub fn user(mut self, uid: u32, gid: u32) -> Self {
+        self.config.user.uid = uid;
+        self.config.user.gid = gid;
+        self
+    }
+
+    /// Set supplementary group IDs
+    #[must_use]
+    pub fn additional_gids(mut self, gids: impl Into<Vec<u32>>) -> Self {
+        self.config.user.additional_gids = gids.into();
+        self
+    }
+
+    /// Run the init process as a user of the container's `/etc/passwd`
+    ///
+    /// Overrides `user()`; the primary group and supplementary groups are
+    /// taken from the account files unless set by name.
+    #[must_use]
+    pub fn user_name(mut self, name: impl Into<String>) -> Self {
+        self.config.user.name = Some(name.into());
+        self
+    }
+
+    /// Set the primary group by name from the container's `/etc/group`
+    #[must_use]
+    pub fn group_name(mut self, name: impl Into<String>) -> Self {
+        self.config.user.group = Some(name.into());
+        self
+    }
+
+    /// Add supplementary groups by name from the container's `/etc/group`
+    #[must_use]
+    pub fn additional_groups<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .user
+            .additional_groups
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the file mode creation mask, e.g. `0o027`
+    #[must_use]
+    pub const fn umask(mut self, umask: u32) -> Self {
+        self.config.user.umask = Some(umask);
+        self
+    }
+
+    /// Run the init process on a new pseudo-terminal
+    ///
+    /// The master is available from `Container::take_console()`, or sent
+    /// to `console_socket()` if one is set.
+    #[must_use]
+    pub const fn terminal(mut self, enable: bool) -> Self {
+        self.config.terminal = enable;
+        self
+    }
+
+    /// Send the terminal master to the Unix socket at `path` on start
+    #[must_use]
+    pub fn console_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.console_socket = Some(path.into());
+        self
+    }
+
+    /// Set the initial terminal size
+    #[must_use]
+    pub const fn console_size(mut self, rows: u16, cols: u16) -> Self {
+        self.config.console_size = Some(WindowSize::new(rows, cols));
+        self
+    }
+
+    /// Capture stdout and stderr of the init process into a rotated log
+    ///
+    /// Read it back with `Container::logs()`. Ignored with `terminal`, whose
+    /// output goes to the terminal master.
+    #[must_use]
+    pub const fn log(mut self, log: LogConfig) -> Self {
+        self.config.log = Some(log);
+        self
+    }
+
+    /// Run the workload under a minimal init, like `docker run --init`
+    ///
+    /// PID 1 of the container then forwards signals to the workload, reaps
+    /// orphaned processes and exits with the workload's exit code.
+    #[must_use]
+    pub const fn mini_init(mut self, enable: bool) -> Self {
+        self.config.mini_init = enable;
+        self
+    }
+
+    /// Pass descriptors of the runtime on to the workload
+    ///
+    /// Every other descriptor above stderr is closed when the workload is
+    /// exec'd.
+    #[must_use]
+    pub fn preserve_fds(mut self, fds: impl IntoIterator<Item = RawFd>) -> Self {
+        self.config.preserve_fds.extend(fds);
+        self
+    }
+
+    /// Set the signal the init process gets when the runtime exits
+    ///
+    /// `SIGKILL` by default. With `None`, the container keeps running and
+    /// can be reattached with `Container::load()`. Init processes are
+    /// cloned from a thread that lives as long as the runtime, so the
+    /// thread calling `start()` may exit without the signal being sent.
+    #[must_use]
+    pub const fn parent_death_signal(mut self, signal: Option<i32>) -> Self {
+        self.config.parent_death_signal = signal;
+        self
+    }
+
+    /// Set the capability sets (Docker's default set if not called)
+    #[must_use]
+    pub const fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.config.capabilities = capabilities;
+        self
+    }
+
+    /// Set a resource limit, replacing any previous limit of `resource`
+    #[must_use]
+    pub fn rlimit(mut self, resource: RlimitResource, soft: u64, hard: u64) -> Self {
+        self.config.rlimits.retain(|l| l.resource != resource);
+        self.config.rlimits.push(Rlimit::new(resource, soft, hard));
+        self
+    }
+
+    /// Grant a capability in addition to the current sets
+    #[must_use]
+    pub fn cap_add(mut self, cap: Capability) -> Self {
+        self.config.capabilities = self.config.capabilities.with(cap);
+        self
+    }
+
+    /// Remove a capability from every set
+    #[must_use]
+    pub fn cap_drop(mut self, cap: Capability) -> Self {
+        self.config.capabilities = self.config.capabilities.without(cap);
+        self
+    }
+
     /// Set CPU quota in microseconds
     #[must_use]
     pub const fn cpu_quota_us(mut self, quota: u64) -> Self {
@@ -210,6 +747,20 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Limit the number of tasks in the container
+    #[must_use]
+    pub const fn pids_max(mut self, max: u64) -> Self {
+        self.config.pids = PidsConfig { max };
+        self
+    }
+
+    /// Restrict the container to CPUs and memory nodes, e.g. `("0-3", "0")`
+    #[must_use]
+    pub fn cpuset(mut self, cpus: &str, mems: &str) -> Self {
+        self.config.cpuset = Some(CpusetConfig::new(cpus, mems));
+        self
+    }
+
     /// Enable network namespace
     #[must_use]
     pub const fn with_network(mut self) -> Self {
@@ -218,6 +769,22 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Enable network namespace with explicit veth/bridge settings
+    #[must_use]
+    pub fn network_config(mut self, config: NetworkConfig) -> Self {
+        self.config.network_config = Some(config);
+        self.with_network()
+    }
+
+    /// Run in a new user namespace with the given UID/GID mappings
+    #[must_use]
+    pub const fn user_namespace(mut self, uid_map: IdMapping, gid_map: IdMapping) -> Self {
+        self.config.uid_map = Some(uid_map);
+        self.config.gid_map = Some(gid_map);
+        self.config.namespaces = self.config.namespaces.union(NamespaceFlags::NEWUSER);
+        self
+    }
+
     /// Set read-only root filesystem
     #[must_use]
     pub const fn readonly(mut self) -> Self {
@@ -225,6 +792,50 @@ impl ContainerConfigBuilder {
         self
     }
 
+    /// Set the directory holding persisted container state
+    #[must_use]
+    pub fn state_root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.state_root = path.into();
+        self
+    }
+
+    /// Set the restart policy
+    #[must_use]
+    pub const fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.config.restart_policy = policy;
+        self
+    }
+
+    /// Set the health check
+    #[must_use]
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.config.health_check = Some(check);
+        self
+    }
+
+    /// Set all lifecycle hooks
+    #[must_use]
+    pub fn hooks(mut self, hooks: Hooks) -> Self {
+        self.config.hooks = hooks;
+        self
+    }
+
+    /// Add a lifecycle hook, run after those already added for `phase`
+    #[must_use]
+    pub fn hook(mut self, phase: HookPhase, hook: Hook) -> Self {
+        self.config.hooks.get_mut(phase).push(hook);
+        self
+    }
+
+    /// Set a label, replacing any previous value for `key`
+    #[must_use]
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.config.labels.retain(|(k, _)| *k != key);
+        self.config.labels.push((key, value.into()));
+        self
+    }
+
     /// Build the configuration
     #[must_use]
     pub fn build(self) -> ContainerConfig {
@@ -249,6 +860,8 @@ pub enum ContainerError {
     Cgroup(CgroupError),
     /// Namespace error
     Namespace(NamespaceError),
+    /// Root filesystem error
+    RootFs(RootFsError),
     /// Invalid state transition
     InvalidState {
         current: ContainerState,
@@ -262,6 +875,21 @@ pub enum ContainerError {
     IoError(String),
     /// Container not found
     NotFound(String),
+    /// Container ID already in use
+    AlreadyExists(String),
+    /// Network setup error
+    Network(NetworkError),
+    /// Container init failed at a specific setup stage
+    Init {
+        stage: InitStage,
+        source: Box<ContainerError>,
+    },
+    /// Lifecycle hook failed
+    Hook {
+        phase: HookPhase,
+        path: String,
+        reason: String,
+    },
 }
 
 impl fmt::Display for ContainerError {
@@ -269,6 +897,7 @@ impl fmt::Display for ContainerError {
         match self {
             Self::Cgroup(e) => write!(f, "Cgroup error: {e}"),
             Self::Namespace(e) => write!(f, "Namespace error: {e}"),
+            Self::RootFs(e) => write!(f, "Root filesystem error: {e}"),
             Self::InvalidState { current, operation } => {
                 write!(f, "Cannot {operation} container in {current} state")
             }
@@ -276,6 +905,14 @@ impl fmt::Display for ContainerError {
             Self::ConfigError(msg) => write!(f, "Config error: {msg}"),
             Self::IoError(msg) => write!(f, "I/O error: {msg}"),
             Self::NotFound(id) => write!(f, "Container not found: {id}"),
+            Self::AlreadyExists(id) => write!(f, "Container already exists: {id}"),
+            Self::Network(e) => write!(f, "Network error: {e}"),
+            Self::Init { stage, source } => write!(f, "Container init failed ({stage}): {source}"),
+            Self::Hook {
+                phase,
+                path,
+                reason,
+            } => write!(f, "{phase} hook {path} failed: {reason}"),
         }
     }
 }
@@ -292,29 +929,214 @@ impl From<NamespaceError> for ContainerError {
     }
 }
 
-// ============================================================================
-// Container
-// ============================================================================
-
-/// A container instance
-#[cfg(feature = "std")]
-pub struct Container {
-    /// Unique container ID
-    id: String,
-    /// Container configuration
-    config: ContainerConfig,
-    /// Cgroup controller
-    cgroup: CgroupController,
-    /// Current state
-    state: ContainerState,
-    /// Init process PID (if running)
-    init_pid: Option<u32>,
+impl From<RootFsError> for ContainerError {
+    fn from(e: RootFsError) -> Self {
+        Self::RootFs(e)
+    }
 }
 
+impl From<NetworkError> for ContainerError {
+    fn from(e: NetworkError) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl ContainerError {
+    /// Attribute the error to a container init stage
+    #[must_use]
+    pub fn at(self, stage: InitStage) -> Self {
+        match self {
+            // Keep the innermost stage
+            Self::Init { .. } => self,
+            other => Self::Init {
+                stage,
+                source: Box::new(other),
+            },
+        }
+    }
+}
+
+/// Setup stage of the container init process
+///
+/// Reported in `ContainerError::Init` so callers can tell which step failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum InitStage {
+    /// Waiting for the runtime to finish its side of the setup
+    Sync = 1,
+    /// User namespace ID mappings
+    IdMap = 2,
+    /// Cgroup placement
+    Cgroup = 3,
+    /// veth/bridge setup
+    Network = 4,
+    /// Mounts and `pivot_root(2)`
+    Mount = 5,
+    /// Hostname
+    Hostname = 6,
+    /// Controlling terminal
+    Terminal = 7,
+    /// User and groups
+    User = 8,
+    /// Working directory
+    Workdir = 9,
+    /// `execve(2)` of the workload
+    Exec = 10,
+    /// Capability sets
+    Capabilities = 11,
+    /// Resource limits
+    Rlimits = 12,
+    /// Redirection of stdout and stderr to the log
+    Stdio = 13,
+    /// Descriptors inherited from the runtime
+    Fds = 14,
+    /// Signal mask, dispositions and parent-death signal
+    Signals = 15,
+    /// `no_new_privs` and seccomp filter
+    Seccomp = 16,
+}
+
+impl InitStage {
+    /// Decode a stage from its wire representation
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            1 => Self::Sync,
+            2 => Self::IdMap,
+            3 => Self::Cgroup,
+            4 => Self::Network,
+            5 => Self::Mount,
+            6 => Self::Hostname,
+            7 => Self::Terminal,
+            8 => Self::User,
+            9 => Self::Workdir,
+            10 => Self::Exec,
+            11 => Self::Capabilities,
+            12 => Self::Rlimits,
+            13 => Self::Stdio,
+            14 => Self::Fds,
+            15 => Self::Signals,
+            16 => Self::Seccomp,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for InitStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sync => write!(f, "sync"),
+            Self::IdMap => write!(f, "uid/gid map"),
+            Self::Cgroup => write!(f, "cgroup"),
+            Self::Network => write!(f, "network"),
+            Self::Mount => write!(f, "mount"),
+            Self::Hostname => write!(f, "hostname"),
+            Self::Terminal => write!(f, "terminal"),
+            Self::User => write!(f, "user"),
+            Self::Workdir => write!(f, "workdir"),
+            Self::Exec => write!(f, "exec"),
+            Self::Capabilities => write!(f, "capabilities"),
+            Self::Rlimits => write!(f, "rlimits"),
+            Self::Stdio => write!(f, "stdio"),
+            Self::Fds => write!(f, "file descriptors"),
+            Self::Signals => write!(f, "signals"),
+            Self::Seccomp => write!(f, "seccomp"),
+        }
+    }
+}
+
+// ============================================================================
+// Container IDs
+// ============================================================================
+
+/// Maximum container ID length
+pub const MAX_ID_LEN: usize = 64;
+
+/// Check that `id` is usable as a container ID
+///
+/// IDs name the cgroup directory under `ALICE_CGROUP` and the state
+/// directory, so they are restricted to ASCII letters, digits, `_` and `-`,
+/// must start with a letter or digit and be at most `MAX_ID_LEN` bytes.
+/// Dots are rejected so an ID can neither traverse (`..`) nor shadow a
+/// cgroup interface file (`cgroup.procs`).
+///
+/// # Errors
+///
+/// Returns `ConfigError` if the ID is invalid.
+pub fn validate_id(id: &str) -> Result<(), ContainerError> {
+    let valid = id.len() <= MAX_ID_LEN
+        && id.bytes().next().is_some_and(|b| b.is_ascii_alphanumeric())
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ContainerError::ConfigError(format!(
+            "Invalid container ID: {id:?}"
+        )))
+    }
+}
+
+// ============================================================================
+// Container
+// ============================================================================
+
+/// A container instance
+#[cfg(feature = "std")]
+pub struct Container {
+    /// Unique container ID
+    id: String,
+    /// Container configuration
+    config: ContainerConfig,
+    /// Cgroup controller
+    cgroup: CgroupController,
+    /// Current state
+    state: ContainerState,
+    /// Init process handle (until it is reaped)
+    init: Option<PidFd>,
+    /// How the last init process terminated
+    exit_status: Option<ExitStatus>,
+    /// Init process start time, identifies its PID across runtime restarts
+    init_start_time: Option<u64>,
+    /// Terminal master of the current run, until taken or sent away
+    console: Option<Console>,
+    /// Log shared by the capture threads of all runs, opened on first start
+    log: Option<SharedLogWriter>,
+    /// Capture thread of the current run
+    log_capture: Option<LogCapture>,
+    /// Creation time (seconds since the Unix epoch)
+    created: u64,
+    /// Health check results of the current run
+    health: Option<HealthState>,
+    /// Called with the container ID when the health status changes
+    health_callback: Option<HealthCallback>,
+    /// Called with audit events, see `Container::on_event()`
+    event_callback: Option<EventCallback>,
+    /// Monitors receiving lifecycle events, see `Container::subscribe()`
+    event_senders: Vec<EventSender>,
+    /// Whether poststop hooks are due once the current run has stopped
+    poststop_pending: bool,
+    /// Failures of poststart and poststop hooks since the last start
+    hook_errors: Vec<ContainerError>,
+}
+
+/// Health change notification, see `Container::on_health_change()`
+#[cfg(feature = "std")]
+type HealthCallback = Box<dyn FnMut(&str, HealthStatus) + Send>;
+
+/// Audit event notification, see `Container::on_event()`
+#[cfg(feature = "std")]
+type EventCallback = Box<dyn FnMut(&str, ContainerEventKind, &str) + Send>;
+
 #[cfg(feature = "std")]
 impl Container {
     /// Create a new container
     ///
+    /// The container state is persisted under `config.state_root` and kept
+    /// up to date on every lifecycle transition.
+    ///
     /// # Arguments
     /// * `id` - Unique container identifier
     /// * `config` - Container configuration
@@ -323,12 +1145,11 @@ impl Container {
     ///
     /// Returns an error if the operation fails.
     pub fn create(id: &str, config: ContainerConfig) -> Result<Self, ContainerError> {
-        // Validate configuration
-        if !config.rootfs.exists() {
-            return Err(ContainerError::ConfigError(format!(
-                "Root filesystem does not exist: {}",
-                config.rootfs.display()
-            )));
+        validate_id(id)?;
+        config.validate()?;
+
+        if StateRecord::exists(&config.state_root, id) {
+            return Err(ContainerError::AlreadyExists(id.to_string()));
         }
 
         // Create cgroup
@@ -341,264 +1162,1007 @@ impl Container {
         if let Some(ref io) = config.io {
             cgroup.set_io(io)?;
         }
+        // Optional controllers are only required when limited
+        if config.pids.max != u64::MAX {
+            cgroup.set_pids(&config.pids)?;
+        }
+        if let Some(ref cpuset) = config.cpuset {
+            cgroup.set_cpuset(cpuset)?;
+        }
 
-        Ok(Self {
+        let container = Self {
             id: id.to_string(),
             config,
             cgroup,
             state: ContainerState::Created,
-            init_pid: None,
-        })
+            init: None,
+            exit_status: None,
+            init_start_time: None,
+            console: None,
+            log: None,
+            log_capture: None,
+            created: crate::state::unix_now(),
+            health: None,
+            health_callback: None,
+            event_callback: None,
+            event_senders: Vec::new(),
+            poststop_pending: false,
+            hook_errors: Vec::new(),
+        };
+
+        if let Err(e) = container.persist() {
+            let _ = container.cgroup.destroy();
+            return Err(e);
+        }
+
+        Ok(container)
+    }
+
+    /// Load a container persisted under the default state root
+    ///
+    /// See [`Container::load_from`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if no state exists for `id`.
+    pub fn load(id: &str) -> Result<Self, ContainerError> {
+        Self::load_from(Path::new(crate::STATE_ROOT), id)
+    }
+
+    /// Load a container persisted under `state_root`
+    ///
+    /// Rebuilds the handle of a container created by an earlier runtime
+    /// instance. A running init process is reattached by PID and start
+    /// time; if it is gone, the container is reported as `Exited` with an
+    /// unknown status. Containers only outlive the runtime that started
+    /// them without a `parent_death_signal`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if no state exists for `id`, or an error if the
+    /// state is corrupt or the cgroup no longer exists.
+    pub fn load_from(state_root: &Path, id: &str) -> Result<Self, ContainerError> {
+        validate_id(id)?;
+        let record = StateRecord::load(state_root, id)?;
+        let cgroup = CgroupController::open(id)?;
+
+        let mut container = Self {
+            id: record.id,
+            config: record.config,
+            cgroup,
+            state: record.state,
+            init: None,
+            exit_status: record.exit_status,
+            init_start_time: record.init_start_time,
+            console: None,
+            log: None,
+            log_capture: None,
+            created: record.created,
+            health: None,
+            health_callback: None,
+            event_callback: None,
+            event_senders: Vec::new(),
+            poststop_pending: false,
+            hook_errors: Vec::new(),
+        };
+        // The state may have been moved since it was written
+        container.config.state_root = state_root.to_path_buf();
+        container.poststop_pending = matches!(
+            container.state,
+            ContainerState::Running | ContainerState::Paused | ContainerState::Exited { .. }
+        );
+
+        if matches!(
+            container.state,
+            ContainerState::Running | ContainerState::Paused
+        ) {
+            let attached = match (record.init_pid, record.init_start_time) {
+                (Some(pid), Some(start_time)) => match PidFd::attach(pid, start_time) {
+                    Ok(init) => Some(init),
+                    Err(ContainerError::NotFound(_)) => None,
+                    Err(e) => return Err(e),
+                },
+                _ => None,
+            };
+
+            match attached {
+                Some(init) => {
+                    container.init = Some(init);
+                    // Probe results did not survive the runtime restart
+                    container.reset_health();
+                }
+                None => container.record_exit(ExitStatus::Unknown)?,
+            }
+        }
+
+        Ok(container)
+    }
+
+    /// Write the current state to the state root
+    fn persist(&self) -> Result<(), ContainerError> {
+        StateRecord {
+            id: self.id.clone(),
+            state: self.state,
+            exit_status: self.exit_status,
+            init_pid: self.pid(),
+            init_start_time: self.init.as_ref().and(self.init_start_time),
+            created: self.created,
+            cgroup_path: self.cgroup.path().to_path_buf(),
+            config: self.config.clone(),
+        }
+        .save(&self.config.state_root)
     }
 
     /// Start the container
     ///
+    /// Returns once the init process has exec'd the workload. If any setup
+    /// step fails, in the runtime or in the child, the child is reaped and
+    /// the underlying error is returned; the container stays in its
+    /// previous state.
+    ///
+    /// Lifecycle hooks run around the init setup. A failing `prestart`,
+    /// `createRuntime`, `createContainer` or `startContainer` hook fails the
+    /// start; `poststop` hooks then run as if the container had stopped.
+    /// `poststart` failures are recorded in `hook_errors()`.
+    ///
     /// # Errors
     ///
     /// Returns an error if the operation fails.
     pub fn start(&mut self) -> Result<(), ContainerError> {
-        if self.state != ContainerState::Created && self.state != ContainerState::Stopped {
+        self.refresh();
+        if !matches!(
+            self.state,
+            ContainerState::Created | ContainerState::Stopped | ContainerState::Exited { .. }
+        ) {
             return Err(ContainerError::InvalidState {
                 current: self.state,
                 operation: "start",
             });
         }
 
+        self.hook_errors.clear();
+        // The previous run exited on its own and was never stopped
+        self.run_poststop_hooks();
+
+        let spawned = match self.spawn_init() {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                self.poststop_pending = true;
+                self.run_poststop_hooks();
+                return Err(e);
+            }
+        };
+
+        self.init_start_time = crate::pidfd::process_start_time(spawned.init.pid()).ok();
+        self.init = Some(spawned.init);
+        self.console = spawned.console;
+        // A capture left from a run that exited on its own keeps going until
+        // the last holder of its pipes is gone
+        self.log_capture = match (&self.log, spawned.output) {
+            (Some(log), Some((stdout, stderr))) => {
+                LogCapture::spawn(Arc::clone(log), stdout, stderr).ok()
+            }
+            _ => None,
+        };
+        self.exit_status = None;
+        self.state = ContainerState::Running;
+        self.poststop_pending = true;
+        self.reset_health();
+
+        self.persist()?;
+        if let Some(pid) = self.pid() {
+            self.notify(ContainerEvent::Started { pid });
+        }
+        self.run_post_hooks(HookPhase::Poststart, "running");
+        Ok(())
+    }
+
+    /// Spawn the init process and drive the setup handshake
+    ///
+    /// The child blocks until the runtime has written the ID maps, placed it
+    /// in the cgroup and configured networking, then reports either a setup
+    /// error or a successful `execve(2)` (end-of-file on the channel).
+    ///
+    /// With `terminal` set, the terminal master is returned unless it was
+    /// sent to the console socket. With `log` set instead, the read ends of
+    /// the init's stdout and stderr pipes are returned.
+    #[cfg(target_os = "linux")]
+    fn spawn_init(&mut self) -> Result<SpawnedInit, ContainerError> {
+        use std::os::unix::io::AsRawFd;
+
+        // Prepare everything that allocates before the address space is duplicated
+        let spec = ExecSpec::init(&self.config)?;
+        let pty = if self.config.terminal {
+            let pty = crate::console::Pty::open(self.config.console_size)
+                .map_err(|e| e.at(InitStage::Terminal))?;
+            Some(pty)
+        } else {
+            None
+        };
+        let pipes = match self.config.log {
+            Some(config) if pty.is_none() => {
+                self.open_log(config)?;
+                Some((pipe()?, pipe()?))
+            }
+            _ => None,
+        };
+        let stdio = match (&pty, &pipes) {
+            (Some(pty), _) => InitStdio::Terminal(pty.slave.as_raw_fd()),
+            (None, Some(((_, stdout), (_, stderr)))) => InitStdio::Log {
+                stdout: stdout.as_raw_fd(),
+                stderr: stderr.as_raw_fd(),
+            },
+            (None, None) => InitStdio::Inherit,
+        };
+
         // Try clone3 with CLONE_INTO_CGROUP first (zero-copy cgroup placement)
-        #[cfg(all(feature = "clone3", target_os = "linux"))]
+        #[cfg(feature = "clone3")]
+        let cloned = self.clone_init3(&spec, stdio).ok();
+        #[cfg(not(feature = "clone3"))]
+        let cloned: Option<(PidFd, crate::handshake::InitChannel)> = None;
+
+        let ((init, channel), in_cgroup) = match cloned {
+            Some(cloned) => (cloned, true),
+            // Fall back to legacy clone + add_process
+            None => (self.clone_init(&spec, stdio)?, false),
+        };
+        let pid = init.pid();
+        // Only the init keeps the slave open, so the master sees its hangup
+        let mut console = pty.map(|pty| pty.master);
+        // Likewise for the write ends, so the capture sees end-of-file
+        let output = pipes.map(|((stdout, _), (stderr, _))| (stdout, stderr));
+
+        let prepared = self
+            .prepare_init(pid, in_cgroup)
+            .and_then(|()| self.run_create_hooks(pid))
+            .and_then(|()| self.send_console(&mut console))
+            .and_then(|()| channel.send_continue().map_err(|e| e.at(InitStage::Sync)));
+        let result = match prepared {
+            Ok(()) => self.await_exec(pid, &channel),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(None) => Ok(SpawnedInit {
+                init,
+                console,
+                output,
+            }),
+            Ok(Some(e)) | Err(e) => {
+                kill_and_reap(pid as libc::pid_t);
+                Err(e)
+            }
+        }
+    }
+
+    /// Spawn init process (non-Linux stub)
+    #[cfg(not(target_os = "linux"))]
+    #[allow(clippy::unused_self)]
+    fn spawn_init(&mut self) -> Result<SpawnedInit, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Open the log on first use, kept open across runs
+    fn open_log(&mut self, config: LogConfig) -> Result<(), ContainerError> {
+        if self.log.is_none() {
+            let writer = crate::logs::LogWriter::open(self.log_path(), config)?;
+            self.log = Some(Arc::new(Mutex::new(writer)));
+        }
+        Ok(())
+    }
+
+    /// Path of the current log file
+    fn log_path(&self) -> PathBuf {
+        StateRecord::dir(&self.config.state_root, &self.id).join(crate::logs::LOG_FILE)
+    }
+
+    /// Hand the terminal master to the console socket, if one is configured
+    #[cfg(target_os = "linux")]
+    fn send_console(&self, console: &mut Option<Console>) -> Result<(), ContainerError> {
+        match (&self.config.console_socket, console.take()) {
+            (Some(socket), Some(master)) => master
+                .send_to(socket)
+                .map_err(|e| e.at(InitStage::Terminal)),
+            (_, master) => {
+                *console = master;
+                Ok(())
+            }
+        }
+    }
+
+    /// Run the hooks due after the init was created, before it continues
+    #[cfg(target_os = "linux")]
+    fn run_create_hooks(&self, pid: u32) -> Result<(), ContainerError> {
+        use crate::hooks::{run_hooks, HookNamespaces};
+
+        let hooks = &self.config.hooks;
+        if hooks.prestart.is_empty()
+            && hooks.create_runtime.is_empty()
+            && hooks.create_container.is_empty()
         {
-            if let Ok(pid) = self.spawn_init_clone3() {
-                self.init_pid = Some(pid);
-                self.state = ContainerState::Running;
-                // No add_process needed - clone3 already placed process in cgroup
-                return Ok(());
+            return Ok(());
+        }
+
+        let state = self.hook_state("creating", Some(pid));
+        run_hooks(HookPhase::Prestart, &hooks.prestart, &state, None)?;
+        run_hooks(
+            HookPhase::CreateRuntime,
+            &hooks.create_runtime,
+            &state,
+            None,
+        )?;
+        run_hooks(
+            HookPhase::CreateContainer,
+            &hooks.create_container,
+            &state,
+            Some(HookNamespaces { pid, chroot: false }),
+        )
+    }
+
+    /// Wait for the init to exec, running `startContainer` hooks right before
+    #[cfg(target_os = "linux")]
+    fn await_exec(
+        &self,
+        pid: u32,
+        channel: &crate::handshake::InitChannel,
+    ) -> Result<Option<ContainerError>, ContainerError> {
+        use crate::hooks::{run_hooks, HookNamespaces};
+
+        let hooks = &self.config.hooks.start_container;
+        if !hooks.is_empty() {
+            if let Some(e) = channel.wait_created()? {
+                return Ok(Some(e));
             }
-            // Fall through to legacy method if clone3 fails
+            run_hooks(
+                HookPhase::StartContainer,
+                hooks,
+                &self.hook_state("created", Some(pid)),
+                Some(HookNamespaces { pid, chroot: true }),
+            )?;
+            channel.send_continue().map_err(|e| e.at(InitStage::Sync))?;
         }
+        channel.wait_exec()
+    }
 
-        // Legacy fork + add_process method
-        let pid = self.spawn_init()?;
+    /// Runtime side of the init setup, done while the child waits
+    #[cfg(target_os = "linux")]
+    fn prepare_init(&self, pid: u32, in_cgroup: bool) -> Result<(), ContainerError> {
+        use crate::namespace::{write_gid_map, write_uid_map};
+        use crate::network::{configure_container_interface, setup_container_network};
 
-        self.init_pid = Some(pid);
-        self.state = ContainerState::Running;
+        if let Some((uid_map, gid_map)) = self.config.id_mappings() {
+            write_uid_map(pid, &uid_map)
+                .and_then(|()| write_gid_map(pid, &gid_map))
+                .map_err(|e| ContainerError::from(e).at(InitStage::IdMap))?;
+        }
+
+        if !in_cgroup {
+            self.cgroup
+                .add_process(pid)
+                .map_err(|e| ContainerError::from(e).at(InitStage::Cgroup))?;
+        }
 
-        // Add init process to cgroup (separate syscall)
-        self.cgroup.add_process(pid)?;
+        if self.config.network && self.config.namespaces.contains(NamespaceFlags::NEWNET) {
+            let network = self
+                .config
+                .network_config
+                .clone()
+                .unwrap_or_else(|| NetworkConfig::from_container_id(&self.id, 0));
+            setup_container_network(&network, pid)
+                .and_then(|_| configure_container_interface(&network, pid))
+                .map_err(|e| ContainerError::from(e).at(InitStage::Network))?;
+        }
 
         Ok(())
     }
 
-    /// Spawn init using clone3 with CLONE_INTO_CGROUP (Linux 5.7+)
+    /// Clone init using clone3 with CLONE_INTO_CGROUP (Linux 5.7+)
     ///
     /// This eliminates the separate cgroup.procs write by placing the
     /// new process directly into the cgroup during clone.
     #[cfg(all(feature = "clone3", target_os = "linux"))]
-    fn spawn_init_clone3(&self) -> Result<u32, ContainerError> {
-        use crate::clone3::{clone3_raw, clone_flags, close_cgroup_fd, open_cgroup_fd, Clone3Args};
+    fn clone_init3(
+        &self,
+        spec: &ExecSpec,
+        stdio: InitStdio,
+    ) -> Result<(PidFd, crate::handshake::InitChannel), ContainerError> {
+        use crate::clone3::{clone3_raw, close_cgroup_fd, open_cgroup_fd, Clone3Args};
+
+        let channel = crate::handshake::InitChannel::new()?;
 
         // Open cgroup directory fd
         let cgroup_fd = open_cgroup_fd(self.cgroup.path())
             .map_err(|e| ContainerError::ProcessError(format!("open cgroup fd: {}", e)))?;
 
-        // Build clone3 args with CLONE_INTO_CGROUP
-        let namespace_flags = self.config.namespaces.bits() as u64;
-        let args = Clone3Args::new()
-            .flags(namespace_flags)
-            .cgroup_fd(cgroup_fd);
+        let (config, init_channel) = (&self.config, &channel);
+        let result = on_spawner_thread(|| {
+            // Build clone3 args with CLONE_INTO_CGROUP, receiving a pidfd for the child
+            let mut pidfd: i32 = -1;
+            let namespace_flags = config.namespaces.bits() as u64;
+            let args = Clone3Args::new()
+                .flags(namespace_flags)
+                .cgroup_fd(cgroup_fd)
+                .with_pidfd(&mut pidfd);
 
-        // Perform clone3
-        let result = unsafe { clone3_raw(&args) };
+            // Perform clone3
+            // SAFETY: args is fully initialized and the pidfd it points to outlives the call.
+            match unsafe { clone3_raw(&args) } {
+                Ok(0) => {
+                    // Child process: set up the container and exec the workload
+                    let code = init_main(config, spec, stdio, init_channel);
+                    // SAFETY: _exit(2) terminates the child immediately without running
+                    // atexit handlers or flushing stdio buffers inherited from the parent.
+                    unsafe { libc::_exit(code) }
+                }
+                Ok(pid) => Ok((pid, pidfd)),
+                Err(e) => Err(e),
+            }
+        });
 
         // Close cgroup fd in parent
         close_cgroup_fd(cgroup_fd);
 
-        match result {
-            Ok(0) => {
-                // Child process
-                // SAFETY: pause(2) is always safe to call; it blocks until a signal is received
-                // and has no preconditions on process state.
-                unsafe {
-                    libc::pause();
-                }
-                std::process::exit(0);
-            }
-            Ok(pid) => Ok(pid),
+        match result? {
+            // SAFETY: CLONE_PIDFD stored a new pidfd for `pid` that nothing else owns.
+            Ok((pid, pidfd)) => init_ready(unsafe { PidFd::from_raw(pid, pidfd) }, channel),
             Err(e) => Err(ContainerError::ProcessError(format!("clone3: {}", e))),
         }
     }
 
-    /// Spawn the init process in new namespaces
+    /// Clone the init process in new namespaces
     #[cfg(target_os = "linux")]
-    fn spawn_init(&self) -> Result<u32, ContainerError> {
-        // For now, use a simple fork approach
-        // In production, would use clone() with namespace flags
+    fn clone_init(
+        &self,
+        spec: &ExecSpec,
+        stdio: InitStdio,
+    ) -> Result<(PidFd, crate::handshake::InitChannel), ContainerError> {
+        use crate::namespace::{clone_with_namespaces, CloneFlags};
 
-        // SAFETY: No threads are running that would be silently killed by fork at this point
-        // (single-threaded init path); all file descriptors are valid. The child calls only
-        // async-signal-safe functions (pause, _exit) before exec.
-        let pid = unsafe { libc::fork() };
+        let channel = crate::handshake::InitChannel::new()?;
 
-        match pid {
-            -1 => Err(ContainerError::ProcessError("fork failed".into())),
-            0 => {
-                // Child process - this would set up namespaces
-                // For testing, just sleep
-                // SAFETY: pause(2) is always safe to call; it blocks until a signal is received.
-                unsafe {
-                    libc::pause();
-                }
-                std::process::exit(0);
+        let flags = CloneFlags {
+            namespaces: self.config.namespaces,
+            extra: 0,
+        };
+
+        let (config, init_channel) = (&self.config, &channel);
+        let pid = on_spawner_thread(|| {
+            // SAFETY: clone(2) is called without CLONE_VM, so the child runs on its own copy
+            // of the address space and the mmap-ed stack; the closure only borrows data that
+            // is duplicated into the child and never touches state shared with the parent.
+            unsafe {
+                clone_with_namespaces(flags, INIT_STACK_SIZE, || {
+                    init_main(config, spec, stdio, init_channel)
+                })
             }
-            child_pid => Ok(child_pid as u32),
-        }
-    }
+        })??;
 
-    /// Spawn init process (non-Linux stub)
-    #[cfg(not(target_os = "linux"))]
-    #[allow(clippy::unused_self)]
-    fn spawn_init(&self) -> Result<u32, ContainerError> {
-        Err(ContainerError::ProcessError(
-            "Container runtime requires Linux".into(),
-        ))
+        // Not yet reaped, so the PID cannot have been reused
+        match PidFd::open(pid) {
+            Ok(init) => init_ready(init, channel),
+            Err(e) => {
+                kill_and_reap(pid as libc::pid_t);
+                Err(e)
+            }
+        }
     }
 
     /// Execute a command in the container
     ///
+    /// The command joins the namespaces of the init process via
+    /// `/proc/<pid>/ns/*`, is placed in the container's cgroup, and is
+    /// chrooted to the container root before `execve(2)`. It inherits the
+    /// container's environment, working directory and user.
+    ///
     /// # Arguments
     /// * `cmd` - Command and arguments
     ///
     /// # Returns
-    /// Exit code of the command
+    /// Exit code of the command (`128 + signal` if it was killed by a signal)
     ///
     /// # Errors
     ///
     /// Returns an error if the operation fails.
+    #[cfg(target_os = "linux")]
     pub fn exec(&mut self, cmd: &[&str]) -> Result<i32, ContainerError> {
-        use std::process::Command;
-
-        if self.state != ContainerState::Running {
-            return Err(ContainerError::InvalidState {
-                current: self.state,
-                operation: "exec",
-            });
-        }
-
-        if cmd.is_empty() {
-            return Err(ContainerError::ConfigError("Empty command".into()));
-        }
-
-        // Execute command in container's namespace
-        // In production, would use nsenter or setns
-        let output = Command::new(cmd[0])
-            .args(&cmd[1..])
-            .current_dir(&self.config.workdir)
-            .envs(self.config.env.iter().cloned())
-            .output()
-            .map_err(|e| ContainerError::ProcessError(e.to_string()))?;
-
-        Ok(output.status.code().unwrap_or(-1))
+        let spawned = self.spawn_exec(cmd, &ExecOptions::new(), ExecStdio::Inherit)?;
+        wait_exit_code(spawned.helper)
     }
 
-    /// Pause the container (freeze all processes)
+    /// Execute a command in the container, giving up after `timeout`
+    ///
+    /// Like `exec()`, but kills the command and returns `None` if it has not
+    /// finished within `timeout`.
     ///
     /// # Errors
     ///
     /// Returns an error if the operation fails.
-    pub fn pause(&mut self) -> Result<(), ContainerError> {
-        if self.state != ContainerState::Running {
-            return Err(ContainerError::InvalidState {
-                current: self.state,
-                operation: "pause",
-            });
+    #[cfg(target_os = "linux")]
+    pub fn exec_timeout(
+        &mut self,
+        cmd: &[&str],
+        timeout: Duration,
+    ) -> Result<Option<i32>, ContainerError> {
+        let helper = self
+            .spawn_exec(cmd, &ExecOptions::new(), ExecStdio::Inherit)?
+            .helper;
+        let handle = match PidFd::open(helper as u32) {
+            Ok(handle) => handle,
+            Err(e) => {
+                kill_and_reap(helper);
+                return Err(e);
+            }
+        };
+
+        if let Some(status) = handle.wait_timeout(timeout)? {
+            return Ok(Some(status.code()));
         }
 
-        self.cgroup.freeze()?;
-        self.state = ContainerState::Paused;
+        // The command gets SIGKILL through its parent-death signal
+        handle.send_signal(libc::SIGKILL)?;
+        handle.wait()?;
+        Ok(None)
+    }
 
-        Ok(())
+    /// Start an exec health probe
+    ///
+    /// The probe's streams are `/dev/null`, so its output never reaches
+    /// the runtime's.
+    #[cfg(target_os = "linux")]
+    fn spawn_probe(&mut self, cmd: &[String]) -> Result<PidFd, ContainerError> {
+        use std::os::unix::io::AsRawFd;
+
+        let null = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/null")
+            .map_err(|e| ContainerError::IoError(format!("open /dev/null: {e}")))?;
+        let cmd: Vec<&str> = cmd.iter().map(String::as_str).collect();
+        let helper = self
+            .spawn_exec(&cmd, &ExecOptions::new(), ExecStdio::Null(null.as_raw_fd()))?
+            .helper;
+        PidFd::open(helper as u32).inspect_err(|_| kill_and_reap(helper))
     }
 
-    /// Resume a paused container
+    /// Execute a command in the container on a new pseudo-terminal
+    ///
+    /// Like `exec()`, but the command runs in a new session whose
+    /// controlling terminal, stdin, stdout and stderr are the slave of a new
+    /// pseudo-terminal sized `console_size`. Returns the master together
+    /// with a handle whose `wait()` reports the command's exit code as
+    /// `ExitStatus::Exited`.
     ///
     /// # Errors
     ///
     /// Returns an error if the operation fails.
-    pub fn resume(&mut self) -> Result<(), ContainerError> {
-        if self.state != ContainerState::Paused {
-            return Err(ContainerError::InvalidState {
-                current: self.state,
-                operation: "resume",
-            });
-        }
-
-        self.cgroup.unfreeze()?;
-        self.state = ContainerState::Running;
+    #[cfg(target_os = "linux")]
+    pub fn exec_terminal(&mut self, cmd: &[&str]) -> Result<(Console, PidFd), ContainerError> {
+        use std::os::unix::io::AsRawFd;
 
-        Ok(())
+        let pty = crate::console::Pty::open(self.config.console_size)?;
+        let stdio = ExecStdio::Terminal {
+            slave: pty.slave.as_raw_fd(),
+            master: pty.master.as_raw_fd(),
+        };
+        let helper = self.spawn_exec(cmd, &ExecOptions::new(), stdio)?.helper;
+        match PidFd::open(helper as u32) {
+            Ok(handle) => Ok((pty.master, handle)),
+            Err(e) => {
+                kill_and_reap(helper);
+                Err(e)
+            }
+        }
     }
 
-    /// Stop the container
+    /// Execute a command in the container with its streams connected to the caller
+    ///
+    /// Like `exec()`, with the environment, working directory and user
+    /// adjusted by `options`. Without `tty`, the command's stdin, stdout
+    /// and stderr are pipes available from the returned handle; with `tty`,
+    /// it runs on a new pseudo-terminal whose master is the handle's
+    /// `console`.
     ///
     /// # Errors
     ///
-    /// Returns an error if the operation fails.
+    /// Returns an error if the container is not running, or the command
+    /// cannot be set up or exec'd.
     #[cfg(target_os = "linux")]
-    pub fn stop(&mut self) -> Result<(), ContainerError> {
-        if self.state != ContainerState::Running && self.state != ContainerState::Paused {
-            return Err(ContainerError::InvalidState {
-                current: self.state,
-                operation: "stop",
-            });
-        }
+    pub fn exec_spawn(
+        &mut self,
+        cmd: &[&str],
+        options: &ExecOptions,
+    ) -> Result<ExecProcess, ContainerError> {
+        use std::os::unix::io::AsRawFd;
 
-        // Kill all processes
-        self.cgroup.kill_all()?;
+        let pty = if options.tty {
+            Some(crate::console::Pty::open(self.config.console_size)?)
+        } else {
+            None
+        };
+        let pipes = if options.tty {
+            None
+        } else {
+            Some((pipe()?, pipe()?, pipe()?))
+        };
+        let stdio = match (&pty, &pipes) {
+            (Some(pty), _) => ExecStdio::Terminal {
+                slave: pty.slave.as_raw_fd(),
+                master: pty.master.as_raw_fd(),
+            },
+            (None, Some((stdin, stdout, stderr))) => ExecStdio::Pipes {
+                command: [
+                    stdin.0.as_raw_fd(),
+                    stdout.1.as_raw_fd(),
+                    stderr.1.as_raw_fd(),
+                ],
+                runtime: [
+                    stdin.1.as_raw_fd(),
+                    stdout.0.as_raw_fd(),
+                    stderr.0.as_raw_fd(),
+                ],
+            },
+            (None, None) => ExecStdio::Inherit,
+        };
 
-        // Wait for init to exit
-        if let Some(pid) = self.init_pid {
-            // SAFETY: pid is a valid child process ID obtained from fork/clone3; status is a
-            // local stack variable passed by mutable pointer as required by waitpid(2).
-            unsafe {
-                let mut status: libc::c_int = 0;
-                libc::waitpid(pid as i32, &mut status, 0);
+        let spawned = self.spawn_exec(cmd, options, stdio)?;
+        let helper = match PidFd::open(spawned.helper as u32) {
+            Ok(handle) => handle,
+            Err(e) => {
+                kill_and_reap(spawned.helper);
+                return Err(e);
             }
-        }
+        };
 
-        self.init_pid = None;
-        self.state = ContainerState::Stopped;
+        let pid = spawned.command;
+        // Only a real pidfd is safe to signal through once the command may be reaped
+        let process = PidFd::open(pid)
+            .ok()
+            .filter(|process| process.as_raw_fd().is_some());
 
-        Ok(())
+        let mut handle = ExecProcess::new(helper, pid, process);
+        // Dropping the command's ends leaves them to the command alone
+        if let Some(pty) = pty {
+            handle.console = Some(pty.master);
+        }
+        if let Some((stdin, stdout, stderr)) = pipes {
+            handle.stdin = Some(stdin.1.into());
+            handle.stdout = Some(stdout.0.into());
+            handle.stderr = Some(stderr.0.into());
+        }
+        Ok(handle)
     }
 
-    /// Stop the container (non-Linux stub)
+    /// Fork the exec helper for `cmd` and wait until the command has exec'd
     ///
-    /// # Errors
-    ///
-    /// Returns an error if the operation fails.
-    #[cfg(not(target_os = "linux"))]
+    /// The helper and the command report the command's PID and any failure
+    /// over a close-on-exec pipe, so end-of-file means `execve(2)` succeeded.
+    #[cfg(target_os = "linux")]
+    fn spawn_exec(
+        &mut self,
+        cmd: &[&str],
+        options: &ExecOptions,
+        stdio: ExecStdio,
+    ) -> Result<SpawnedExec, ContainerError> {
+        use std::fs::{File, OpenOptions};
+        use std::os::unix::io::AsRawFd;
+
+        use crate::namespace::NamespaceFds;
+
+        self.refresh();
+        if self.state != ContainerState::Running {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "exec",
+            });
+        }
+
+        if cmd.is_empty() {
+            return Err(ContainerError::ConfigError("Empty command".into()));
+        }
+
+        let Some(init_pid) = self.pid() else {
+            return Err(ContainerError::NotFound(self.id.clone()));
+        };
+
+        // Everything the child needs is opened or allocated before fork
+        let config = options.apply(&self.config);
+        let args: Vec<String> = cmd.iter().map(|s| (*s).to_string()).collect();
+        let root_path = PathBuf::from(format!("/proc/{init_pid}/root"));
+        let spec = ExecSpec::new(&args, &config, &root_path)?;
+        let namespaces = NamespaceFds::open(init_pid)?;
+        let root = File::open(&root_path)
+            .map_err(|e| ContainerError::IoError(format!("open container root: {e}")))?;
+        let cgroup_procs = OpenOptions::new()
+            .write(true)
+            .open(self.cgroup.path().join("cgroup.procs"))
+            .map_err(|e| ContainerError::IoError(format!("open cgroup.procs: {e}")))?;
+        let workdir = to_cstring(&config.workdir.to_string_lossy())?;
+        let (report, report_end) = pipe()?;
+
+        let attach = ExecAttach {
+            namespaces: &namespaces,
+            root_fd: root.as_raw_fd(),
+            cgroup_procs_fd: cgroup_procs.as_raw_fd(),
+            workdir: &workdir,
+            stdio,
+            report: report_end.as_raw_fd(),
+        };
+
+        // SAFETY: see "Fork Safety" in the module documentation; the child only uses data
+        // prepared above and terminates with _exit(2).
+        let pid = unsafe { libc::fork() };
+
+        match pid {
+            -1 => Err(ContainerError::ProcessError(format!(
+                "fork: errno {}",
+                last_errno()
+            ))),
+            0 => {
+                let code = exec_main(&attach, &config, &spec);
+                // SAFETY: _exit(2) terminates the child without running the parent's
+                // atexit handlers or flushing inherited stdio buffers.
+                unsafe { libc::_exit(code) }
+            }
+            helper => {
+                drop(report_end);
+                match read_exec_reports(report) {
+                    Ok(Some(command)) => Ok(SpawnedExec { helper, command }),
+                    Ok(None) => {
+                        let code = wait_exit_code(helper)?;
+                        Err(ContainerError::ProcessError(format!(
+                            "exec {}: helper exited with code {code}",
+                            cmd[0]
+                        )))
+                    }
+                    Err(e) => {
+                        kill_and_reap(helper);
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute a command (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn exec(&mut self, _cmd: &[&str]) -> Result<i32, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Execute a command with a timeout (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn exec_timeout(
+        &mut self,
+        _cmd: &[&str],
+        _timeout: Duration,
+    ) -> Result<Option<i32>, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Start an exec health probe (non-Linux stub)
+    #[cfg(not(target_os = "linux"))]
+    fn spawn_probe(&mut self, _cmd: &[String]) -> Result<PidFd, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Execute a command on a terminal (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn exec_terminal(&mut self, _cmd: &[&str]) -> Result<(Console, PidFd), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Execute a command with connected streams (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn exec_spawn(
+        &mut self,
+        _cmd: &[&str],
+        _options: &ExecOptions,
+    ) -> Result<ExecProcess, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Pause the container (freeze all processes)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn pause(&mut self) -> Result<(), ContainerError> {
+        self.refresh();
+        if self.state != ContainerState::Running {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "pause",
+            });
+        }
+
+        self.cgroup.freeze()?;
+        self.state = ContainerState::Paused;
+
+        self.persist()?;
+        self.notify(ContainerEvent::Paused);
+        Ok(())
+    }
+
+    /// Resume a paused container
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn resume(&mut self) -> Result<(), ContainerError> {
+        self.refresh();
+        if self.state != ContainerState::Paused {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "resume",
+            });
+        }
+
+        self.cgroup.unfreeze()?;
+        self.state = ContainerState::Running;
+
+        self.persist()?;
+        self.notify(ContainerEvent::Resumed);
+        Ok(())
+    }
+
+    /// Stop the container immediately
+    ///
+    /// Kills every process in the cgroup via `cgroup.kill`. Use `stop_with()`
+    /// to give the workload a chance to shut down cleanly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
     pub fn stop(&mut self) -> Result<(), ContainerError> {
+        self.stop_with(libc::SIGKILL, Duration::ZERO)
+    }
+
+    /// Stop the container gracefully
+    ///
+    /// Sends `signal` to the init process and waits up to `timeout` for the
+    /// cgroup to become empty before falling back to `cgroup.kill`. A paused
+    /// container is thawed after signalling so the workload can handle it.
+    ///
+    /// Also cleans up an exited container whose cgroup may still hold
+    /// processes. The init exit status remains available via `exit_status()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(target_os = "linux")]
+    pub fn stop_with(&mut self, signal: i32, timeout: Duration) -> Result<(), ContainerError> {
+        self.refresh();
+        if !matches!(
+            self.state,
+            ContainerState::Running | ContainerState::Paused | ContainerState::Exited { .. }
+        ) {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "stop",
+            });
+        }
+
+        if let Some(init) = &self.init {
+            match init.send_signal(signal) {
+                // Already gone: nothing to wait for
+                Ok(()) | Err(ContainerError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+            if self.state == ContainerState::Paused {
+                self.cgroup.unfreeze()?;
+            }
+        }
+
+        if !self.cgroup.wait_unpopulated(timeout)? {
+            // Kill all remaining processes
+            self.cgroup.kill_all()?;
+            self.cgroup.wait_unpopulated(KILL_TIMEOUT)?;
+        }
+
+        // Reap init
+        if let Some(init) = self.init.take() {
+            let status = init.wait()?;
+            self.exit_status = Some(status);
+            self.notify(ContainerEvent::Exited(status));
+        }
+        // With the cgroup empty, the capture reaches end-of-file
+        if let Some(capture) = self.log_capture.take() {
+            capture.join();
+        }
+
+        self.state = ContainerState::Stopped;
+
+        self.persist()?;
+        self.run_poststop_hooks();
+        Ok(())
+    }
+
+    /// Stop the container gracefully (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn stop_with(&mut self, _signal: i32, _timeout: Duration) -> Result<(), ContainerError> {
         Err(ContainerError::ProcessError(
             "Container runtime requires Linux".into(),
         ))
     }
 
+    /// Send a signal to the container init process
+    ///
+    /// Delivered through the init pidfd (`pidfd_send_signal(2)`), so it can
+    /// never reach an unrelated process that reused the PID. Note that the
+    /// init of a PID namespace ignores signals it has no handler for.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container is not running or the signal is invalid.
+    pub fn kill(&mut self, signal: i32) -> Result<(), ContainerError> {
+        self.refresh();
+        let Some(init) = self
+            .init
+            .as_ref()
+            .filter(|_| matches!(self.state, ContainerState::Running | ContainerState::Paused))
+        else {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "kill",
+            });
+        };
+
+        init.send_signal(signal)
+    }
+
     /// Destroy the container
     ///
-    /// Stops the container if running and removes all resources.
+    /// Stops the container if running and removes its cgroup and persisted
+    /// state.
     ///
     /// # Errors
     ///
     /// Returns an error if the operation fails.
     pub fn destroy(mut self) -> Result<(), ContainerError> {
-        // Stop if running
-        if self.state == ContainerState::Running || self.state == ContainerState::Paused {
+        // Stop if running, or if an exited init may have left processes behind
+        if matches!(
+            self.state,
+            ContainerState::Running | ContainerState::Paused | ContainerState::Exited { .. }
+        ) {
             self.stop()?;
         }
 
         // Remove cgroup
         self.cgroup.destroy()?;
 
-        Ok(())
+        StateRecord::remove(&self.config.state_root, &self.id)
     }
 
     // Getters
@@ -610,80 +2174,1835 @@ impl Container {
     }
 
     /// Get current state
+    ///
+    /// A running container whose init process has terminated is reported as
+    /// `Exited` even before it has been reaped by `wait()` or `try_wait()`.
     #[must_use]
-    pub const fn state(&self) -> ContainerState {
+    pub fn state(&self) -> ContainerState {
+        if matches!(self.state, ContainerState::Running | ContainerState::Paused) {
+            if let Some(Ok(Some(status))) = self.init.as_ref().map(PidFd::peek_exit) {
+                return ContainerState::Exited {
+                    code: status.code(),
+                };
+            }
+        }
         self.state
     }
 
-    /// Get init process PID
-    #[must_use]
-    pub const fn pid(&self) -> Option<u32> {
-        self.init_pid
+    /// Get init process PID
+    #[must_use]
+    pub fn pid(&self) -> Option<u32> {
+        self.init.as_ref().map(PidFd::pid)
+    }
+
+    /// Init process handle, until it is reaped
+    pub(crate) const fn init_handle(&self) -> Option<&PidFd> {
+        self.init.as_ref()
+    }
+
+    /// Get how the last init process terminated, once it has been reaped
+    #[must_use]
+    pub const fn exit_status(&self) -> Option<ExitStatus> {
+        self.exit_status
+    }
+
+    /// Take the terminal master of the current run
+    ///
+    /// Only set after starting with `terminal` and no `console_socket`.
+    /// Returns `None` once taken.
+    pub fn take_console(&mut self) -> Option<Console> {
+        self.console.take()
+    }
+
+    /// Read the captured output of the init process
+    ///
+    /// Returns records newer than `since`, and of those only the last `tail`,
+    /// oldest first, across rotated files. With `follow`, the iterator then
+    /// waits for new records until the output of the current run has ended;
+    /// without a run capturing in this runtime, it ends right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if the container has no `log` configured, or
+    /// an error if the log cannot be read.
+    pub fn logs(
+        &self,
+        since: Option<SystemTime>,
+        tail: Option<usize>,
+        follow: bool,
+    ) -> Result<Logs, ContainerError> {
+        let Some(config) = self.config.log else {
+            return Err(ContainerError::ConfigError(format!(
+                "Container {} has no log configured",
+                self.id
+            )));
+        };
+        let done = self
+            .log_capture
+            .as_ref()
+            .filter(|_| follow)
+            .map(LogCapture::done);
+        Logs::open(&self.log_path(), config.max_files, since, tail, done)
+    }
+
+    /// Wait for the init process to exit
+    ///
+    /// Reaps the init process through its pidfd and moves a running
+    /// container to `Exited`. Returns immediately if it already exited.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container was never started.
+    pub fn wait(&mut self) -> Result<ExitStatus, ContainerError> {
+        if let Some(status) = self.exit_status {
+            return Ok(status);
+        }
+        let Some(init) = self.init.as_ref() else {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "wait",
+            });
+        };
+
+        let status = init.wait()?;
+        self.record_exit(status)?;
+        Ok(status)
+    }
+
+    /// Check whether the init process has exited, without blocking
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the container was never started.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, ContainerError> {
+        if let Some(status) = self.exit_status {
+            return Ok(Some(status));
+        }
+        let Some(init) = self.init.as_ref() else {
+            return Err(ContainerError::InvalidState {
+                current: self.state,
+                operation: "wait",
+            });
+        };
+
+        let status = init.try_wait()?;
+        if let Some(status) = status {
+            self.record_exit(status)?;
+        }
+        Ok(status)
+    }
+
+    /// Reap the init process if it has exited on its own
+    fn refresh(&mut self) {
+        if self.init.is_some() {
+            let _ = self.try_wait();
+        }
+    }
+
+    /// Record the init exit status, release its handle and persist the state
+    fn record_exit(&mut self, status: ExitStatus) -> Result<(), ContainerError> {
+        self.init = None;
+        self.exit_status = Some(status);
+        if matches!(self.state, ContainerState::Running | ContainerState::Paused) {
+            self.state = ContainerState::Exited {
+                code: status.code(),
+            };
+        }
+        self.notify(ContainerEvent::Exited(status));
+        self.persist()
+    }
+
+    /// Failures of `poststart` and `poststop` hooks since the last start
+    ///
+    /// These hooks run after the lifecycle operation has taken effect, so
+    /// their failures do not fail `start()` or `stop()`.
+    #[must_use]
+    pub fn hook_errors(&self) -> &[ContainerError] {
+        &self.hook_errors
+    }
+
+    /// OCI state passed to hooks on stdin
+    fn hook_state(&self, status: &str, pid: Option<u32>) -> String {
+        // Bundles conventionally hold the root filesystem in `<bundle>/rootfs`
+        let bundle = self.config.rootfs.parent().unwrap_or(Path::new("/"));
+        crate::hooks::state_json(&self.id, status, pid, bundle, &self.config.labels)
+    }
+
+    /// Run every hook of a post phase, recording failures
+    fn run_post_hooks(&mut self, phase: HookPhase, status: &str) {
+        let hooks = self.config.hooks.get(phase);
+        if hooks.is_empty() {
+            return;
+        }
+        let state = self.hook_state(status, self.pid());
+        for hook in hooks {
+            if let Err(e) = hook.run(phase, &state, None) {
+                self.hook_errors.push(e);
+            }
+        }
+    }
+
+    /// Run `poststop` hooks once per run
+    fn run_poststop_hooks(&mut self) {
+        if core::mem::take(&mut self.poststop_pending) {
+            self.run_post_hooks(HookPhase::Poststop, "stopped");
+        }
+    }
+
+    /// Get the health status
+    ///
+    /// `None` unless a health check is configured and the container is
+    /// running or paused.
+    #[must_use]
+    pub fn health(&self) -> Option<HealthStatus> {
+        if !matches!(
+            self.state(),
+            ContainerState::Running | ContainerState::Paused
+        ) {
+            return None;
+        }
+        self.health.map(|h| h.status)
+    }
+
+    /// Register a callback for health status changes
+    ///
+    /// The callback receives the container ID and the new status. It runs
+    /// on the thread calling `start()` or `check_health()`, or on a
+    /// supervisor's probe thread, so it should return quickly.
+    pub fn on_health_change(&mut self, callback: impl FnMut(&str, HealthStatus) + Send + 'static) {
+        self.health_callback = Some(Box::new(callback));
+    }
+
+    /// Run the configured health probe if it is due
+    ///
+    /// Does nothing unless the container is running and a health check is
+    /// configured. Probes run on the calling thread and may block for up to
+    /// the check's timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an exec probe cannot be started.
+    pub fn check_health(&mut self) -> Result<Option<HealthStatus>, ContainerError> {
+        let Some(probe) = self.start_health_probe()? else {
+            return Ok(self.health());
+        };
+        let healthy = probe.run();
+        Ok(self.finish_health_probe(&probe, healthy))
+    }
+
+    /// Start the configured health probe if it is due
+    ///
+    /// The probe is waited for by `PendingProbe::run()` and its result
+    /// recorded by `finish_health_probe()`; no other probe starts meanwhile.
+    pub(crate) fn start_health_probe(&mut self) -> Result<Option<PendingProbe>, ContainerError> {
+        self.refresh();
+        let (Some(check), Some(mut health)) = (self.config.health_check.clone(), self.health)
+        else {
+            return Ok(None);
+        };
+        let now = Instant::now();
+        if self.state != ContainerState::Running || !health.due(&check, now) {
+            return Ok(None);
+        }
+
+        let target = match &check.probe {
+            HealthProbe::Exec(cmd) => match self.spawn_probe(cmd) {
+                Ok(handle) => ProbeTarget::Exec(handle, check.timeout),
+                // A probe command that cannot be set up or exec'd fails the probe
+                Err(ContainerError::Init { .. }) => ProbeTarget::Failed,
+                Err(e) => return Err(e),
+            },
+            HealthProbe::File(path) => match self.pid() {
+                Some(pid) => ProbeTarget::File(pid, path.clone()),
+                None => ProbeTarget::Failed,
+            },
+            HealthProbe::Tcp(addr) => ProbeTarget::Tcp(*addr, check.timeout),
+        };
+
+        health.begin(now);
+        self.health = Some(health);
+        Ok(Some(PendingProbe::new(&health, now, target)))
+    }
+
+    /// Record the result of a probe from `start_health_probe()`
+    ///
+    /// Results of probes started before the container was last (re)started
+    /// are dropped.
+    pub(crate) fn finish_health_probe(
+        &mut self,
+        probe: &PendingProbe,
+        healthy: bool,
+    ) -> Option<HealthStatus> {
+        let (Some(check), Some(mut health)) = (self.config.health_check.clone(), self.health)
+        else {
+            return None;
+        };
+        if health.owns(probe) {
+            let previous = health.status;
+            let status = health.record(&check, probe.at, healthy);
+            self.health = Some(health);
+            if status != previous {
+                self.notify_health(status);
+            }
+        }
+        self.health()
+    }
+
+    /// Start a new round of health checks for a freshly started init
+    fn reset_health(&mut self) {
+        if self.config.health_check.is_none() {
+            return;
+        }
+        let previous = self.health.map(|h| h.status);
+        self.health = Some(HealthState::new(Instant::now()));
+        if previous != Some(HealthStatus::Starting) {
+            self.notify_health(HealthStatus::Starting);
+        }
+    }
+
+    fn notify_health(&mut self, status: HealthStatus) {
+        if let Some(callback) = self.health_callback.as_mut() {
+            callback(&self.id, status);
+        }
+    }
+
+    /// Get configuration
+    #[must_use]
+    pub const fn config(&self) -> &ContainerConfig {
+        &self.config
+    }
+
+    /// Get creation time (seconds since the Unix epoch)
+    #[must_use]
+    pub const fn created(&self) -> u64 {
+        self.created
+    }
+
+    /// Get cgroup path
+    #[must_use]
+    pub fn cgroup_path(&self) -> &Path {
+        self.cgroup.path()
+    }
+
+    /// Get current memory usage
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn memory_usage(&self) -> Result<u64, ContainerError> {
+        Ok(self.cgroup.memory_current()?)
+    }
+
+    /// Get current CPU usage
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn cpu_usage(&self) -> Result<u64, ContainerError> {
+        Ok(self.cgroup.cpu_usage_us()?)
+    }
+
+    /// Take a snapshot of resource usage
+    ///
+    /// Collects CPU, memory, I/O and task accounting of the container's
+    /// cgroup and, for containers with their own network, the traffic of
+    /// the veth pair.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cgroup accounting cannot be read.
+    pub fn stats(&self) -> Result<ContainerStats, ContainerError> {
+        let veth = (self.config.network && self.config.namespaces.contains(NamespaceFlags::NEWNET))
+            .then(|| {
+                self.config.network_config.as_ref().map_or_else(
+                    || NetworkConfig::from_container_id(&self.id, 0).veth_host,
+                    |n| n.veth_host.clone(),
+                )
+            });
+        Ok(crate::stats::collect(&self.cgroup, veth.as_deref())?)
+    }
+
+    /// List the processes of the container
+    ///
+    /// Like `ps` inside the container: each process is reported with its
+    /// host and in-container PID, and its UID and user name as seen in the
+    /// container.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` unless the container is running or paused, or
+    /// an error if the cgroup's process list cannot be read.
+    pub fn top(&self) -> Result<Vec<ProcessInfo>, ContainerError> {
+        let init_pid = self.live_init("list processes of")?;
+        crate::top::list(&self.cgroup, init_pid)
+    }
+
+    /// PID of the init process of a running or paused container
+    fn live_init(&self, operation: &'static str) -> Result<u32, ContainerError> {
+        let current = self.state();
+        match (current, self.pid()) {
+            (ContainerState::Running | ContainerState::Paused, Some(init_pid)) => Ok(init_pid),
+            _ => Err(ContainerError::InvalidState { current, operation }),
+        }
+    }
+
+    /// Copy a host file or directory into the running container
+    ///
+    /// As with `cp -r`, if `container_path` is an existing directory the
+    /// copy is placed inside it under the name of `host_path`; otherwise it
+    /// is created as `container_path`. Container paths are resolved through
+    /// `/proc/<init>/root`, so they see the container's mounts and symlinks
+    /// cannot lead out of its root.
+    ///
+    /// Directories are copied recursively as a tar stream. Modes,
+    /// modification times and numeric ownership are preserved; IDs are kept
+    /// as seen inside the container, so a host file owned by UID 1000 is
+    /// owned by UID 1000 in the container, whatever its user namespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` unless the container is running or paused, or
+    /// an error if a path does not exist or a file cannot be copied.
+    #[cfg(target_os = "linux")]
+    pub fn copy_in(&self, host_path: &Path, container_path: &Path) -> Result<(), ContainerError> {
+        use crate::archive::{copy_tree, Owners};
+
+        let init_pid = self.live_init("copy into")?;
+        let (source_dir, source) = open_host_parent(host_path)?;
+        let (dir, name) = resolve_copy_target(&proc_root(init_pid), container_path, &source)?;
+        copy_tree(
+            &source_dir,
+            &source,
+            &Owners::identity(),
+            &dir,
+            &name,
+            &Owners::of_process(init_pid),
+        )
+        .map_err(|e| copy_error(host_path, &e))
+    }
+
+    /// Copy a file or directory out of the running container
+    ///
+    /// The counterpart of `copy_in()`: `host_path` is treated like the
+    /// target of `cp -r`, and ownership is written with the IDs seen inside
+    /// the container.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` unless the container is running or paused, or
+    /// an error if a path does not exist or a file cannot be copied.
+    #[cfg(target_os = "linux")]
+    pub fn copy_out(&self, container_path: &Path, host_path: &Path) -> Result<(), ContainerError> {
+        use crate::archive::{copy_tree, Owners};
+
+        let init_pid = self.live_init("copy from")?;
+        let (dir, name) = open_copy_source(&proc_root(init_pid), container_path)?;
+        let (dest, dest_name) = if host_path.is_dir() {
+            let dest = std::fs::File::open(host_path).map_err(|e| copy_error(host_path, &e))?;
+            (dest, name.clone())
+        } else {
+            open_host_parent(host_path)?
+        };
+        copy_tree(
+            &dir,
+            &name,
+            &Owners::of_process(init_pid),
+            &dest,
+            &dest_name,
+            &Owners::identity(),
+        )
+        .map_err(|e| copy_error(container_path, &e))
+    }
+
+    /// Extract a tar archive into a directory of the running container
+    ///
+    /// Entries are created relative to `container_dir`, which must exist.
+    /// Entry paths with `..` and entries that would be written through a
+    /// symbolic link are rejected. Archive IDs are taken as seen inside the
+    /// container.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` unless the container is running or paused, or
+    /// an error if the archive is malformed or cannot be extracted.
+    #[cfg(target_os = "linux")]
+    pub fn copy_in_archive(
+        &self,
+        archive: impl std::io::Read,
+        container_dir: &Path,
+    ) -> Result<(), ContainerError> {
+        use crate::archive::{unpack, Owners, TarReader};
+
+        let init_pid = self.live_init("copy into")?;
+        let dir = crate::rootfs::open_dir_in_root(&proc_root(init_pid), container_dir)
+            .map_err(|e| copy_error(container_dir, &e))?;
+        unpack(
+            &mut TarReader::new(archive),
+            &dir,
+            &Owners::of_process(init_pid),
+        )
+        .map_err(|e| copy_error(container_dir, &e))
+    }
+
+    /// Write a file or directory of the running container as a tar archive
+    ///
+    /// The archive holds `container_path` under its own name, or the
+    /// contents of the root as `.` for `/`, with IDs as seen inside the
+    /// container.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` unless the container is running or paused, or
+    /// an error if the path does not exist or the archive cannot be written.
+    #[cfg(target_os = "linux")]
+    pub fn copy_out_archive(
+        &self,
+        container_path: &Path,
+        archive: impl std::io::Write,
+    ) -> Result<(), ContainerError> {
+        use crate::archive::{Owners, Packer, TarWriter};
+
+        let init_pid = self.live_init("copy from")?;
+        let (dir, name) = open_copy_source(&proc_root(init_pid), container_path)?;
+        let owners = Owners::of_process(init_pid);
+        let mut tar = TarWriter::new(archive);
+        Packer::new(&owners)
+            .pack(&mut tar, &dir, &name, Path::new(&name))
+            .and_then(|()| tar.finish().map(drop))
+            .map_err(|e| copy_error(container_path, &e))
+    }
+
+    /// Write the container's root filesystem as a tar archive
+    ///
+    /// A running or paused container is read through `/proc/<init>/root`,
+    /// so the archive holds the root as its processes see it, including
+    /// changes made since start; pause the container for a consistent
+    /// snapshot. Otherwise the configured rootfs directory is read. Entries
+    /// are opened relative to their parent directory without following
+    /// symbolic links, so processes in the container cannot make the export
+    /// read host files.
+    ///
+    /// Only the root filesystem itself is archived: mount points such as
+    /// `/proc` appear as empty directories. Ownership (as seen inside the
+    /// container), permissions, modification times, extended attributes,
+    /// symbolic and hard links, device nodes and FIFOs are preserved.
+    /// `RootFs::import()` turns the archive back into a root filesystem.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` if the container uses the host root, or an
+    /// error if a file cannot be read or the archive cannot be written.
+    #[cfg(target_os = "linux")]
+    pub fn export(&self, archive: impl std::io::Write) -> Result<(), ContainerError> {
+        use crate::archive::{Owners, Packer, TarWriter};
+
+        if self.config.rootfs == Path::new("/") {
+            return Err(ContainerError::ConfigError(
+                "Cannot export the host root filesystem".into(),
+            ));
+        }
+        let (root, owners) = match self.live_init("export") {
+            Ok(init_pid) => (proc_root(init_pid), Owners::of_process(init_pid)),
+            Err(_) => {
+                let owners = self
+                    .config
+                    .id_mappings()
+                    .map_or_else(Owners::identity, |(uid, gid)| {
+                        Owners::from_mappings(uid, gid)
+                    });
+                (self.config.rootfs.clone(), owners)
+            }
+        };
+
+        let mut tar = TarWriter::new(archive);
+        std::fs::File::open(&root)
+            .and_then(|dir| {
+                Packer::new(&owners).one_file_system().pack(
+                    &mut tar,
+                    &dir,
+                    std::ffi::OsStr::new("."),
+                    Path::new("."),
+                )
+            })
+            .and_then(|()| tar.finish().map(drop))
+            .map_err(|e| ContainerError::IoError(format!("export {}: {e}", self.id)))
+    }
+
+    /// Copy into the container (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn copy_in(&self, _host_path: &Path, _container_path: &Path) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Copy out of the container (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn copy_out(
+        &self,
+        _container_path: &Path,
+        _host_path: &Path,
+    ) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Extract an archive into the container (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn copy_in_archive(
+        &self,
+        _archive: impl std::io::Read,
+        _container_dir: &Path,
+    ) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Archive a path of the container (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn copy_out_archive(
+        &self,
+        _container_path: &Path,
+        _archive: impl std::io::Write,
+    ) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Export the root filesystem (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn export(&self, _archive: impl std::io::Write) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+
+    /// Update CPU limits
+    ///
+    /// Shorthand for `update()` with only CPU limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn update_cpu(&mut self, config: &CpuConfig) -> Result<(), ContainerError> {
+        self.update(&Resources::new().cpu(*config))
+    }
+
+    /// Update memory limits
+    ///
+    /// Shorthand for `update()` with only memory limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    pub fn update_memory(&mut self, config: &MemoryConfig) -> Result<(), ContainerError> {
+        self.update(&Resources::new().memory(*config))
+    }
+
+    /// Change several resource limits at once
+    ///
+    /// Either every limit in `resources` is applied or, if a cgroup write
+    /// fails, the previous values are restored and the error is returned.
+    /// On success the configuration is updated and persisted, and a
+    /// `CgroupUpdated` event is reported to the `on_event()` callback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a limit cannot be applied or the state cannot be
+    /// persisted.
+    pub fn update(&mut self, resources: &Resources) -> Result<(), ContainerError> {
+        if resources.is_empty() {
+            return Ok(());
+        }
+        self.cgroup.update(resources)?;
+
+        if let Some(cpu) = resources.cpu {
+            self.config.cpu = cpu;
+        }
+        if let Some(memory) = resources.memory {
+            self.config.memory = memory;
+        }
+        if let Some(io) = &resources.io {
+            self.config.io = Some(io.clone());
+        }
+        if let Some(pids) = resources.pids {
+            self.config.pids = pids;
+        }
+        if let Some(cpuset) = &resources.cpuset {
+            self.config.cpuset = Some(cpuset.clone());
+        }
+        self.persist()?;
+
+        self.emit(ContainerEventKind::CgroupUpdated, &resources.to_string());
+        Ok(())
+    }
+
+    /// Register a callback for audit events
+    ///
+    /// The callback receives the container ID, the event kind and a payload
+    /// describing the change, e.g. to append them to a
+    /// [`StateChangeTrail`](crate::StateChangeTrail). It runs on the thread
+    /// performing the operation, so it should return quickly.
+    pub fn on_event(
+        &mut self,
+        callback: impl FnMut(&str, ContainerEventKind, &str) + Send + 'static,
+    ) {
+        self.event_callback = Some(Box::new(callback));
+    }
+
+    /// Report an audit event to the registered callback
+    fn emit(&mut self, kind: ContainerEventKind, payload: &str) {
+        if let Some(callback) = self.event_callback.as_mut() {
+            callback(&self.id, kind, payload);
+        }
+    }
+
+    /// Watch this container's lifecycle events
+    ///
+    /// Besides the transitions performed through this handle, the monitor
+    /// reports OOM kills, `memory.high` throttling and the cgroup becoming
+    /// empty, and sees the init process exit without `wait()` being called.
+    /// Use [`ContainerManager::subscribe()`](crate::manager::ContainerManager::subscribe)
+    /// or [`EventMonitor::watch()`] to watch several containers at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the monitor cannot be created or the cgroup
+    /// files cannot be watched.
+    pub fn subscribe(&mut self) -> Result<EventMonitor, ContainerError> {
+        let mut monitor = EventMonitor::new()?;
+        monitor.watch(self)?;
+        Ok(monitor)
+    }
+
+    /// Report lifecycle events to `sender`, once per monitor
+    pub(crate) fn add_event_sender(&mut self, sender: EventSender) {
+        if !self
+            .event_senders
+            .iter()
+            .any(|s| s.is_same_monitor(&sender))
+        {
+            self.event_senders.push(sender);
+        }
+    }
+
+    /// Report a lifecycle event to every subscribed monitor
+    fn notify(&mut self, event: ContainerEvent) {
+        let init = &self.init;
+        let id = &self.id;
+        // Monitors that have been dropped are forgotten
+        self.event_senders.retain(|sender| {
+            sender.send(Notice::Event {
+                id: id.clone(),
+                event,
+                init: match event {
+                    ContainerEvent::Started { .. } => init.as_ref().and_then(PidFd::try_clone),
+                    _ => None,
+                },
+            })
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+#[allow(clippy::missing_fields_in_debug)]
+impl fmt::Debug for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Container")
+            .field("id", &self.id)
+            .field("state", &self.state)
+            .field("init", &self.init)
+            .field("exit_status", &self.exit_status)
+            .field("created", &self.created)
+            .field("health", &self.health.map(|h| h.status))
+            .finish()
+    }
+}
+
+// ============================================================================
+// Spawner Thread
+// ============================================================================
+
+/// Job run on the spawner thread
+#[cfg(target_os = "linux")]
+type SpawnJob = Box<dyn FnOnce() + Send>;
+
+/// Sender to the spawner thread, with the PID of the process it runs in
+#[cfg(target_os = "linux")]
+static SPAWNER: Mutex<Option<(u32, std::sync::mpsc::Sender<SpawnJob>)>> = Mutex::new(None);
+
+/// Run `f` on a thread that lives as long as the runtime process
+///
+/// The kernel sends a child's parent-death signal when the *thread* that
+/// created it exits. Init processes are cloned here, so their signal means
+/// that the runtime exited, not that the thread calling `start()` did. The
+/// thread is started on first use, and again in a forked runtime.
+///
+/// # Errors
+///
+/// Returns an error if the thread cannot be started.
+#[cfg(target_os = "linux")]
+fn on_spawner_thread<R: Send>(f: impl FnOnce() -> R + Send) -> Result<R, ContainerError> {
+    use std::sync::mpsc;
+
+    let (done, result) = mpsc::sync_channel(1);
+    let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+        let _ = done.send(std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)));
+    });
+    // SAFETY: only the lifetime bound changes. The job borrows from this frame, which does
+    // not return before the job has run or was dropped: `result.recv()` below waits for
+    // either, as dropping the job drops `done`.
+    let job: SpawnJob = unsafe { core::mem::transmute(job) };
+
+    let mut spawner = SPAWNER
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let pid = std::process::id();
+    let jobs = match spawner.as_ref() {
+        Some((owner, jobs)) if *owner == pid => jobs.clone(),
+        // Not started yet, or started by the process this one was forked from
+        _ => {
+            let (jobs, queue) = mpsc::channel::<SpawnJob>();
+            std::thread::Builder::new()
+                .name("alice-container-spawner".into())
+                .spawn(move || queue.into_iter().for_each(|job| job()))
+                .map_err(|e| ContainerError::ProcessError(format!("spawn spawner thread: {e}")))?;
+            *spawner = Some((pid, jobs.clone()));
+            jobs
+        }
+    };
+    drop(spawner);
+    jobs.send(job)
+        .map_err(|_| ContainerError::ProcessError("spawner thread exited".into()))?;
+
+    match result.recv() {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(panic)) => std::panic::resume_unwind(panic),
+        Err(_) => Err(ContainerError::ProcessError("spawner thread exited".into())),
+    }
+}
+
+// ============================================================================
+// Container Init (child side)
+// ============================================================================
+
+/// Stack size for the legacy `clone(2)` init path
+#[cfg(target_os = "linux")]
+const INIT_STACK_SIZE: usize = 1024 * 1024;
+
+/// How long `stop_with()` waits for the cgroup to drain after `cgroup.kill`
+#[cfg(target_os = "linux")]
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Exit code used by the init process when setup or exec fails
+#[cfg(target_os = "linux")]
+const INIT_FAILURE_EXIT_CODE: i32 = 127;
+
+/// Pre-built `execve(2)` arguments for a container process
+///
+/// Built in the parent so that configuration errors surface from `start()`
+/// or `exec()` and the child does not need to allocate argv/envp after cloning.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct ExecSpec {
+    /// Candidate program paths, tried in order
+    programs: Vec<CString>,
+    /// Argument vector
+    argv: Vec<CString>,
+    /// Environment (`KEY=VALUE`)
+    envp: Vec<CString>,
+    /// Identity with names resolved to IDs
+    user: ProcessUser,
+    /// Profile installed before exec, which sets `no_new_privs`
+    seccomp: SeccompProfile,
+    /// pidfd of the runtime, if the kernel has `pidfd_open(2)`
+    runtime: Option<OwnedFd>,
+}
+
+#[cfg(target_os = "linux")]
+impl ExecSpec {
+    /// Build the exec arguments for the init process of a container
+    ///
+    /// User names are resolved against the root filesystem the init process
+    /// will pivot into.
+    fn init(config: &ContainerConfig) -> Result<Self, ContainerError> {
+        let root = if config.namespaces.contains(NamespaceFlags::NEWNS) {
+            config.rootfs.as_path()
+        } else {
+            Path::new("/")
+        };
+        Ok(Self {
+            runtime: own_pidfd(),
+            ..Self::new(&config.args, config, root)?
+        })
+    }
+
+    /// Build the exec arguments for `args` with the environment of a container
+    ///
+    /// User names are resolved against `root`, the container root as seen
+    /// from the runtime.
+    fn new(args: &[String], config: &ContainerConfig, root: &Path) -> Result<Self, ContainerError> {
+        let Some(program) = args.first() else {
+            return Err(ContainerError::ConfigError("Empty command".into()));
+        };
+
+        let argv = args
+            .iter()
+            .map(|a| to_cstring(a))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let envp = config
+            .env
+            .iter()
+            .map(|(k, v)| to_cstring(&format!("{k}={v}")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let programs = resolve_program(program, config)
+            .iter()
+            .map(|p| to_cstring(p))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            programs,
+            argv,
+            envp,
+            user: config.user.resolve(root)?,
+            // No filter yet; see `SeccompProfile::install`
+            seccomp: SeccompProfile::new(SeccompAction::Allow),
+            runtime: None,
+        })
+    }
+}
+
+/// Convert a configuration string to a `CString`
+#[cfg(target_os = "linux")]
+fn to_cstring(s: &str) -> Result<CString, ContainerError> {
+    CString::new(s).map_err(|_| ContainerError::ConfigError(format!("Interior NUL byte in {s:?}")))
+}
+
+/// Candidate paths for the init program
+///
+/// Names containing a `/` are used as-is; bare names are looked up in the
+/// `PATH` of the container environment (evaluated inside the container root).
+#[cfg(target_os = "linux")]
+fn resolve_program(program: &str, config: &ContainerConfig) -> Vec<String> {
+    if program.contains('/') {
+        return vec![program.to_string()];
+    }
+
+    let path = config
+        .env
+        .iter()
+        .find(|(k, _)| k == "PATH")
+        .map_or("/usr/local/bin:/usr/bin:/bin", |(_, v)| v.as_str());
+
+    path.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| format!("{}/{program}", dir.trim_end_matches('/')))
+        .collect()
+}
+
+/// Wait for a freshly cloned init process to report that it is running
+///
+/// Closes the parent's copy of the child's end first so that a child that
+/// dies early is seen as end-of-file.
+#[cfg(target_os = "linux")]
+fn init_ready(
+    init: PidFd,
+    mut channel: crate::handshake::InitChannel,
+) -> Result<(PidFd, crate::handshake::InitChannel), ContainerError> {
+    channel.close_child_end();
+    match channel.wait_ready() {
+        Ok(()) => Ok((init, channel)),
+        Err(e) => {
+            kill_and_reap(init.pid() as libc::pid_t);
+            Err(e)
+        }
+    }
+}
+
+/// Standard streams of the init process, as descriptors inherited over clone
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+enum InitStdio {
+    /// The runtime's own streams
+    Inherit,
+    /// Slave of the init's pseudo-terminal
+    Terminal(RawFd),
+    /// Write ends of the log pipes
+    Log { stdout: RawFd, stderr: RawFd },
+}
+
+/// Make the log pipes the init's stdout and stderr
+///
+/// stdin is left as inherited. The pipes are close-on-exec, their copies
+/// on 1 and 2 are not.
+#[cfg(target_os = "linux")]
+fn redirect_output(stdout: RawFd, stderr: RawFd) -> Result<(), ContainerError> {
+    for (fd, target) in [(stdout, libc::STDOUT_FILENO), (stderr, libc::STDERR_FILENO)] {
+        // SAFETY: both descriptors are open in the child; dup2(2) does not touch memory.
+        if unsafe { libc::dup2(fd, target) } < 0 {
+            return Err(ContainerError::IoError(format!(
+                "dup2: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Entry point of the container init process
+///
+/// Waits for the runtime, then sets up the container and execs the
+/// workload. Failures are reported over the init channel; returns the exit
+/// code to use in that case.
+#[cfg(target_os = "linux")]
+fn init_main(
+    config: &ContainerConfig,
+    spec: &ExecSpec,
+    stdio: InitStdio,
+    channel: &crate::handshake::InitChannel,
+) -> i32 {
+    channel.close_parent_end_in_child();
+
+    let Err(e) = channel
+        .send_ready()
+        .and_then(|()| channel.wait_continue())
+        .map_err(|e| e.at(InitStage::Sync))
+        .and_then(|()| init_container(config, spec, stdio, channel));
+    channel.send_error(&e);
+    INIT_FAILURE_EXIT_CODE
+}
+
+/// Set up the container environment and exec the workload
+///
+/// Runs inside the new namespaces. Only returns if a step fails, with the
+/// error attributed to the failing `InitStage`. `stdio` are the streams
+/// prepared by the runtime for the workload.
+#[cfg(target_os = "linux")]
+fn init_container(
+    config: &ContainerConfig,
+    spec: &ExecSpec,
+    stdio: InitStdio,
+    channel: &crate::handshake::InitChannel,
+) -> Result<Infallible, ContainerError> {
+    use std::os::unix::io::AsRawFd;
+
+    use crate::namespace::Namespaces;
+
+    if config.namespaces.contains(NamespaceFlags::NEWNS) {
+        setup_mounts(config).map_err(|e| e.at(InitStage::Mount))?;
+    }
+
+    if config.namespaces.contains(NamespaceFlags::NEWUTS) {
+        Namespaces::new(config.namespaces)
+            .set_hostname(&config.hostname)
+            .map_err(|e| ContainerError::from(e).at(InitStage::Hostname))?;
+    }
+
+    match stdio {
+        InitStdio::Inherit => {}
+        InitStdio::Terminal(slave) => {
+            crate::console::attach_slave(slave).map_err(|e| e.at(InitStage::Terminal))?;
+        }
+        InitStdio::Log { stdout, stderr } => {
+            redirect_output(stdout, stderr).map_err(|e| e.at(InitStage::Stdio))?;
+        }
+    }
+
+    // Before dropping capabilities, so hard limits can still be raised
+    crate::rlimit::apply_all(&config.rlimits).map_err(|e| e.at(InitStage::Rlimits))?;
+    config
+        .capabilities
+        .drop_bounding()
+        .map_err(|e| e.at(InitStage::Capabilities))?;
+    apply_user(&spec.user).map_err(|e| e.at(InitStage::User))?;
+    config
+        .capabilities
+        .apply()
+        .map_err(|e| e.at(InitStage::Capabilities))?;
+
+    std::env::set_current_dir(&config.workdir).map_err(|e| {
+        ContainerError::ConfigError(format!(
+            "Working directory {}: {e}",
+            config.workdir.display()
+        ))
+        .at(InitStage::Workdir)
+    })?;
+
+    if !config.hooks.start_container.is_empty() {
+        // The runtime runs startContainer hooks now
+        channel
+            .send_created()
+            .and_then(|()| channel.wait_continue())
+            .map_err(|e| e.at(InitStage::Sync))?;
+    }
+
+    // Nothing of the runtime's process state reaches the workload
+    reset_signals().map_err(|e| e.at(InitStage::Signals))?;
+    if let Some(signal) = config.parent_death_signal {
+        // After the credential change, which clears it
+        let runtime = spec.runtime.as_ref().map(AsRawFd::as_raw_fd);
+        set_parent_death_signal(signal, runtime).map_err(|e| e.at(InitStage::Signals))?;
+    }
+    seal_inherited_fds(&config.preserve_fds).map_err(|e| e.at(InitStage::Fds))?;
+    spec.seccomp
+        .install()
+        .map_err(|e| ContainerError::ProcessError(e.to_string()).at(InitStage::Seccomp))?;
+
+    if config.mini_init {
+        crate::mini_init::fork_workload(channel).map_err(|e| e.at(InitStage::Exec))?;
+    }
+
+    exec_spec(spec).map_err(|e| e.at(InitStage::Exec))
+}
+
+/// Switch to the container root and mount a fresh `/proc`
+#[cfg(target_os = "linux")]
+fn setup_mounts(config: &ContainerConfig) -> Result<(), ContainerError> {
+    use crate::namespace::pivot_root;
+    use crate::rootfs::{mount, mount_flags, mount_proc, RootFs};
+
+    if config.rootfs == Path::new("/") {
+        // No root switch: just keep our mounts from propagating to the host
+        mount(
+            None,
+            Path::new("/"),
+            None,
+            mount_flags::MS_REC | mount_flags::MS_PRIVATE,
+            None,
+        )?;
+    } else {
+        let rootfs = RootFs::open(&config.rootfs)?;
+        let put_old = rootfs.prepare_pivot()?;
+        pivot_root(rootfs.path(), &put_old)?;
+        std::env::set_current_dir("/").map_err(|e| ContainerError::IoError(e.to_string()))?;
+    }
+
+    // A fresh /proc reflecting the new PID namespace
+    if config.namespaces.contains(NamespaceFlags::NEWPID) {
+        mount_proc(Path::new("/proc"))?;
+    }
+
+    if config.rootfs != Path::new("/") {
+        RootFs::cleanup_old_root()?;
+
+        if config.readonly_rootfs {
+            mount(
+                None,
+                Path::new("/"),
+                None,
+                mount_flags::MS_REMOUNT | mount_flags::MS_BIND | mount_flags::MS_RDONLY,
+                None,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `/proc/<pid>/root`, the root directory of a process
+#[cfg(target_os = "linux")]
+fn proc_root(pid: u32) -> PathBuf {
+    PathBuf::from(format!("/proc/{pid}/root"))
+}
+
+/// Parent directory and name of a host path to copy from or to
+#[cfg(target_os = "linux")]
+fn open_host_parent(path: &Path) -> Result<(std::fs::File, std::ffi::OsString), ContainerError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| ContainerError::ConfigError(format!("No file name: {}", path.display())))?;
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let dir =
+        std::fs::File::open(parent.unwrap_or(Path::new("."))).map_err(|e| copy_error(path, &e))?;
+    Ok((dir, name.to_os_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn copy_error(path: &Path, e: &std::io::Error) -> ContainerError {
+    ContainerError::IoError(format!("copy {}: {e}", path.display()))
+}
+
+/// Directory and name to copy `name` to, for `cp -r <name> <dest>` in `root`
+///
+/// An existing directory `dest` receives the copy as `name`, anything else
+/// is created as the last component of `dest` in its parent.
+#[cfg(target_os = "linux")]
+fn resolve_copy_target(
+    root: &Path,
+    dest: &Path,
+    name: &std::ffi::OsStr,
+) -> Result<(std::fs::File, std::ffi::OsString), ContainerError> {
+    use crate::rootfs::open_dir_in_root;
+
+    match open_dir_in_root(root, dest) {
+        Ok(dir) => Ok((dir, name.to_os_string())),
+        Err(e)
+            if e.kind() == std::io::ErrorKind::NotFound
+                || e.raw_os_error() == Some(libc::ENOTDIR) =>
+        {
+            let (Some(parent), Some(dest_name)) = (dest.parent(), dest.file_name()) else {
+                return Err(copy_error(dest, &e));
+            };
+            let dir = open_dir_in_root(root, parent).map_err(|e| copy_error(dest, &e))?;
+            Ok((dir, dest_name.to_os_string()))
+        }
+        Err(e) => Err(copy_error(dest, &e)),
+    }
+}
+
+/// Parent directory and name of `source` in `root`, `.` for the root itself
+///
+/// Only the parent is resolved, so a symbolic link named by `source` is
+/// copied as a link.
+#[cfg(target_os = "linux")]
+fn open_copy_source(
+    root: &Path,
+    source: &Path,
+) -> Result<(std::fs::File, std::ffi::OsString), ContainerError> {
+    let (parent, name) = match (source.parent(), source.file_name()) {
+        (Some(parent), Some(name)) => (parent, name),
+        _ => (Path::new("/"), std::ffi::OsStr::new(".")),
+    };
+    let dir = crate::rootfs::open_dir_in_root(root, parent).map_err(|e| copy_error(source, &e))?;
+    Ok((dir, name.to_os_string()))
+}
+
+/// Handles used by an exec'd process to attach to a running container
+#[cfg(target_os = "linux")]
+struct ExecAttach<'a> {
+    /// Namespaces of the container init process
+    namespaces: &'a crate::namespace::NamespaceFds,
+    /// `/proc/<init>/root` of the container
+    root_fd: libc::c_int,
+    /// Writable `cgroup.procs` of the container cgroup
+    cgroup_procs_fd: libc::c_int,
+    /// Working directory inside the container
+    workdir: &'a CString,
+    /// Standard streams of the command
+    stdio: ExecStdio,
+    /// Pipe receiving the reports of the helper and the command
+    report: RawFd,
+}
+
+/// Exec helper forked by `spawn_exec()`
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+struct SpawnedExec {
+    /// Exec helper, whose exit code is the command's
+    helper: libc::pid_t,
+    /// PID of the command in the runtime's PID namespace
+    command: u32,
+}
+
+/// Exec report: the helper forked the command, followed by its PID
+#[cfg(target_os = "linux")]
+const REPORT_PID: u8 = 1;
+
+/// Exec report: attaching or exec failed, followed by the encoded error
+#[cfg(target_os = "linux")]
+const REPORT_ERROR: u8 = 2;
+
+/// Send one report to `spawn_exec()`
+///
+/// Each report is a tag, a native-endian `u16` length and the payload,
+/// written at once and truncated to `PIPE_BUF`, so reports of the helper
+/// and the command never interleave.
+#[cfg(target_os = "linux")]
+fn send_report(fd: RawFd, tag: u8, payload: &[u8]) {
+    let payload = &payload[..payload.len().min(libc::PIPE_BUF - 3)];
+    let len = (payload.len() as u16).to_ne_bytes();
+    let header = [tag, len[0], len[1]];
+    let iov = [
+        libc::iovec {
+            iov_base: header.as_ptr() as *mut libc::c_void,
+            iov_len: header.len(),
+        },
+        libc::iovec {
+            iov_base: payload.as_ptr() as *mut libc::c_void,
+            iov_len: payload.len(),
+        },
+    ];
+    // SAFETY: both buffers outlive the call and writev(2) only reads them.
+    unsafe { libc::writev(fd, iov.as_ptr(), 2) };
+}
+
+/// Read the reports of an exec helper until the command has exec'd
+///
+/// Returns the command's PID, or `None` if the helper exited without
+/// forking it.
+///
+/// # Errors
+///
+/// Returns the error reported by the helper or the command.
+#[cfg(target_os = "linux")]
+fn read_exec_reports(report: OwnedFd) -> Result<Option<u32>, ContainerError> {
+    use std::io::Read;
+
+    let mut buf = Vec::new();
+    std::fs::File::from(report)
+        .read_to_end(&mut buf)
+        .map_err(|e| ContainerError::IoError(format!("read exec report: {e}")))?;
+
+    let mut pid = None;
+    let mut rest = buf.as_slice();
+    while let [tag, a, b, tail @ ..] = rest {
+        let len = usize::from(u16::from_ne_bytes([*a, *b]));
+        if tail.len() < len {
+            break;
+        }
+        let (payload, tail) = tail.split_at(len);
+        match *tag {
+            REPORT_PID => pid = payload.try_into().ok().map(u32::from_ne_bytes),
+            REPORT_ERROR => {
+                return Err(crate::handshake::decode_error(payload).unwrap_or_else(|| {
+                    ContainerError::ProcessError("malformed exec error report".into())
+                }))
+            }
+            _ => {}
+        }
+        rest = tail;
+    }
+    Ok(pid)
+}
+
+/// Standard streams of an exec'd command, as descriptors inherited over fork
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+enum ExecStdio {
+    /// The runtime's own streams
+    Inherit,
+    /// `/dev/null`, open for reading and writing
+    Null(RawFd),
+    /// Slave and master of the command's pseudo-terminal
+    Terminal { slave: RawFd, master: RawFd },
+    /// Command and runtime ends of the stdin, stdout and stderr pipes
+    Pipes {
+        command: [RawFd; 3],
+        runtime: [RawFd; 3],
+    },
+}
+
+#[cfg(target_os = "linux")]
+impl ExecStdio {
+    /// Connect the streams, in the command
+    ///
+    /// The runtime's ends are close-on-exec and go away with `execve(2)`.
+    fn attach(self) -> Result<(), ContainerError> {
+        match self {
+            Self::Inherit => Ok(()),
+            Self::Terminal { slave, .. } => crate::console::attach_slave(slave),
+            Self::Null(null) => Self::Pipes {
+                command: [null; 3],
+                runtime: [-1; 3],
+            }
+            .attach(),
+            Self::Pipes { command, .. } => {
+                for (target, fd) in (0..).zip(command) {
+                    // SAFETY: fd is open in the command; dup2(2) does not touch memory.
+                    if unsafe { libc::dup2(fd, target) } < 0 {
+                        return Err(ContainerError::IoError(format!(
+                            "dup2: errno {}",
+                            last_errno()
+                        )));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Close every descriptor, in the helper
+    ///
+    /// Leaves the streams to the command, so the runtime sees end-of-file
+    /// and a hangup when it exits, and the command sees the runtime closing
+    /// its stdin.
+    fn close(self) {
+        let (first, second) = match self {
+            Self::Inherit => ([-1; 3], [-1; 3]),
+            Self::Null(null) => ([null, -1, -1], [-1; 3]),
+            Self::Terminal { slave, master } => ([slave, master, -1], [-1; 3]),
+            Self::Pipes { command, runtime } => (command, runtime),
+        };
+        for fd in first.into_iter().chain(second).filter(|&fd| fd >= 0) {
+            // SAFETY: fd is an open descriptor inherited from the runtime and unused here.
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// Entry point of the forked exec helper
+///
+/// Returns the exit code to use: the command's own status on success,
+/// `INIT_FAILURE_EXIT_CODE` if attaching or exec fails. Failures are sent
+/// to the runtime over the report pipe.
+#[cfg(target_os = "linux")]
+fn exec_main(attach: &ExecAttach<'_>, config: &ContainerConfig, spec: &ExecSpec) -> i32 {
+    use std::os::unix::io::AsRawFd;
+
+    // Opened before joining the container's PID namespace, where the helper has no PID
+    let helper = own_pidfd();
+    match attach_and_fork(attach) {
+        Ok(0) => {
+            let helper = helper.as_ref().map(AsRawFd::as_raw_fd);
+            let Err(e) = exec_command(attach, config, spec, helper);
+            send_report(
+                attach.report,
+                REPORT_ERROR,
+                &crate::handshake::encode_error(&e),
+            );
+            INIT_FAILURE_EXIT_CODE
+        }
+        Ok(child) => {
+            attach.stdio.close();
+            send_report(attach.report, REPORT_PID, &child.to_ne_bytes());
+            // SAFETY: report is an open pipe inherited from the runtime and unused afterwards.
+            unsafe { libc::close(attach.report) };
+            wait_exit_code(child as libc::pid_t).unwrap_or(INIT_FAILURE_EXIT_CODE)
+        }
+        Err(e) => {
+            send_report(
+                attach.report,
+                REPORT_ERROR,
+                &crate::handshake::encode_error(&e),
+            );
+            INIT_FAILURE_EXIT_CODE
+        }
+    }
+}
+
+/// Set up and exec the command, in the helper's child
+///
+/// Hardened like the init process: none of the runtime's signal state or
+/// descriptors reach the command, which gets `SIGKILL` if the helper, whose
+/// pidfd is `helper`, dies.
+/// Only returns on failure, with the stage that failed.
+#[cfg(target_os = "linux")]
+fn exec_command(
+    attach: &ExecAttach<'_>,
+    config: &ContainerConfig,
+    spec: &ExecSpec,
+    helper: Option<RawFd>,
+) -> Result<Infallible, ContainerError> {
+    let capabilities = &config.capabilities;
+    attach.stdio.attach().map_err(|e| e.at(InitStage::Stdio))?;
+    crate::rlimit::apply_all(&config.rlimits).map_err(|e| e.at(InitStage::Rlimits))?;
+    capabilities
+        .drop_bounding()
+        .map_err(|e| e.at(InitStage::Capabilities))?;
+    apply_user(&spec.user).map_err(|e| e.at(InitStage::User))?;
+    capabilities
+        .apply()
+        .map_err(|e| e.at(InitStage::Capabilities))?;
+    reset_signals().map_err(|e| e.at(InitStage::Signals))?;
+    set_parent_death_signal(libc::SIGKILL, helper).map_err(|e| e.at(InitStage::Signals))?;
+    seal_inherited_fds(&[]).map_err(|e| e.at(InitStage::Fds))?;
+    spec.seccomp
+        .install()
+        .map_err(|e| ContainerError::ProcessError(e.to_string()).at(InitStage::Seccomp))?;
+    exec_spec(spec).map_err(|e| e.at(InitStage::Exec))
+}
+
+/// `close_range(2)` syscall number
+#[cfg(target_os = "linux")]
+pub(crate) const SYS_CLOSE_RANGE: libc::c_long = 436;
+
+/// `close_range(2)` flag: mark close-on-exec instead of closing
+#[cfg(target_os = "linux")]
+const CLOSE_RANGE_CLOEXEC: libc::c_uint = 1 << 2;
+
+/// Highest descriptor tried when `close_range(2)` is unavailable
+#[cfg(target_os = "linux")]
+const MAX_FALLBACK_FD: u64 = 1 << 20;
+
+/// Keep only stdin, stdout, stderr and `preserve` across `execve(2)`
+///
+/// Inherited descriptors are marked close-on-exec rather than closed, so the
+/// init channel keeps working until the exec ends the handshake. Uses
+/// `close_range(2)` (Linux 5.11+), or `fcntl(2)` on every descriptor below
+/// the `RLIMIT_NOFILE` soft limit on older kernels.
+#[cfg(target_os = "linux")]
+fn seal_inherited_fds(preserve: &[RawFd]) -> Result<(), ContainerError> {
+    // SAFETY: close_range(2) takes plain integers and does not touch memory.
+    let ret = unsafe { libc::syscall(SYS_CLOSE_RANGE, 3u32, u32::MAX, CLOSE_RANGE_CLOEXEC) };
+    if ret < 0 {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: limit is a valid out-pointer.
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } < 0 {
+            return Err(ContainerError::ProcessError(format!(
+                "getrlimit(RLIMIT_NOFILE): errno {}",
+                last_errno()
+            )));
+        }
+        for fd in 3..limit.rlim_cur.min(MAX_FALLBACK_FD) as RawFd {
+            // SAFETY: F_SETFD takes an integer; descriptors that are not open fail with EBADF.
+            unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+        }
+    }
+
+    for &fd in preserve {
+        // SAFETY: as above.
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, 0) } < 0 {
+            return Err(ContainerError::ProcessError(format!(
+                "preserved descriptor {fd}: errno {}",
+                last_errno()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Restore default signal dispositions and unblock all signals
+///
+/// Both ignored signals and the mask survive `execve(2)`, so the workload
+/// would otherwise start with the runtime's.
+#[cfg(target_os = "linux")]
+fn reset_signals() -> Result<(), ContainerError> {
+    for signal in 1..=libc::SIGRTMAX() {
+        // SAFETY: SIG_DFL installs no handler; SIGKILL, SIGSTOP and signals reserved by
+        // libc are rejected without effect.
+        unsafe { libc::signal(signal, libc::SIG_DFL) };
+    }
+
+    // SAFETY: sigset_t is plain data; an all-zero value is valid before sigemptyset(3).
+    let mut empty: libc::sigset_t = unsafe { core::mem::zeroed() };
+    // SAFETY: empty is a valid set; the old mask is not needed.
+    let ret = unsafe {
+        libc::sigemptyset(&mut empty);
+        libc::sigprocmask(libc::SIG_SETMASK, &empty, core::ptr::null_mut())
+    };
+    if ret < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "sigprocmask: errno {}",
+            last_errno()
+        )));
+    }
+    Ok(())
+}
+
+/// Have the kernel send `signal` to this process when its parent exits
+///
+/// `parent` is a pidfd of the parent process, if the kernel has pidfds. A
+/// parent that already exited would never deliver the signal, so that case
+/// is an error. Must be called after switching credentials, which clears
+/// the setting.
+#[cfg(target_os = "linux")]
+fn set_parent_death_signal(
+    signal: libc::c_int,
+    parent: Option<RawFd>,
+) -> Result<(), ContainerError> {
+    // SAFETY: PR_SET_PDEATHSIG takes a signal number and no pointers.
+    if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, signal as libc::c_ulong) } < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "prctl(PR_SET_PDEATHSIG): errno {}",
+            last_errno()
+        )));
+    }
+
+    let Some(fd) = parent else {
+        return Ok(());
+    };
+    // A pidfd is readable once its process has exited
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: pollfd is a single valid entry; a zero timeout does not block.
+    match unsafe { libc::poll(&mut pollfd, 1, 0) } {
+        0 => Ok(()),
+        n if n > 0 => Err(ContainerError::ProcessError(
+            "parent exited before the parent-death signal was set".into(),
+        )),
+        _ => Err(ContainerError::ProcessError(format!(
+            "poll parent pidfd: errno {}",
+            last_errno()
+        ))),
+    }
+}
+
+/// pidfd of the calling process, `None` on kernels without `pidfd_open(2)`
+///
+/// Passed to children so they can check with `set_parent_death_signal`
+/// that the process their signal is tied to is still there.
+#[cfg(target_os = "linux")]
+fn own_pidfd() -> Option<OwnedFd> {
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: getpid(2) cannot fail; pidfd_open(2) takes a PID and flags and returns a
+    // new descriptor or -1.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, libc::getpid(), 0) };
+    // SAFETY: a non-negative result is a new descriptor owned by nobody else.
+    (fd >= 0).then(|| unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// Move into the container's cgroup, namespaces and root, then fork
+///
+/// The extra fork is required for the PID namespace to take effect. Returns
+/// 0 in the child and the child's PID in the helper.
+#[cfg(target_os = "linux")]
+fn attach_and_fork(attach: &ExecAttach<'_>) -> Result<u32, ContainerError> {
+    // Writing "0" moves the writing process itself
+    // SAFETY: cgroup_procs_fd is an open, writable descriptor; the buffer is a static string.
+    if unsafe { libc::write(attach.cgroup_procs_fd, b"0".as_ptr().cast(), 1) } < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "join cgroup: errno {}",
+            last_errno()
+        )));
+    }
+
+    attach.namespaces.enter()?;
+
+    // SAFETY: root_fd refers to the container root directory opened before fork; "." is a
+    // static NUL-terminated string.
+    let ret = unsafe {
+        if libc::fchdir(attach.root_fd) < 0 {
+            -1
+        } else {
+            libc::chroot(c".".as_ptr())
+        }
+    };
+    if ret < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "chroot: errno {}",
+            last_errno()
+        )));
+    }
+
+    // SAFETY: workdir is a valid NUL-terminated string owned by the caller.
+    if unsafe { libc::chdir(attach.workdir.as_ptr()) } < 0 {
+        return Err(ContainerError::ConfigError(format!(
+            "Working directory {}: errno {}",
+            attach.workdir.to_string_lossy(),
+            last_errno()
+        )));
+    }
+
+    // SAFETY: single fork of the helper process; the child only execs prepared data.
+    match unsafe { libc::fork() } {
+        -1 => Err(ContainerError::ProcessError(format!(
+            "fork: errno {}",
+            last_errno()
+        ))),
+        pid => Ok(pid as u32),
+    }
+}
+
+/// Wait for a child and translate its status into a shell-style exit code
+#[cfg(target_os = "linux")]
+fn wait_exit_code(pid: libc::pid_t) -> Result<i32, ContainerError> {
+    let mut status: libc::c_int = 0;
+    loop {
+        // SAFETY: pid is a child of this process; status is a valid out-pointer.
+        let ret = unsafe { libc::waitpid(pid, &mut status, 0) };
+        if ret >= 0 {
+            break;
+        }
+        if last_errno() != libc::EINTR {
+            return Err(ContainerError::ProcessError(format!(
+                "waitpid: errno {}",
+                last_errno()
+            )));
+        }
+    }
+
+    Ok(exit_code_from_status(status))
+}
+
+/// Init process started by `Container::spawn_init()`
+#[cfg(feature = "std")]
+struct SpawnedInit {
+    init: PidFd,
+    /// Terminal master, unless sent to the console socket
+    console: Option<Console>,
+    /// Read ends of the init's stdout and stderr pipes
+    output: Option<(std::os::unix::io::OwnedFd, std::os::unix::io::OwnedFd)>,
+}
+
+/// Kill a child that failed to start and reap it
+#[cfg(target_os = "linux")]
+fn kill_and_reap(pid: libc::pid_t) {
+    // SAFETY: pid is a child of this process that has not been reaped yet.
+    unsafe {
+        libc::kill(pid, libc::SIGKILL);
+    }
+    let _ = wait_exit_code(pid);
+}
+
+/// Translate a `waitpid(2)` status into a shell-style exit code
+#[cfg(target_os = "linux")]
+pub(crate) const fn exit_code_from_status(status: libc::c_int) -> i32 {
+    if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        -1
+    }
+}
+
+/// Read errno after a failed libc call
+#[cfg(target_os = "linux")]
+fn last_errno() -> i32 {
+    // SAFETY: errno is thread-local and always readable.
+    unsafe { *libc::__errno_location() }
+}
+
+/// Create a close-on-exec pipe, returning its read and write ends
+///
+/// # Errors
+///
+/// Returns an error if `pipe2(2)` fails.
+#[cfg(target_os = "linux")]
+pub(crate) fn pipe() -> Result<(OwnedFd, OwnedFd), ContainerError> {
+    use std::os::unix::io::FromRawFd;
+
+    let mut fds = [0; 2];
+    // SAFETY: fds is a valid array of two descriptors.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(ContainerError::IoError(format!(
+            "pipe2: errno {}",
+            last_errno()
+        )));
     }
+    // SAFETY: pipe2(2) returned two new descriptors that nothing else owns.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
 
-    /// Get configuration
-    #[must_use]
-    pub const fn config(&self) -> &ContainerConfig {
-        &self.config
+/// Set the umask and switch to the configured user and groups
+///
+/// Groups are changed first, while the process still has the privilege to do so,
+/// and always, so no supplementary group of the runtime is inherited.
+/// `user` must already be resolved to IDs.
+#[cfg(target_os = "linux")]
+fn apply_user(user: &ProcessUser) -> Result<(), ContainerError> {
+    if let Some(umask) = user.umask {
+        // SAFETY: umask(2) takes a plain mode and cannot fail.
+        unsafe { libc::umask(umask as libc::mode_t) };
     }
 
-    /// Get cgroup path
-    #[must_use]
-    pub fn cgroup_path(&self) -> &Path {
-        self.cgroup.path()
+    // SAFETY: the pointer/length pair describes the live `additional_gids` slice; gid_t is u32.
+    let ret = unsafe { libc::setgroups(user.additional_gids.len(), user.additional_gids.as_ptr()) };
+    if ret < 0 {
+        let errno = last_errno();
+        // A user namespace that denies setgroups(2) has no groups to drop
+        if !(errno == libc::EPERM && user.additional_gids.is_empty() && setgroups_denied()) {
+            return Err(ContainerError::ProcessError(format!(
+                "setgroups: errno {errno}"
+            )));
+        }
     }
 
-    /// Get current memory usage
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the operation fails.
-    pub fn memory_usage(&self) -> Result<u64, ContainerError> {
-        Ok(self.cgroup.memory_current()?)
+    if user.is_root() {
+        return Ok(());
     }
 
-    /// Get current CPU usage
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the operation fails.
-    pub fn cpu_usage(&self) -> Result<u64, ContainerError> {
-        Ok(self.cgroup.cpu_usage_us()?)
+    // Keep the permitted capabilities across the UID change; `Capabilities::apply()`
+    // then sets the final sets. The flag is cleared again by execve(2).
+    // SAFETY: PR_SET_KEEPCAPS takes an integer flag and no pointers.
+    if unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 1 as libc::c_ulong) } < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "prctl(PR_SET_KEEPCAPS): errno {}",
+            last_errno()
+        )));
     }
 
-    /// Update CPU limits
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the operation fails.
-    pub fn update_cpu(&mut self, config: &CpuConfig) -> Result<(), ContainerError> {
-        self.cgroup.set_cpu(config)?;
-        self.config.cpu = *config;
-        Ok(())
+    // SAFETY: setresgid(2)/setresuid(2) take plain integer IDs and validate them.
+    if unsafe { libc::setresgid(user.gid, user.gid, user.gid) } < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "setresgid({}): errno {}",
+            user.gid,
+            last_errno()
+        )));
+    }
+    // SAFETY: see above.
+    if unsafe { libc::setresuid(user.uid, user.uid, user.uid) } < 0 {
+        return Err(ContainerError::ProcessError(format!(
+            "setresuid({}): errno {}",
+            user.uid,
+            last_errno()
+        )));
     }
 
-    /// Update memory limits
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the operation fails.
-    pub fn update_memory(&mut self, config: &MemoryConfig) -> Result<(), ContainerError> {
-        self.cgroup.set_memory(config)?;
-        self.config.memory = *config;
-        Ok(())
+    Ok(())
+}
+
+/// Whether `/proc/self/setgroups` reads "deny"
+///
+/// Read with plain syscalls, as the caller runs between fork and exec.
+#[cfg(target_os = "linux")]
+fn setgroups_denied() -> bool {
+    let mut buf = [0u8; 8];
+    // SAFETY: the path is a static NUL-terminated string; the descriptor is closed below.
+    let fd = unsafe {
+        libc::open(
+            c"/proc/self/setgroups".as_ptr(),
+            libc::O_RDONLY | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return false;
     }
+    // SAFETY: buf is a live, writable buffer of the given length.
+    let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    // SAFETY: fd was opened above and is not used afterwards.
+    unsafe { libc::close(fd) };
+    usize::try_from(n).is_ok_and(|n| buf[..n].starts_with(b"deny"))
 }
 
-#[cfg(feature = "std")]
-#[allow(clippy::missing_fields_in_debug)]
-impl fmt::Debug for Container {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Container")
-            .field("id", &self.id)
-            .field("state", &self.state)
-            .field("init_pid", &self.init_pid)
-            .finish()
+/// `execve(2)` the first candidate program that can be executed
+#[cfg(target_os = "linux")]
+fn exec_spec(spec: &ExecSpec) -> Result<Infallible, ContainerError> {
+    let mut argv: Vec<*const libc::c_char> = spec.argv.iter().map(|a| a.as_ptr()).collect();
+    argv.push(core::ptr::null());
+    let mut envp: Vec<*const libc::c_char> = spec.envp.iter().map(|e| e.as_ptr()).collect();
+    envp.push(core::ptr::null());
+
+    let mut errno = libc::ENOENT;
+    for program in &spec.programs {
+        // SAFETY: program, argv and envp point to NUL-terminated strings owned by `spec`,
+        // and both pointer arrays are NULL-terminated. execve(2) only returns on failure.
+        unsafe {
+            libc::execve(program.as_ptr(), argv.as_ptr(), envp.as_ptr());
+        }
+        errno = last_errno();
+        // Keep searching PATH only while the candidate simply does not exist
+        if errno != libc::ENOENT && errno != libc::ENOTDIR {
+            break;
+        }
     }
+
+    Err(ContainerError::ProcessError(format!(
+        "execve {}: errno {errno}",
+        spec.argv[0].to_string_lossy()
+    )))
 }
 
 // ============================================================================
@@ -719,6 +4038,114 @@ mod tests {
         assert_eq!(ContainerState::Running.to_string(), "running");
         assert_eq!(ContainerState::Paused.to_string(), "paused");
         assert_eq!(ContainerState::Stopped.to_string(), "stopped");
+        assert_eq!(
+            ContainerState::Exited { code: 137 }.to_string(),
+            "exited (137)"
+        );
+    }
+
+    #[test]
+    fn test_exit_status_code_and_signal() {
+        assert_eq!(ExitStatus::Exited(3).code(), 3);
+        assert_eq!(ExitStatus::Exited(3).signal(), None);
+        assert_eq!(ExitStatus::Signaled(9).code(), 137);
+        assert_eq!(ExitStatus::Signaled(9).signal(), Some(9));
+        assert!(ExitStatus::Exited(0).success());
+        assert!(!ExitStatus::Signaled(15).success());
+        assert_eq!(ExitStatus::Signaled(15).to_string(), "signal 15");
+        assert_eq!(ExitStatus::Exited(1).to_string(), "exit code 1");
+    }
+
+    #[test]
+    fn test_restart_policy() {
+        let failed = ExitStatus::Exited(1);
+        let killed = ExitStatus::Signaled(9);
+        let ok = ExitStatus::Exited(0);
+
+        assert!(!RestartPolicy::Never.should_restart(failed, 0));
+        assert!(RestartPolicy::Always.should_restart(ok, 100));
+        assert!(RestartPolicy::UnlessStopped.should_restart(killed, 100));
+
+        let on_failure = RestartPolicy::OnFailure { max_retries: 2 };
+        assert!(!on_failure.should_restart(ok, 0));
+        assert!(on_failure.should_restart(failed, 0));
+        assert!(on_failure.should_restart(killed, 1));
+        assert!(!on_failure.should_restart(killed, 2));
+        assert!(on_failure.should_restart(ExitStatus::Unknown, 0));
+
+        assert!(RestartPolicy::Always.start_on_recovery());
+        assert!(!RestartPolicy::UnlessStopped.start_on_recovery());
+        assert_eq!(on_failure.to_string(), "on-failure:2");
+        assert_eq!(RestartPolicy::default(), RestartPolicy::Never);
+    }
+
+    #[test]
+    fn test_config_labels() {
+        let config = ContainerConfig::builder()
+            .label("app", "web")
+            .label("tier", "front")
+            .label("app", "api")
+            .build();
+        assert_eq!(config.label("app"), Some("api"));
+        assert_eq!(config.label("tier"), Some("front"));
+        assert_eq!(config.label("zone"), None);
+        assert_eq!(config.labels.len(), 2);
+    }
+
+    #[test]
+    fn test_config_rlimits() {
+        let core = ContainerConfig::default();
+        assert_eq!(
+            core.rlimit(RlimitResource::Core),
+            Some(&Rlimit::new(RlimitResource::Core, 0, 0))
+        );
+
+        let config = ContainerConfig::builder()
+            .rlimit(RlimitResource::Nofile, 1024, 4096)
+            .rlimit(RlimitResource::Nofile, 65_536, 65_536)
+            .build();
+        assert_eq!(config.rlimits.len(), 2);
+        assert_eq!(
+            config.rlimit(RlimitResource::Nofile).map(|l| l.soft),
+            Some(65_536)
+        );
+        assert!(config.validate().is_ok());
+
+        let invalid = ContainerConfig::builder()
+            .rlimit(RlimitResource::Nproc, 100, 10)
+            .build();
+        assert!(matches!(
+            invalid.validate(),
+            Err(ContainerError::ConfigError(_))
+        ));
+        assert!(matches!(
+            Container::create("rlimit-invalid", invalid),
+            Err(ContainerError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_id() {
+        for id in ["web", "web-1", "a_b", "0abc", &"x".repeat(MAX_ID_LEN)] {
+            assert!(validate_id(id).is_ok(), "{id}");
+        }
+        for id in [
+            "",
+            "..",
+            "../x",
+            "a/b",
+            "-web",
+            "_web",
+            "cgroup.procs",
+            "web 1",
+            "wéb",
+            &"x".repeat(MAX_ID_LEN + 1),
+        ] {
+            assert!(
+                matches!(validate_id(id), Err(ContainerError::ConfigError(_))),
+                "{id}"
+            );
+        }
     }
 
     #[test]
@@ -869,6 +4296,499 @@ mod tests {
         assert_eq!(c1.network, c2.network);
     }
 
+    #[test]
+    fn test_container_config_default_args() {
+        let config = ContainerConfig::default();
+        assert_eq!(config.args, vec!["/bin/sh"]);
+    }
+
+    #[test]
+    fn test_container_config_builder_args() {
+        let config = ContainerConfig::builder()
+            .args(["/usr/bin/game-server", "--port", "7777"])
+            .build();
+        assert_eq!(config.args, vec!["/usr/bin/game-server", "--port", "7777"]);
+    }
+
+    #[test]
+    fn test_container_config_default_user_is_root() {
+        let config = ContainerConfig::default();
+        assert!(config.user.is_root());
+        assert!(!config.terminal);
+    }
+
+    #[test]
+    fn test_container_config_builder_user_and_terminal() {
+        let config = ContainerConfig::builder()
+            .user(1000, 1000)
+            .additional_gids(vec![27, 44])
+            .terminal(true)
+            .build();
+        assert_eq!(config.user.uid, 1000);
+        assert_eq!(config.user.gid, 1000);
+        assert_eq!(config.user.additional_gids, vec![27, 44]);
+        assert!(config.terminal);
+    }
+
+    #[test]
+    fn test_process_user_is_root() {
+        assert!(ProcessUser::new(0, 0).is_root());
+        assert!(!ProcessUser::new(0, 1).is_root());
+        let mut user = ProcessUser::new(0, 0);
+        user.additional_gids.push(10);
+        assert!(!user.is_root());
+        let mut user = ProcessUser::new(0, 0);
+        user.name = Some("root".into());
+        assert!(!user.is_root());
+    }
+
+    #[test]
+    fn test_process_user_resolve_names() {
+        let db = crate::passwd::AccountDb::parse(
+            "root:x:0:0::/root:/bin/sh\napp:x:1000:1000::/home/app:/bin/sh\n",
+            "root:x:0:\nadm:x:4:app\nstaff:x:50:\napp:x:1000:\n",
+        );
+        let mut user = ProcessUser::new(0, 0);
+        user.name = Some("app".into());
+        user.additional_gids = vec![4, 7];
+        user.umask = Some(0o027);
+
+        let resolved = user.resolve_in(&db).unwrap();
+        assert_eq!((resolved.uid, resolved.gid), (1000, 1000));
+        assert_eq!(resolved.additional_gids, vec![4, 7]);
+        assert_eq!(resolved.umask, Some(0o027));
+        assert!(!resolved.has_names());
+
+        user.group = Some("staff".into());
+        user.additional_groups = vec!["root".into()];
+        let resolved = user.resolve_in(&db).unwrap();
+        assert_eq!(resolved.gid, 50);
+        assert_eq!(resolved.additional_gids, vec![4, 7, 0]);
+
+        // Numeric names work without an entry, unknown names do not
+        user.name = Some("2000".into());
+        assert_eq!(user.resolve_in(&db).unwrap().uid, 2000);
+        user.name = Some("nobody".into());
+        let err = user.resolve_in(&db).unwrap_err();
+        assert!(err.to_string().contains("nobody"));
+        user.name = None;
+        user.group = Some("wheel".into());
+        assert!(matches!(
+            user.resolve_in(&db),
+            Err(ContainerError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_user_resolve_in_rootfs() {
+        let dir = std::env::temp_dir().join(format!(
+            "alice-container-user-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos()
+        ));
+        std::fs::create_dir_all(dir.join("etc")).unwrap();
+        std::fs::write(dir.join("etc/passwd"), "www:x:33:33::/var/www:/bin/false\n").unwrap();
+
+        let config = ContainerConfig::builder().user_name("www").build();
+        let resolved = config.user.resolve(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        let resolved = resolved.unwrap();
+        assert_eq!((resolved.uid, resolved.gid), (33, 33));
+
+        // IDs only: nothing to read
+        let numeric = ProcessUser::new(5, 5);
+        assert_eq!(numeric.resolve(Path::new("/nonexistent")).unwrap(), numeric);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_copy_paths_resolve_in_root() {
+        use std::ffi::OsStr;
+
+        let root =
+            std::env::temp_dir().join(format!("alice-container-copy-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("srv/data")).unwrap();
+        std::os::unix::fs::symlink("/", root.join("srv/up")).unwrap();
+
+        let name = OsStr::new("game.cfg");
+        let (into_dir, into_name) =
+            resolve_copy_target(&root, Path::new("/srv/data"), name).unwrap();
+        let (as_file, as_name) =
+            resolve_copy_target(&root, Path::new("/srv/data/server.cfg"), name).unwrap();
+        // The absolute symlink resolves to the container root, not the host's
+        let (escaped, _) = resolve_copy_target(&root, Path::new("/srv/up/srv"), name).unwrap();
+        let missing = resolve_copy_target(&root, Path::new("/nope/server.cfg"), name);
+        let (source_dir, source_name) = open_copy_source(&root, Path::new("/srv/up")).unwrap();
+        let (_, root_name) = open_copy_source(&root, Path::new("/")).unwrap();
+
+        let canonical =
+            |dir: &std::fs::File| std::fs::canonicalize(crate::archive::fd_path(dir)).unwrap();
+        let root = std::fs::canonicalize(&root).unwrap();
+        let paths = [&into_dir, &as_file, &escaped, &source_dir].map(canonical);
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(paths[0], root.join("srv/data"));
+        assert_eq!(into_name, "game.cfg");
+        assert_eq!(paths[1], root.join("srv/data"));
+        assert_eq!(as_name, "server.cfg");
+        assert_eq!(paths[2], root.join("srv"));
+        assert!(missing.is_err());
+        assert_eq!(paths[3], root.join("srv"));
+        assert_eq!(source_name, "up");
+        assert_eq!(root_name, ".");
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_umask() {
+        let config = ContainerConfig::builder().umask(0o1777).build();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("umask"));
+        assert!(ContainerConfig::builder()
+            .umask(0o022)
+            .build()
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_config_rejects_preserved_stdio() {
+        let config = ContainerConfig::builder().preserve_fds([3, 2]).build();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("descriptor 2"));
+        assert!(ContainerConfig::builder()
+            .preserve_fds([3, 4])
+            .build()
+            .validate()
+            .is_ok());
+    }
+
+    /// Run `check` in a forked child and return its exit code
+    ///
+    /// For tests that change process-wide state the test harness shares.
+    #[cfg(target_os = "linux")]
+    fn in_child(check: impl FnOnce() -> i32) -> i32 {
+        // SAFETY: the child only makes syscalls and leaves with _exit(2).
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let code = check();
+            // SAFETY: _exit(2) skips the test harness's atexit handlers.
+            unsafe { libc::_exit(code) };
+        }
+        wait_exit_code(pid).unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_apply_user_sets_exactly_the_configured_groups() {
+        let groups = || {
+            let mut list = [0 as libc::gid_t; 64];
+            // SAFETY: list is a live, writable array of the given length.
+            let n = unsafe { libc::getgroups(list.len() as libc::c_int, list.as_mut_ptr()) };
+            list[..usize::try_from(n).unwrap_or(0)].to_vec()
+        };
+
+        let code = in_child(|| {
+            // SAFETY: geteuid(2) cannot fail.
+            if unsafe { libc::geteuid() } != 0 {
+                // Only root can change its groups
+                return 0;
+            }
+            let mut user = ProcessUser::new(0, 0);
+            user.additional_gids = vec![4242, 4243];
+            if apply_user(&user).is_err() || groups() != [4242, 4243] {
+                return 1;
+            }
+            // Root without supplementary groups drops the ones it had
+            if apply_user(&ProcessUser::new(0, 0)).is_err() || !groups().is_empty() {
+                return 2;
+            }
+            0
+        });
+        assert_eq!(code, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_seal_inherited_fds() {
+        use std::os::unix::io::AsRawFd;
+
+        let code = in_child(|| {
+            let cloexec = |fd| {
+                // SAFETY: F_GETFD takes no argument.
+                unsafe { libc::fcntl(fd, libc::F_GETFD) & libc::FD_CLOEXEC != 0 }
+            };
+            let (leaked, _) = pipe().unwrap();
+            let (kept, _) = pipe().unwrap();
+            let (leaked, kept) = (leaked.as_raw_fd(), kept.as_raw_fd());
+            // As if opened by another thread without O_CLOEXEC
+            // SAFETY: leaked is an open descriptor owned by this closure.
+            unsafe { libc::fcntl(leaked, libc::F_SETFD, 0) };
+
+            if seal_inherited_fds(&[kept]).is_err() {
+                return 1;
+            }
+            match (cloexec(leaked), cloexec(kept)) {
+                (true, false) => 0,
+                (false, _) => 2,
+                (true, true) => 3,
+            }
+        });
+        assert_eq!(code, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reset_signals() {
+        extern "C" fn handler(_: libc::c_int) {}
+
+        let code = in_child(|| {
+            // SAFETY: the sets are valid and the handler is a plain function.
+            unsafe {
+                let mut blocked: libc::sigset_t = core::mem::zeroed();
+                libc::sigemptyset(&mut blocked);
+                libc::sigaddset(&mut blocked, libc::SIGUSR1);
+                libc::sigaddset(&mut blocked, libc::SIGRTMIN());
+                libc::sigprocmask(libc::SIG_BLOCK, &blocked, core::ptr::null_mut());
+                libc::signal(libc::SIGTERM, libc::SIG_IGN);
+                libc::signal(libc::SIGUSR2, handler as libc::sighandler_t);
+            }
+
+            if reset_signals().is_err() {
+                return 1;
+            }
+            // SAFETY: sigset_t and sigaction are plain data; all-zero values are valid.
+            let mut mask: libc::sigset_t = unsafe { core::mem::zeroed() };
+            // SAFETY: mask is a valid out-pointer; no set is installed.
+            unsafe { libc::sigprocmask(libc::SIG_BLOCK, core::ptr::null(), &mut mask) };
+            for signal in 1..=libc::SIGRTMAX() {
+                // SAFETY: mask is a valid set.
+                if unsafe { libc::sigismember(&mask, signal) } == 1 {
+                    return 2;
+                }
+                // SAFETY: as above.
+                let mut action: libc::sigaction = unsafe { core::mem::zeroed() };
+                // SAFETY: action is a valid out-pointer; signals reserved by libc fail.
+                let queried = unsafe { libc::sigaction(signal, core::ptr::null(), &mut action) };
+                if queried == 0 && action.sa_sigaction != libc::SIG_DFL {
+                    return 3;
+                }
+            }
+            0
+        });
+        assert_eq!(code, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parent_death_signal_requires_a_live_parent() {
+        use std::os::unix::io::AsRawFd;
+
+        let code = in_child(|| {
+            // SAFETY: the child leaves with _exit(2) straight away.
+            let gone = unsafe { libc::fork() };
+            if gone == 0 {
+                // SAFETY: see above.
+                unsafe { libc::_exit(0) };
+            }
+            // A zombie still has a pidfd
+            // SAFETY: pidfd_open(2) takes a PID and flags.
+            let gone_fd = unsafe { libc::syscall(libc::SYS_pidfd_open, gone, 0) } as RawFd;
+            let _ = wait_exit_code(gone);
+            let Some(alive) = own_pidfd() else {
+                // Nothing to check without pidfds
+                return 0;
+            };
+
+            if set_parent_death_signal(libc::SIGKILL, Some(alive.as_raw_fd())).is_err() {
+                return 1;
+            }
+            if set_parent_death_signal(libc::SIGKILL, Some(gone_fd)).is_ok() {
+                return 2;
+            }
+            0
+        });
+        assert_eq!(code, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_init_dies_with_the_runtime() {
+        use std::os::unix::io::AsRawFd;
+
+        let (report, report_end) = pipe().unwrap();
+        // SAFETY: the child only makes syscalls, starts threads and never returns.
+        let runtime = unsafe { libc::fork() };
+        assert!(runtime >= 0);
+        if runtime == 0 {
+            let pidfd = own_pidfd();
+            let parent = pidfd.as_ref().map(AsRawFd::as_raw_fd);
+            let (ready, ready_end) = pipe().unwrap();
+            let (ready, ready_end) = (ready.as_raw_fd(), ready_end.as_raw_fd());
+            // Started from a thread that exits right away, like a manager's worker
+            let init = std::thread::spawn(move || {
+                on_spawner_thread(|| {
+                    // SAFETY: the child only makes syscalls until it is killed.
+                    let init = unsafe { libc::fork() };
+                    if init == 0 {
+                        if set_parent_death_signal(libc::SIGKILL, parent).is_err() {
+                            // SAFETY: _exit(2) skips the test harness's atexit handlers.
+                            unsafe { libc::_exit(1) };
+                        }
+                        // SAFETY: the buffer is a static byte; pause(2) waits for a signal.
+                        unsafe {
+                            libc::write(ready_end, b"r".as_ptr().cast(), 1);
+                            loop {
+                                libc::pause();
+                            }
+                        }
+                    }
+                    // Return only once the signal is set, so this thread's exit would send it
+                    let mut byte = [0u8; 1];
+                    // SAFETY: byte is a valid one-byte buffer.
+                    unsafe { libc::read(ready, byte.as_mut_ptr().cast(), 1) };
+                    init
+                })
+            })
+            .join();
+
+            std::thread::sleep(Duration::from_millis(100));
+            let init = match init {
+                // SAFETY: init is our child; WNOHANG does not block.
+                Ok(Ok(init))
+                    if unsafe { libc::waitpid(init, core::ptr::null_mut(), libc::WNOHANG) }
+                        == 0 =>
+                {
+                    init
+                }
+                _ => -1,
+            };
+            // SAFETY: the buffer is valid for its length; the process then waits to be killed.
+            unsafe {
+                libc::write(
+                    report_end.as_raw_fd(),
+                    init.to_ne_bytes().as_ptr().cast(),
+                    4,
+                );
+                loop {
+                    libc::pause();
+                }
+            }
+        }
+        drop(report_end);
+
+        let mut buf = [0u8; 4];
+        let read = std::io::Read::read_exact(&mut std::fs::File::from(report), &mut buf);
+        let init = i32::from_ne_bytes(buf);
+        // SAFETY: pidfd_open(2) takes a PID and flags.
+        let init_fd = unsafe { libc::syscall(libc::SYS_pidfd_open, init, 0) } as RawFd;
+
+        // SAFETY: runtime is our child, killed and reaped here.
+        unsafe { libc::kill(runtime, libc::SIGKILL) };
+        let _ = wait_exit_code(runtime);
+        let mut pollfd = libc::pollfd {
+            fd: init_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pollfd is a single valid entry.
+        let exited = unsafe { libc::poll(&mut pollfd, 1, 5000) } == 1;
+        if !exited && init > 0 {
+            // SAFETY: do not leave the init behind when the test fails.
+            unsafe { libc::kill(init, libc::SIGKILL) };
+        }
+        // SAFETY: init_fd is unused from here on.
+        unsafe { libc::close(init_fd) };
+
+        assert!(read.is_ok());
+        assert!(init > 0, "init did not survive its starting thread");
+        assert!(init_fd >= 0);
+        assert!(exited, "init outlived the runtime");
+    }
+
+    // --- Exec spec tests ---
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resolve_program_absolute_path() {
+        let config = ContainerConfig::default();
+        assert_eq!(resolve_program("/bin/true", &config), vec!["/bin/true"]);
+        assert_eq!(resolve_program("./run.sh", &config), vec!["./run.sh"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resolve_program_searches_container_path() {
+        let config = ContainerConfig {
+            env: vec![("PATH".to_string(), "/opt/bin::/sbin/".to_string())],
+            ..ContainerConfig::default()
+        };
+        assert_eq!(
+            resolve_program("server", &config),
+            vec!["/opt/bin/server", "/sbin/server"]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_init_spec_rejects_empty_command() {
+        let config = ContainerConfig::builder()
+            .args(Vec::<String>::new())
+            .build();
+        let err = ExecSpec::init(&config).unwrap_err();
+        assert!(err.to_string().contains("Empty command"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_init_spec_rejects_interior_nul() {
+        let config = ContainerConfig::builder().args(["/bin/sh", "a\0b"]).build();
+        assert!(matches!(
+            ExecSpec::init(&config),
+            Err(ContainerError::ConfigError(_))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_init_spec_builds_env_pairs() {
+        let config = ContainerConfig::builder().env("FOO", "bar").build();
+        let spec = ExecSpec::init(&config).unwrap();
+        assert!(spec.envp.iter().any(|e| e.to_str() == Ok("FOO=bar")));
+        assert_eq!(spec.argv[0].to_str(), Ok("/bin/sh"));
+        assert_eq!(spec.programs.len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_exec_spec_uses_given_args_and_container_env() {
+        let config = ContainerConfig::builder()
+            .args(["/bin/server"])
+            .env("MODE", "admin")
+            .build();
+        let args = vec!["ls".to_string(), "-l".to_string()];
+        let spec = ExecSpec::new(&args, &config, Path::new("/")).unwrap();
+        assert_eq!(spec.argv[0].to_str(), Ok("ls"));
+        assert_eq!(spec.argv[1].to_str(), Ok("-l"));
+        assert!(spec.envp.iter().any(|e| e.to_str() == Ok("MODE=admin")));
+        assert!(spec
+            .programs
+            .iter()
+            .any(|p| p.to_str() == Ok("/usr/bin/ls")));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_exit_code_from_status() {
+        // exit(3): status = 3 << 8
+        assert_eq!(exit_code_from_status(3 << 8), 3);
+        // killed by SIGKILL: status = 9
+        assert_eq!(exit_code_from_status(libc::SIGKILL), 128 + libc::SIGKILL);
+    }
+
     // --- ContainerError additional tests ---
 
     #[test]
@@ -911,6 +4831,74 @@ mod tests {
         assert!(err.to_string().contains("Namespace error"));
     }
 
+    #[test]
+    fn test_container_error_from_rootfs_error() {
+        let err: ContainerError = RootFsError::MountFailed("errno: 1".into()).into();
+        assert!(err.to_string().contains("Root filesystem error"));
+        assert!(err.to_string().contains("errno: 1"));
+    }
+
+    #[test]
+    fn test_container_error_from_network_error() {
+        let err: ContainerError = NetworkError::PermissionDenied.into();
+        assert!(err.to_string().contains("Network error"));
+    }
+
+    #[test]
+    fn test_container_error_at_stage() {
+        let err = ContainerError::ProcessError("execve /bin/x: errno 2".into()).at(InitStage::Exec);
+        assert!(matches!(
+            err,
+            ContainerError::Init {
+                stage: InitStage::Exec,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Container init failed (exec): Process error: execve /bin/x: errno 2"
+        );
+
+        // The innermost stage wins
+        let err = err.at(InitStage::Sync);
+        assert!(matches!(
+            err,
+            ContainerError::Init {
+                stage: InitStage::Exec,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_init_stage_from_u8_roundtrip() {
+        for value in 1..=16 {
+            let stage = InitStage::from_u8(value).unwrap();
+            assert_eq!(stage as u8, value);
+        }
+        assert_eq!(InitStage::from_u8(0), None);
+        assert_eq!(InitStage::from_u8(17), None);
+    }
+
+    #[test]
+    fn test_container_config_builder_user_namespace() {
+        let config = ContainerConfig::builder()
+            .user_namespace(IdMapping::root_to_user(1000), IdMapping::root_to_user(100))
+            .build();
+        assert!(config.namespaces.contains(NamespaceFlags::NEWUSER));
+        assert_eq!(config.uid_map.unwrap().outer_id, 1000);
+        assert_eq!(config.gid_map.unwrap().outer_id, 100);
+    }
+
+    #[test]
+    fn test_container_config_builder_network_config() {
+        let net = NetworkConfig::from_container_id("web", 3);
+        let config = ContainerConfig::builder().network_config(net).build();
+        assert!(config.network);
+        assert!(config.namespaces.contains(NamespaceFlags::NEWNET));
+        assert_eq!(config.network_config.unwrap().container_ip, "10.0.0.5/24");
+    }
+
     #[test]
     fn test_container_error_invalid_state_pause_on_stopped() {
         let err = ContainerError::InvalidState {
@@ -0,0 +1,716 @@
+//! Container Events
+//!
+//! Typed lifecycle events of one or many containers, delivered by an
+//! [`EventMonitor`] as they happen instead of by polling cgroup files.
+//!
+//! ## Sources
+//!
+//! | Event | Source |
+//! |-------|--------|
+//! | `Created` | `ContainerManager::create()` |
+//! | `Started`, `Paused`, `Resumed` | the `Container` method performing the transition |
+//! | `Exited` | the init pidfd becoming readable, or the container reaping its init |
+//! | `OomKilled`, `MemoryHigh` | `oom_kill` and `high` counters of `memory.events` (inotify) |
+//! | `CgroupEmptied` | `populated` of `cgroup.events` dropping to 0 (inotify) |
+//!
+//! Containers report their own transitions through a channel to every
+//! monitor watching them; an eventfd wakes a monitor blocked in `poll(2)`
+//! on the inotify descriptor and the init pidfds.
+//!
+//! `Exited` is reported once per run, by whichever source sees it first.
+//! When the pidfd wins, the status is peeked with `WNOWAIT`, so the init
+//! process stays reapable by `Container::wait()`.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! let manager = ContainerManager::new();
+//! let mut events = manager.subscribe()?;
+//! manager.create("web-1", config)?;
+//! manager.start("web-1")?;
+//!
+//! while let Some((id, event)) = events.next_event(None)? {
+//!     if let ContainerEvent::OomKilled { count } = event {
+//!         eprintln!("{id}: OOM killer ended {count} processes");
+//!     }
+//! }
+//! ```
+
+use core::fmt;
+use std::collections::{HashMap, VecDeque};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::container::{Container, ContainerError, ExitStatus};
+use crate::pidfd::PidFd;
+use crate::stats::MemoryEvents;
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Lifecycle event of a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerEvent {
+    /// Container created through a subscribed `ContainerManager`
+    Created,
+    /// Init process started
+    Started {
+        /// Init PID in the runtime's PID namespace
+        pid: u32,
+    },
+    /// Processes frozen
+    Paused,
+    /// Processes thawed
+    Resumed,
+    /// Init process terminated
+    Exited(ExitStatus),
+    /// The OOM killer ended processes of the container
+    OomKilled {
+        /// Processes killed since the previous event
+        count: u64,
+    },
+    /// The container was throttled above `memory.high`
+    MemoryHigh {
+        /// Throttling events since the previous event
+        count: u64,
+    },
+    /// The last process left the cgroup
+    CgroupEmptied,
+}
+
+impl fmt::Display for ContainerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Started { pid } => write!(f, "started (pid {pid})"),
+            Self::Paused => write!(f, "paused"),
+            Self::Resumed => write!(f, "resumed"),
+            Self::Exited(status) => write!(f, "exited ({status})"),
+            Self::OomKilled { count } => write!(f, "oom-killed ({count} processes)"),
+            Self::MemoryHigh { count } => write!(f, "memory.high exceeded ({count} times)"),
+            Self::CgroupEmptied => write!(f, "cgroup emptied"),
+        }
+    }
+}
+
+// ============================================================================
+// Notices
+// ============================================================================
+
+/// Message from a container or manager to a monitor
+#[derive(Debug)]
+pub(crate) enum Notice {
+    /// Start watching a container's cgroup files and init process
+    Watch {
+        id: String,
+        cgroup: PathBuf,
+        init: Option<PidFd>,
+    },
+    /// Transition performed by the container, with the new init for `Started`
+    Event {
+        id: String,
+        event: ContainerEvent,
+        init: Option<PidFd>,
+    },
+}
+
+impl Notice {
+    /// Watch the current cgroup and init process of `container`
+    pub(crate) fn watch(container: &Container) -> Self {
+        Self::Watch {
+            id: container.id().to_string(),
+            cgroup: container.cgroup_path().to_path_buf(),
+            init: container.init_handle().and_then(PidFd::try_clone),
+        }
+    }
+}
+
+/// Sending end of a monitor, held by the containers it watches
+#[derive(Debug, Clone)]
+pub(crate) struct EventSender {
+    tx: Sender<Notice>,
+    /// eventfd the monitor polls
+    wake: Arc<OwnedFd>,
+}
+
+impl EventSender {
+    /// Queue a notice and wake the monitor
+    ///
+    /// Returns `false` once the monitor has been dropped.
+    pub(crate) fn send(&self, notice: Notice) -> bool {
+        if self.tx.send(notice).is_err() {
+            return false;
+        }
+        let one = 1u64.to_ne_bytes();
+        // SAFETY: wake is an open non-blocking eventfd and `one` is a valid 8-byte buffer.
+        // A saturated counter (EAGAIN) still leaves the eventfd readable.
+        unsafe { libc::write(self.wake.as_raw_fd(), one.as_ptr().cast(), one.len()) };
+        true
+    }
+
+    /// Whether both ends belong to the same monitor
+    pub(crate) fn is_same_monitor(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.wake, &other.wake)
+    }
+}
+
+// ============================================================================
+// Cgroup Snapshot
+// ============================================================================
+
+/// Watched state of a container's cgroup files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CgroupSnapshot {
+    memory: MemoryEvents,
+    populated: bool,
+}
+
+impl CgroupSnapshot {
+    /// Read `memory.events` and `cgroup.events`; missing files read as zero
+    fn read(cgroup: &Path) -> Self {
+        let memory = std::fs::read_to_string(cgroup.join("memory.events"))
+            .map(|content| MemoryEvents::from_memory_events(&content))
+            .unwrap_or_default();
+        let populated = std::fs::read_to_string(cgroup.join("cgroup.events"))
+            .ok()
+            .and_then(|content| crate::cgroup::parse_populated(&content))
+            .unwrap_or(false);
+        Self { memory, populated }
+    }
+
+    /// Events implied by moving from `self` to `next`
+    fn changes(&self, next: &Self) -> Vec<ContainerEvent> {
+        let mut events = Vec::new();
+        let oom_kills = next.memory.oom_kill.saturating_sub(self.memory.oom_kill);
+        if oom_kills > 0 {
+            events.push(ContainerEvent::OomKilled { count: oom_kills });
+        }
+        let high = next.memory.high.saturating_sub(self.memory.high);
+        if high > 0 {
+            events.push(ContainerEvent::MemoryHigh { count: high });
+        }
+        if self.populated && !next.populated {
+            events.push(ContainerEvent::CgroupEmptied);
+        }
+        events
+    }
+}
+
+/// Split a buffer read from an inotify descriptor into `(wd, mask)` pairs
+fn parse_inotify(buf: &[u8]) -> Vec<(i32, u32)> {
+    // struct inotify_event { int wd; u32 mask; u32 cookie; u32 len; char name[]; }
+    const HEADER: usize = 16;
+    let field = |at: usize| [buf[at], buf[at + 1], buf[at + 2], buf[at + 3]];
+
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER <= buf.len() {
+        let wd = i32::from_ne_bytes(field(offset));
+        let mask = u32::from_ne_bytes(field(offset + 4));
+        let len = u32::from_ne_bytes(field(offset + 12)) as usize;
+        events.push((wd, mask));
+        offset += HEADER + len;
+    }
+    events
+}
+
+// ============================================================================
+// Monitor
+// ============================================================================
+
+/// A watched container
+#[derive(Debug)]
+struct Watched {
+    cgroup: PathBuf,
+    snapshot: CgroupSnapshot,
+    /// inotify watch descriptors of the cgroup files
+    wds: Vec<i32>,
+    /// Init process of the current run, until its exit is reported
+    init: Option<PidFd>,
+    /// Whether `Exited` was reported for the current run
+    exited: bool,
+}
+
+/// Receives the events of a set of containers
+///
+/// Created by `Container::subscribe()`, `ContainerManager::subscribe()` or
+/// `EventMonitor::new()` followed by `watch()`. A container stays watched
+/// until `unwatch()` is called or its cgroup is removed.
+#[derive(Debug)]
+pub struct EventMonitor {
+    inotify: OwnedFd,
+    wake: Arc<OwnedFd>,
+    tx: Sender<Notice>,
+    rx: Receiver<Notice>,
+    containers: HashMap<String, Watched>,
+    /// inotify watch descriptor to container ID
+    watches: HashMap<i32, String>,
+    pending: VecDeque<(String, ContainerEvent)>,
+}
+
+impl EventMonitor {
+    /// Create a monitor watching no container
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the inotify or eventfd descriptor cannot be created.
+    #[cfg(target_os = "linux")]
+    pub fn new() -> Result<Self, ContainerError> {
+        use std::os::unix::io::FromRawFd;
+
+        // SAFETY: inotify_init1(2) takes flags only and returns a new descriptor or -1.
+        let inotify = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if inotify < 0 {
+            return Err(io_error("inotify_init1", &std::io::Error::last_os_error()));
+        }
+        // SAFETY: inotify is a new descriptor that nothing else owns.
+        let inotify = unsafe { OwnedFd::from_raw_fd(inotify) };
+
+        // SAFETY: eventfd(2) takes an initial value and flags and returns a new descriptor or -1.
+        let wake = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if wake < 0 {
+            return Err(io_error("eventfd", &std::io::Error::last_os_error()));
+        }
+        // SAFETY: wake is a new descriptor that nothing else owns.
+        let wake = Arc::new(unsafe { OwnedFd::from_raw_fd(wake) });
+
+        let (tx, rx) = mpsc::channel();
+        Ok(Self {
+            inotify,
+            wake,
+            tx,
+            rx,
+            containers: HashMap::new(),
+            watches: HashMap::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Create a monitor (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Result<Self, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container events require Linux".into(),
+        ))
+    }
+
+    /// Start delivering the events of `container`
+    ///
+    /// Watching a container again resets its counters to the current values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cgroup files cannot be watched.
+    pub fn watch(&mut self, container: &mut Container) -> Result<(), ContainerError> {
+        self.handle(Notice::watch(container))?;
+        container.add_event_sender(self.sender());
+        Ok(())
+    }
+
+    /// Stop delivering the events of a container
+    pub fn unwatch(&mut self, id: &str) {
+        if let Some(watched) = self.containers.remove(id) {
+            for wd in watched.wds {
+                self.watches.remove(&wd);
+                // SAFETY: inotify is an open inotify descriptor; a stale wd only fails.
+                unsafe { libc::inotify_rm_watch(self.inotify.as_raw_fd(), wd) };
+            }
+        }
+        self.pending.retain(|(pending, _)| pending != id);
+    }
+
+    /// IDs of the watched containers
+    #[must_use]
+    pub fn watched(&self) -> Vec<String> {
+        self.containers.keys().cloned().collect()
+    }
+
+    /// Wait up to `timeout` (forever if `None`) for the next event
+    ///
+    /// Returns `None` if no event arrived in time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if polling or reading the inotify descriptor fails.
+    pub fn next_event(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<(String, ContainerEvent)>, ContainerError> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            while let Ok(notice) = self.rx.try_recv() {
+                // Failures only cost the cgroup events of that container
+                let _ = self.handle(notice);
+            }
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let timeout_ms = match deadline {
+                None => -1,
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Ok(None);
+                    }
+                    left.as_millis().clamp(1, libc::c_int::MAX as u128) as libc::c_int
+                }
+            };
+            self.poll(timeout_ms)?;
+        }
+    }
+
+    /// Sending end for containers reporting to this monitor
+    pub(crate) fn sender(&self) -> EventSender {
+        EventSender {
+            tx: self.tx.clone(),
+            wake: Arc::clone(&self.wake),
+        }
+    }
+
+    /// Apply a notice, queueing the event it carries
+    fn handle(&mut self, notice: Notice) -> Result<(), ContainerError> {
+        match notice {
+            Notice::Watch { id, cgroup, init } => self.add(id, cgroup, init),
+            Notice::Event { id, event, init } => {
+                let Some(watched) = self.containers.get_mut(&id) else {
+                    return Ok(());
+                };
+                match event {
+                    ContainerEvent::Started { .. } => {
+                        watched.init = init;
+                        watched.exited = false;
+                    }
+                    ContainerEvent::Exited(_) => {
+                        watched.init = None;
+                        if std::mem::replace(&mut watched.exited, true) {
+                            return Ok(());
+                        }
+                    }
+                    _ => {}
+                }
+                self.pending.push_back((id, event));
+                Ok(())
+            }
+        }
+    }
+
+    /// Watch the cgroup files of a container
+    fn add(
+        &mut self,
+        id: String,
+        cgroup: PathBuf,
+        init: Option<PidFd>,
+    ) -> Result<(), ContainerError> {
+        self.unwatch(&id);
+
+        let mut wds = Vec::new();
+        for file in ["cgroup.events", "memory.events"] {
+            let path = cgroup.join(file);
+            match add_watch(&self.inotify, &path) {
+                Ok(wd) => {
+                    self.watches.insert(wd, id.clone());
+                    wds.push(wd);
+                }
+                // No memory controller: no memory events
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(io_error(&path.display().to_string(), &e)),
+            }
+        }
+
+        // Read after the watches are in place so no change is missed
+        let snapshot = CgroupSnapshot::read(&cgroup);
+        self.containers.insert(
+            id,
+            Watched {
+                cgroup,
+                snapshot,
+                wds,
+                init,
+                exited: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Wait for the wake eventfd, inotify or an init pidfd and queue the results
+    fn poll(&mut self, timeout_ms: libc::c_int) -> Result<(), ContainerError> {
+        let mut fds = vec![
+            libc::pollfd {
+                fd: self.wake.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let mut inits = Vec::new();
+        for (id, watched) in &self.containers {
+            if let Some(fd) = watched.init.as_ref().and_then(PidFd::as_raw_fd) {
+                fds.push(libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                });
+                inits.push(id.clone());
+            }
+        }
+
+        // SAFETY: fds is a valid array of pollfd for open descriptors.
+        let ret = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout_ms) };
+        if ret < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(());
+            }
+            return Err(io_error("poll", &err));
+        }
+
+        if fds[0].revents != 0 {
+            let mut count = [0u8; 8];
+            // SAFETY: wake is an open eventfd and count is a valid 8-byte buffer.
+            unsafe { libc::read(fds[0].fd, count.as_mut_ptr().cast(), count.len()) };
+        }
+        if fds[1].revents != 0 {
+            self.read_inotify()?;
+        }
+        for (pfd, id) in fds[2..].iter().zip(inits) {
+            if pfd.revents != 0 {
+                self.init_exited(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drain the inotify descriptor and diff the changed cgroups
+    fn read_inotify(&mut self) -> Result<(), ContainerError> {
+        let mut changed: Vec<String> = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            // SAFETY: inotify is an open descriptor and buf is valid for its length.
+            let n =
+                unsafe { libc::read(self.inotify.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+            if n < 0 {
+                let err = std::io::Error::last_os_error();
+                match err.kind() {
+                    std::io::ErrorKind::WouldBlock => break,
+                    std::io::ErrorKind::Interrupted => continue,
+                    _ => return Err(io_error("read inotify", &err)),
+                }
+            }
+
+            for (wd, mask) in parse_inotify(&buf[..n as usize]) {
+                if mask & libc::IN_IGNORED != 0 {
+                    // The cgroup was removed
+                    if let Some(id) = self.watches.remove(&wd) {
+                        if let Some(watched) = self.containers.get_mut(&id) {
+                            watched.wds.retain(|&w| w != wd);
+                        }
+                    }
+                } else if let Some(id) = self.watches.get(&wd) {
+                    if !changed.contains(id) {
+                        changed.push(id.clone());
+                    }
+                }
+            }
+        }
+
+        for id in changed {
+            let Some(watched) = self.containers.get_mut(&id) else {
+                continue;
+            };
+            let snapshot = CgroupSnapshot::read(&watched.cgroup);
+            for event in watched.snapshot.changes(&snapshot) {
+                self.pending.push_back((id.clone(), event));
+            }
+            watched.snapshot = snapshot;
+        }
+        Ok(())
+    }
+
+    /// Report the exit of a container's init once its pidfd is readable
+    fn init_exited(&mut self, id: String) {
+        let Some(watched) = self.containers.get_mut(&id) else {
+            return;
+        };
+        let Some(init) = watched.init.take() else {
+            return;
+        };
+        // An error means the container has already reaped it and reports the status itself
+        if let Ok(Some(status)) = init.peek_exit() {
+            if !std::mem::replace(&mut watched.exited, true) {
+                self.pending.push_back((id, ContainerEvent::Exited(status)));
+            }
+        }
+    }
+}
+
+/// `inotify_add_watch(2)` for modifications of `path`
+#[cfg(target_os = "linux")]
+fn add_watch(inotify: &OwnedFd, path: &Path) -> std::io::Result<i32> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    // SAFETY: inotify is an open inotify descriptor and path a valid C string.
+    let wd =
+        unsafe { libc::inotify_add_watch(inotify.as_raw_fd(), path.as_ptr(), libc::IN_MODIFY) };
+    if wd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(wd)
+}
+
+/// `inotify_add_watch(2)` (non-Linux stub)
+#[cfg(not(target_os = "linux"))]
+fn add_watch(_inotify: &OwnedFd, _path: &Path) -> std::io::Result<i32> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+}
+
+/// Convert an I/O error of `what`
+fn io_error(what: &str, err: &std::io::Error) -> ContainerError {
+    ContainerError::IoError(format!("{what}: {err}"))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_changes() {
+        let before = CgroupSnapshot {
+            memory: MemoryEvents {
+                high: 3,
+                oom_kill: 1,
+                ..MemoryEvents::default()
+            },
+            populated: true,
+        };
+        assert!(before.changes(&before).is_empty());
+
+        let after = CgroupSnapshot {
+            memory: MemoryEvents {
+                high: 5,
+                oom: 1,
+                oom_kill: 2,
+                ..MemoryEvents::default()
+            },
+            populated: false,
+        };
+        assert_eq!(
+            before.changes(&after),
+            vec![
+                ContainerEvent::OomKilled { count: 1 },
+                ContainerEvent::MemoryHigh { count: 2 },
+                ContainerEvent::CgroupEmptied,
+            ]
+        );
+
+        // Becoming populated is reported as `Started`, not from the cgroup
+        assert!(after.changes(&before).is_empty());
+    }
+
+    #[test]
+    fn test_parse_inotify() {
+        let mut buf = Vec::new();
+        for (wd, mask, name) in [
+            (1i32, libc::IN_MODIFY, &b""[..]),
+            (2, libc::IN_IGNORED, b"x\0\0\0"),
+        ] {
+            buf.extend_from_slice(&wd.to_ne_bytes());
+            buf.extend_from_slice(&mask.to_ne_bytes());
+            buf.extend_from_slice(&0u32.to_ne_bytes());
+            buf.extend_from_slice(&(name.len() as u32).to_ne_bytes());
+            buf.extend_from_slice(name);
+        }
+        assert_eq!(
+            parse_inotify(&buf),
+            vec![(1, libc::IN_MODIFY), (2, libc::IN_IGNORED)]
+        );
+        assert!(parse_inotify(&buf[..8]).is_empty());
+    }
+
+    #[test]
+    fn test_monitor_delivers_file_and_container_events() {
+        let dir =
+            std::env::temp_dir().join(format!("alice-container-events-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cgroup.events"), "populated 1\nfrozen 0\n").unwrap();
+        std::fs::write(
+            dir.join("memory.events"),
+            "low 0\nhigh 0\nmax 0\noom 0\noom_kill 0\n",
+        )
+        .unwrap();
+
+        let mut monitor = EventMonitor::new().unwrap();
+        monitor.add("web-1".into(), dir.clone(), None).unwrap();
+        assert_eq!(monitor.watched(), vec!["web-1".to_string()]);
+        let timeout = Some(Duration::from_secs(5));
+
+        std::fs::write(
+            dir.join("memory.events"),
+            "low 0\nhigh 0\nmax 1\noom 1\noom_kill 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            monitor.next_event(timeout).unwrap(),
+            Some(("web-1".into(), ContainerEvent::OomKilled { count: 1 }))
+        );
+
+        std::fs::write(dir.join("cgroup.events"), "populated 0\nfrozen 0\n").unwrap();
+        assert_eq!(
+            monitor.next_event(timeout).unwrap(),
+            Some(("web-1".into(), ContainerEvent::CgroupEmptied))
+        );
+
+        // Transitions reported by the container from another thread
+        let sender = monitor.sender();
+        std::thread::spawn(move || {
+            for event in [
+                ContainerEvent::Exited(ExitStatus::Exited(0)),
+                ContainerEvent::Exited(ExitStatus::Exited(0)),
+                ContainerEvent::Paused,
+            ] {
+                sender.send(Notice::Event {
+                    id: "web-1".into(),
+                    event,
+                    init: None,
+                });
+            }
+        });
+        assert_eq!(
+            monitor.next_event(timeout).unwrap(),
+            Some((
+                "web-1".into(),
+                ContainerEvent::Exited(ExitStatus::Exited(0))
+            ))
+        );
+        // The second exit of the same run is dropped
+        assert_eq!(
+            monitor.next_event(timeout).unwrap(),
+            Some(("web-1".into(), ContainerEvent::Paused))
+        );
+
+        monitor.unwatch("web-1");
+        assert!(monitor.watched().is_empty());
+        assert_eq!(monitor.next_event(Some(Duration::ZERO)).unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
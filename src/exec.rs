@@ -0,0 +1,249 @@
+//! Streaming Exec
+//!
+//! Commands started in a running container with `Container::exec_spawn()`,
+//! with their standard streams connected to the caller.
+//!
+//! ```text
+//! runtime                    exec helper              command
+//!   ExecProcess.stdin  ──── pipe ─────────────────────► stdin
+//!   ExecProcess.stdout ◄─── pipe ────────────────────── stdout
+//!   ExecProcess.stderr ◄─── pipe ────────────────────── stderr
+//!   wait() ◄──────────────── exit code ◄── waitpid ─────┘
+//! ```
+//!
+//! With `tty`, the command instead runs in a new session on a
+//! pseudo-terminal and all three streams go through `ExecProcess.console`.
+//!
+//! The command is a child of the exec helper, which joined the container's
+//! namespaces; `wait()` reaps the helper, which exits with the command's
+//! exit code. The command gets `SIGKILL` if the helper dies.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::console::Console;
+use crate::container::{ContainerConfig, ContainerError, ProcessUser};
+use crate::pidfd::PidFd;
+
+// ============================================================================
+// Options
+// ============================================================================
+
+/// How `Container::exec_spawn()` runs a command
+///
+/// Unset options are taken from the container configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecOptions {
+    /// Variables added to the container environment, replacing equal keys
+    pub env: Vec<(String, String)>,
+    /// Working directory inside the container
+    pub workdir: Option<PathBuf>,
+    /// User and groups the command runs as
+    pub user: Option<ProcessUser>,
+    /// Run the command on a new pseudo-terminal
+    pub tty: bool,
+}
+
+impl ExecOptions {
+    /// Options running the command like the container's init process
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an environment variable
+    #[must_use]
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set the working directory
+    #[must_use]
+    pub fn workdir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.workdir = Some(path.into());
+        self
+    }
+
+    /// Set the user and groups
+    #[must_use]
+    pub fn user(mut self, user: ProcessUser) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Run on a new pseudo-terminal sized like the container's console
+    #[must_use]
+    pub const fn tty(mut self, enable: bool) -> Self {
+        self.tty = enable;
+        self
+    }
+
+    /// The container configuration with these options applied
+    pub(crate) fn apply(&self, config: &ContainerConfig) -> ContainerConfig {
+        let mut config = config.clone();
+        for (key, value) in &self.env {
+            match config.env.iter_mut().find(|(k, _)| k == key) {
+                Some((_, v)) => v.clone_from(value),
+                None => config.env.push((key.clone(), value.clone())),
+            }
+        }
+        if let Some(workdir) = &self.workdir {
+            config.workdir.clone_from(workdir);
+        }
+        if let Some(user) = &self.user {
+            config.user = user.clone();
+        }
+        config
+    }
+}
+
+// ============================================================================
+// Process
+// ============================================================================
+
+/// Command started with `Container::exec_spawn()`
+///
+/// Like `std::process::Child`, the streams are public so they can be taken
+/// and moved to other threads. Dropping the handle does not stop the
+/// command; the helper stays a zombie until the runtime exits.
+#[derive(Debug)]
+pub struct ExecProcess {
+    /// Writes to the command's stdin (`None` with `tty`)
+    pub stdin: Option<File>,
+    /// Reads the command's stdout (`None` with `tty`)
+    pub stdout: Option<File>,
+    /// Reads the command's stderr (`None` with `tty`)
+    pub stderr: Option<File>,
+    /// Terminal master carrying all three streams (`tty` only)
+    pub console: Option<Console>,
+    /// The command, for signalling; `None` if it exited before it was opened
+    process: Option<PidFd>,
+    /// PID of the command in the runtime's PID namespace
+    pid: u32,
+    /// Exec helper, whose exit code is the command's
+    helper: PidFd,
+}
+
+impl ExecProcess {
+    pub(crate) const fn new(helper: PidFd, pid: u32, process: Option<PidFd>) -> Self {
+        Self {
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            console: None,
+            process,
+            pid,
+            helper,
+        }
+    }
+
+    /// PID of the command, as seen from the runtime
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Handle of the command, if it could be opened
+    #[must_use]
+    pub const fn pidfd(&self) -> Option<&PidFd> {
+        self.process.as_ref()
+    }
+
+    /// Send a signal to the command
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if the command has exited, or an error if the
+    /// signal is invalid.
+    pub fn kill(&self, signal: i32) -> Result<(), ContainerError> {
+        match &self.process {
+            Some(process) => process.send_signal(signal),
+            None => Err(ContainerError::NotFound(format!("process {}", self.pid))),
+        }
+    }
+
+    /// Wait for the command to exit and return its exit code
+    ///
+    /// Closes `stdin` first, so a command reading it sees end-of-file.
+    /// The code is `128 + signal` if the command was killed by a signal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the helper cannot be waited for.
+    pub fn wait(&mut self) -> Result<i32, ContainerError> {
+        self.stdin = None;
+        Ok(self.helper.wait()?.code())
+    }
+
+    /// Wait up to `timeout` for the command to exit
+    ///
+    /// Returns `None` if it is still running; `stdin` is left open.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the helper cannot be waited for.
+    #[cfg(target_os = "linux")]
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<i32>, ContainerError> {
+        Ok(self
+            .helper
+            .wait_timeout(timeout)?
+            .map(|status| status.code()))
+    }
+
+    /// Wait for the command with a timeout (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait_timeout(&mut self, _timeout: Duration) -> Result<Option<i32>, ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_options_apply() {
+        let config = ContainerConfig::builder()
+            .env("MODE", "prod")
+            .user(1000, 1000)
+            .build();
+        let options = ExecOptions::new()
+            .env("MODE", "debug")
+            .env("RCON", "1")
+            .workdir("/srv/game")
+            .user(ProcessUser::new(0, 0));
+
+        let applied = options.apply(&config);
+        let value = |key: &str| {
+            applied
+                .env
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(value("MODE"), Some("debug"));
+        assert_eq!(value("RCON"), Some("1"));
+        assert_eq!(value("PATH"), Some("/usr/local/bin:/usr/bin:/bin"));
+        assert_eq!(applied.env.len(), config.env.len() + 1);
+        assert_eq!(applied.workdir, PathBuf::from("/srv/game"));
+        assert!(applied.user.is_root());
+
+        // Nothing set leaves the configuration as it is
+        let unchanged = ExecOptions::new().apply(&config);
+        assert_eq!(unchanged.env, config.env);
+        assert_eq!(unchanged.workdir, config.workdir);
+        assert_eq!(unchanged.user, config.user);
+    }
+}
@@ -253,7 +253,7 @@ pub unsafe extern "C" fn ac_ctr_container_id(ptr: *const Container) -> *mut c_ch
     }
 }
 
-/// Get container state: 0=Created, 1=Running, 2=Paused, 3=Stopped.
+/// Get container state: 0=Created, 1=Running, 2=Paused, 3=Stopped, 4=Exited.
 #[no_mangle]
 pub unsafe extern "C" fn ac_ctr_container_state(ptr: *const Container) -> u8 {
     if ptr.is_null() {
@@ -264,6 +264,7 @@ pub unsafe extern "C" fn ac_ctr_container_state(ptr: *const Container) -> u8 {
         crate::container::ContainerState::Running => 1,
         crate::container::ContainerState::Paused => 2,
         crate::container::ContainerState::Stopped => 3,
+        crate::container::ContainerState::Exited { .. } => 4,
     }
 }
 
@@ -0,0 +1,654 @@
+//! Parent/Child Handshake for Container Init
+//!
+//! A `SOCK_SEQPACKET` socket pair connects `Container::start()` with the
+//! freshly cloned init process.
+//!
+//! ```text
+//! parent                                   child (init)
+//!   │ clone / clone3                         │
+//!   │ ◀──────────────────────────────  READY │
+//!   │                                        │ wait for CONTINUE
+//!   │ uid_map / gid_map                      │
+//!   │ cgroup placement (legacy path)         │
+//!   │ veth setup                             │
+//!   │ CONTINUE ────────────────────────────▶ │
+//!   │                                        │ mounts, pivot_root, ...
+//!   │ ◀──────────────────────────── CREATED  │ (only with startContainer hooks)
+//!   │ startContainer hooks                   │ wait for CONTINUE
+//!   │ CONTINUE ────────────────────────────▶ │
+//!   │ ◀──────────────────────────── ERROR(e) │ (on failure)
+//!   │ ◀─────────────────────────────── EOF   │ execve (CLOEXEC closes the socket)
+//! ```
+//!
+//! `READY` proves that the child actually runs: on some kernels a child
+//! cloned with `CLONE_INTO_CGROUP` into a cgroup that was once killed via
+//! `cgroup.kill` is SIGKILLed before it executes any code, and the runtime
+//! then falls back to the legacy clone path.
+//!
+//! The child's end is `SOCK_CLOEXEC`, so a successful `execve(2)` is observed
+//! by the parent as end-of-file. Any failure is sent as a serialized
+//! [`ContainerError`] so that `start()` returns the real cause.
+
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use crate::cgroup::CgroupError;
+use crate::container::{ContainerError, InitStage};
+use crate::namespace::NamespaceError;
+use crate::rootfs::RootFsError;
+
+// ============================================================================
+// Wire Format
+// ============================================================================
+
+/// Parent → child: setup is complete, continue with init
+const MSG_CONTINUE: u8 = 1;
+/// Child → parent: init failed, followed by an encoded error
+const MSG_ERROR: u8 = 2;
+/// Child → parent: the child is running and waits for `CONTINUE`
+const MSG_READY: u8 = 3;
+/// Child → parent: the container is set up and waits for `CONTINUE` before exec
+const MSG_CREATED: u8 = 4;
+
+/// Maximum size of a single handshake message
+const MAX_MESSAGE: usize = 4096;
+
+/// Maximum length of an encoded string field
+const MAX_STRING: usize = 1024;
+
+// Top-level error tags
+const TAG_CGROUP: u8 = 1;
+const TAG_NAMESPACE: u8 = 2;
+const TAG_ROOTFS: u8 = 3;
+const TAG_PROCESS: u8 = 4;
+const TAG_CONFIG: u8 = 5;
+const TAG_IO: u8 = 6;
+const TAG_NOT_FOUND: u8 = 7;
+const TAG_INIT: u8 = 8;
+
+/// Serialize a container error for transfer to the parent
+#[must_use]
+pub fn encode_error(error: &ContainerError) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    write_error(&mut buf, error);
+    buf
+}
+
+/// Deserialize a container error sent by the child
+///
+/// Returns `None` if the message is malformed.
+#[must_use]
+pub fn decode_error(buf: &[u8]) -> Option<ContainerError> {
+    let mut reader = Reader { buf, pos: 0 };
+    let error = reader.error()?;
+    (reader.pos == buf.len()).then_some(error)
+}
+
+fn write_error(buf: &mut Vec<u8>, error: &ContainerError) {
+    match error {
+        ContainerError::Cgroup(e) => {
+            buf.push(TAG_CGROUP);
+            match e {
+                CgroupError::NotFound(s) => write_tagged(buf, 1, s),
+                CgroupError::PermissionDenied => buf.push(2),
+                CgroupError::InvalidParameter(s) => write_tagged(buf, 3, s),
+                CgroupError::IoError(s) => write_tagged(buf, 4, s),
+                CgroupError::CgroupV2NotAvailable => buf.push(5),
+                CgroupError::ControllerNotEnabled(s) => write_tagged(buf, 6, s),
+            }
+        }
+        ContainerError::Namespace(e) => {
+            buf.push(TAG_NAMESPACE);
+            match e {
+                NamespaceError::PermissionDenied => buf.push(1),
+                NamespaceError::InvalidArgument => buf.push(2),
+                NamespaceError::OutOfMemory => buf.push(3),
+                NamespaceError::NotInNamespace(ns) => write_tagged(buf, 4, ns),
+                NamespaceError::InvalidPath => buf.push(5),
+                NamespaceError::NotSupported => buf.push(6),
+                NamespaceError::OsError(errno) => {
+                    buf.push(7);
+                    buf.extend_from_slice(&errno.to_le_bytes());
+                }
+            }
+        }
+        ContainerError::RootFs(e) => {
+            buf.push(TAG_ROOTFS);
+            match e {
+                RootFsError::PathNotFound(s) => write_tagged(buf, 1, s),
+                RootFsError::PermissionDenied => buf.push(2),
+                RootFsError::MountFailed(s) => write_tagged(buf, 3, s),
+                RootFsError::DeviceCreationFailed(s) => write_tagged(buf, 4, s),
+                RootFsError::IoError(s) => write_tagged(buf, 5, s),
+                RootFsError::NotSupported => buf.push(6),
+            }
+        }
+        ContainerError::ProcessError(s) => write_tagged(buf, TAG_PROCESS, s),
+        ContainerError::ConfigError(s) => write_tagged(buf, TAG_CONFIG, s),
+        ContainerError::IoError(s) => write_tagged(buf, TAG_IO, s),
+        ContainerError::NotFound(s) => write_tagged(buf, TAG_NOT_FOUND, s),
+        ContainerError::Init { stage, source } => {
+            buf.push(TAG_INIT);
+            buf.push(*stage as u8);
+            write_error(buf, source);
+        }
+        // Remaining variants are not produced by the child; keep their message
+        other => write_tagged(buf, TAG_PROCESS, &other.to_string()),
+    }
+}
+
+fn write_tagged(buf: &mut Vec<u8>, tag: u8, s: &str) {
+    buf.push(tag);
+    write_string(buf, s);
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    let mut end = s.len().min(MAX_STRING);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    buf.extend_from_slice(&(end as u16).to_le_bytes());
+    buf.extend_from_slice(&s.as_bytes()[..end]);
+}
+
+/// Cursor over an encoded error
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn u8(&mut self) -> Option<u8> {
+        let b = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&[u8]> {
+        let slice = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = u16::from_le_bytes(self.bytes(2)?.try_into().ok()?) as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).ok()
+    }
+
+    fn error(&mut self) -> Option<ContainerError> {
+        let error = match self.u8()? {
+            TAG_CGROUP => ContainerError::Cgroup(match self.u8()? {
+                1 => CgroupError::NotFound(self.string()?),
+                2 => CgroupError::PermissionDenied,
+                3 => CgroupError::InvalidParameter(self.string()?),
+                4 => CgroupError::IoError(self.string()?),
+                5 => CgroupError::CgroupV2NotAvailable,
+                6 => CgroupError::ControllerNotEnabled(self.string()?),
+                _ => return None,
+            }),
+            TAG_NAMESPACE => ContainerError::Namespace(match self.u8()? {
+                1 => NamespaceError::PermissionDenied,
+                2 => NamespaceError::InvalidArgument,
+                3 => NamespaceError::OutOfMemory,
+                4 => NamespaceError::NotInNamespace(static_namespace_name(&self.string()?)),
+                5 => NamespaceError::InvalidPath,
+                6 => NamespaceError::NotSupported,
+                7 => NamespaceError::OsError(self.i32()?),
+                _ => return None,
+            }),
+            TAG_ROOTFS => ContainerError::RootFs(match self.u8()? {
+                1 => RootFsError::PathNotFound(self.string()?),
+                2 => RootFsError::PermissionDenied,
+                3 => RootFsError::MountFailed(self.string()?),
+                4 => RootFsError::DeviceCreationFailed(self.string()?),
+                5 => RootFsError::IoError(self.string()?),
+                6 => RootFsError::NotSupported,
+                _ => return None,
+            }),
+            TAG_PROCESS => ContainerError::ProcessError(self.string()?),
+            TAG_CONFIG => ContainerError::ConfigError(self.string()?),
+            TAG_IO => ContainerError::IoError(self.string()?),
+            TAG_NOT_FOUND => ContainerError::NotFound(self.string()?),
+            TAG_INIT => {
+                let stage = InitStage::from_u8(self.u8()?)?;
+                ContainerError::Init {
+                    stage,
+                    source: Box::new(self.error()?),
+                }
+            }
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
+/// Map a namespace name back to the static string used by `NotInNamespace`
+fn static_namespace_name(name: &str) -> &'static str {
+    match name {
+        "UTS" => "UTS",
+        "PID" => "PID",
+        "IPC" => "IPC",
+        "mount" => "mount",
+        "network" => "network",
+        "user" => "user",
+        "cgroup" => "cgroup",
+        _ => "unknown",
+    }
+}
+
+// ============================================================================
+// Init Channel
+// ============================================================================
+
+/// Socket pair shared by `Container::start()` and the init process
+#[derive(Debug)]
+pub struct InitChannel {
+    /// Parent's end
+    parent: OwnedFd,
+    /// Child's end (closed in the parent once the child is running)
+    child: Option<OwnedFd>,
+}
+
+impl InitChannel {
+    /// Create a new `SOCK_SEQPACKET` socket pair
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `socketpair(2)` fails.
+    pub fn new() -> Result<Self, ContainerError> {
+        let mut fds: [RawFd; 2] = [-1; 2];
+        // SAFETY: fds is a valid out-array of two ints; SOCK_CLOEXEC keeps both ends from
+        // leaking into exec'd programs.
+        let ret = unsafe {
+            libc::socketpair(
+                libc::AF_UNIX,
+                libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+                0,
+                fds.as_mut_ptr(),
+            )
+        };
+        if ret < 0 {
+            return Err(ContainerError::IoError(format!(
+                "socketpair: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        // SAFETY: socketpair(2) succeeded, so both descriptors are open and owned by us.
+        let (parent, child) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        Ok(Self {
+            parent,
+            child: Some(child),
+        })
+    }
+
+    /// Close the child's end in the parent after the child has been created
+    pub fn close_child_end(&mut self) {
+        self.child = None;
+    }
+
+    /// Child side: close the inherited copy of the parent's end
+    ///
+    /// Uses `close(2)` directly because the child never unwinds or returns
+    /// into code that would drop the channel.
+    pub fn close_parent_end_in_child(&self) {
+        // SAFETY: only called in the child, which owns an independent copy of the descriptor.
+        unsafe {
+            libc::close(self.parent.as_raw_fd());
+        }
+    }
+
+    /// Mini-init side: close the child's end once the workload is forked
+    ///
+    /// The workload keeps its copy until `execve(2)`, so the parent still
+    /// sees a successful exec as end-of-file and a failure as an error.
+    pub fn close_child_end_in_supervisor(&self) {
+        // SAFETY: only called in the init process after forking the workload, which owns
+        // its own copy of the descriptor.
+        unsafe {
+            libc::close(self.child_fd());
+        }
+    }
+
+    /// Parent side: wait until the child is running
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child exited before it became ready.
+    pub fn wait_ready(&self) -> Result<(), ContainerError> {
+        let mut buf = [0u8; 16];
+        match recv(self.parent.as_raw_fd(), &mut buf)? {
+            1 if buf[0] == MSG_READY => Ok(()),
+            0 => Err(ContainerError::ProcessError(
+                "container init exited before it was ready".into(),
+            )),
+            _ => Err(ContainerError::ProcessError(
+                "unexpected message from container init".into(),
+            )),
+        }
+    }
+
+    /// Parent side: tell the child to continue
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child has already exited.
+    pub fn send_continue(&self) -> Result<(), ContainerError> {
+        send(self.parent.as_raw_fd(), &[MSG_CONTINUE])
+    }
+
+    /// Parent side: wait until the child execs or reports an error
+    ///
+    /// Returns `Ok(None)` once the child's end is closed by `execve(2)`,
+    /// `Ok(Some(e))` if the child reported a failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be read or the message is malformed.
+    pub fn wait_exec(&self) -> Result<Option<ContainerError>, ContainerError> {
+        let mut buf = [0u8; MAX_MESSAGE];
+        let n = recv(self.parent.as_raw_fd(), &mut buf)?;
+        match buf[..n].split_first() {
+            None => Ok(None),
+            Some((&MSG_ERROR, payload)) => decode_error(payload).map(Some).ok_or_else(|| {
+                ContainerError::ProcessError("malformed error from container init".into())
+            }),
+            Some(_) => Err(ContainerError::ProcessError(
+                "unexpected message from container init".into(),
+            )),
+        }
+    }
+
+    /// Parent side: wait until the child has set up the container
+    ///
+    /// Returns `Ok(None)` once the child waits before `execve(2)`,
+    /// `Ok(Some(e))` if the child reported a failure instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child exited or sent an unexpected message.
+    pub fn wait_created(&self) -> Result<Option<ContainerError>, ContainerError> {
+        let mut buf = [0u8; MAX_MESSAGE];
+        let n = recv(self.parent.as_raw_fd(), &mut buf)?;
+        match buf[..n].split_first() {
+            Some((&MSG_CREATED, [])) => Ok(None),
+            Some((&MSG_ERROR, payload)) => decode_error(payload).map(Some).ok_or_else(|| {
+                ContainerError::ProcessError("malformed error from container init".into())
+            }),
+            None => Err(ContainerError::ProcessError(
+                "container init exited during setup".into(),
+            )),
+            Some(_) => Err(ContainerError::ProcessError(
+                "unexpected message from container init".into(),
+            )),
+        }
+    }
+
+    /// Child side: tell the parent that the child is running
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent has gone away.
+    pub fn send_ready(&self) -> Result<(), ContainerError> {
+        send(self.child_fd(), &[MSG_READY])
+    }
+
+    /// Child side: tell the parent that the container is set up
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent has gone away.
+    pub fn send_created(&self) -> Result<(), ContainerError> {
+        send(self.child_fd(), &[MSG_CREATED])
+    }
+
+    /// Child side: block until the parent has finished its setup
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent went away before sending `CONTINUE`.
+    pub fn wait_continue(&self) -> Result<(), ContainerError> {
+        let mut buf = [0u8; 16];
+        let n = recv(self.child_fd(), &mut buf)?;
+        if n == 1 && buf[0] == MSG_CONTINUE {
+            Ok(())
+        } else {
+            Err(ContainerError::ProcessError(
+                "runtime closed the init channel".into(),
+            ))
+        }
+    }
+
+    /// Child side: report a failure to the parent
+    ///
+    /// Strings are already cut to `MAX_STRING` on a char boundary when
+    /// encoded. An error that still does not fit in one message is sent as
+    /// its flattened text, never as a truncated frame the parent cannot
+    /// decode.
+    pub fn send_error(&self, error: &ContainerError) {
+        let mut msg = vec![MSG_ERROR];
+        msg.extend_from_slice(&encode_error(error));
+        if msg.len() > MAX_MESSAGE {
+            msg.truncate(1);
+            msg.extend_from_slice(&encode_error(&ContainerError::ProcessError(
+                error.to_string(),
+            )));
+        }
+        // Nothing more can be done if the parent is gone
+        let _ = send(self.child_fd(), &msg);
+    }
+
+    /// Descriptor of the child's end
+    fn child_fd(&self) -> RawFd {
+        self.child.as_ref().map_or(-1, AsRawFd::as_raw_fd)
+    }
+}
+
+fn send(fd: RawFd, msg: &[u8]) -> Result<(), ContainerError> {
+    // SAFETY: msg is a valid buffer of msg.len() bytes; MSG_NOSIGNAL avoids SIGPIPE if the
+    // peer has exited.
+    let ret = unsafe { libc::send(fd, msg.as_ptr().cast(), msg.len(), libc::MSG_NOSIGNAL) };
+    if ret < 0 {
+        Err(ContainerError::IoError(format!(
+            "init channel send: {}",
+            std::io::Error::last_os_error()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn recv(fd: RawFd, buf: &mut [u8]) -> Result<usize, ContainerError> {
+    loop {
+        // SAFETY: buf is a valid writable buffer of buf.len() bytes.
+        let ret = unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), 0) };
+        if ret >= 0 {
+            return Ok(ret as usize);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(ContainerError::IoError(format!("init channel recv: {err}")));
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(error: &ContainerError) -> ContainerError {
+        decode_error(&encode_error(error)).expect("decodable")
+    }
+
+    #[test]
+    fn test_roundtrip_string_variants() {
+        let err = roundtrip(&ContainerError::ConfigError("bad workdir".into()));
+        assert!(matches!(err, ContainerError::ConfigError(ref s) if s == "bad workdir"));
+
+        let err = roundtrip(&ContainerError::IoError("eof".into()));
+        assert!(matches!(err, ContainerError::IoError(ref s) if s == "eof"));
+
+        let err = roundtrip(&ContainerError::ProcessError(
+            "execve /bin/x: errno 2".into(),
+        ));
+        assert!(matches!(err, ContainerError::ProcessError(ref s) if s.contains("errno 2")));
+    }
+
+    #[test]
+    fn test_roundtrip_namespace_errors() {
+        let err = roundtrip(&ContainerError::Namespace(NamespaceError::OsError(-22)));
+        assert!(matches!(
+            err,
+            ContainerError::Namespace(NamespaceError::OsError(-22))
+        ));
+
+        let err = roundtrip(&NamespaceError::NotInNamespace("UTS").into());
+        assert!(matches!(
+            err,
+            ContainerError::Namespace(NamespaceError::NotInNamespace("UTS"))
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip_cgroup_and_rootfs_errors() {
+        let err = roundtrip(&CgroupError::ControllerNotEnabled("pids".into()).into());
+        assert!(matches!(
+            err,
+            ContainerError::Cgroup(CgroupError::ControllerNotEnabled(ref s)) if s == "pids"
+        ));
+
+        let err = roundtrip(&RootFsError::MountFailed("errno: 1".into()).into());
+        assert!(matches!(
+            err,
+            ContainerError::RootFs(RootFsError::MountFailed(ref s)) if s == "errno: 1"
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip_init_stage() {
+        let err = ContainerError::Init {
+            stage: InitStage::Mount,
+            source: Box::new(RootFsError::PermissionDenied.into()),
+        };
+        match roundtrip(&err) {
+            ContainerError::Init { stage, source } => {
+                assert_eq!(stage, InitStage::Mount);
+                assert!(matches!(
+                    *source,
+                    ContainerError::RootFs(RootFsError::PermissionDenied)
+                ));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_other_variants_keep_message() {
+        let err = ContainerError::InvalidState {
+            current: crate::container::ContainerState::Stopped,
+            operation: "exec",
+        };
+        let decoded = roundtrip(&err);
+        assert!(matches!(decoded, ContainerError::ProcessError(ref s) if s.contains("exec")));
+    }
+
+    #[test]
+    fn test_long_strings_are_truncated() {
+        let long = "é".repeat(MAX_STRING);
+        let err = roundtrip(&ContainerError::ConfigError(long));
+        match err {
+            ContainerError::ConfigError(s) => assert!(s.len() <= MAX_STRING),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_channel_sends_errors_longer_than_a_message() {
+        let mut channel = InitChannel::new().unwrap();
+
+        let long = format!("{}ü", "x".repeat(MAX_STRING - 1)).repeat(5);
+        assert!(long.len() > MAX_MESSAGE);
+        channel.send_error(&ContainerError::ConfigError(long.clone()).at(InitStage::Workdir));
+        match channel.wait_exec().unwrap() {
+            Some(ContainerError::Init { stage, source }) => {
+                assert_eq!(stage, InitStage::Workdir);
+                let ContainerError::ConfigError(s) = *source else {
+                    panic!("unexpected {source:?}");
+                };
+                // Cut before the two-byte character straddling the limit
+                assert_eq!(s.len(), MAX_STRING - 1);
+                assert!(long.starts_with(&s));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        channel.close_child_end();
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        assert!(decode_error(&[]).is_none());
+        assert!(decode_error(&[0xff]).is_none());
+        assert!(decode_error(&[TAG_CONFIG, 10, 0, b'x']).is_none());
+        // Trailing garbage
+        let mut buf = encode_error(&ContainerError::IoError("x".into()));
+        buf.push(0);
+        assert!(decode_error(&buf).is_none());
+    }
+
+    #[test]
+    fn test_channel_continue_and_exec_eof() {
+        let mut channel = InitChannel::new().unwrap();
+        channel.send_ready().unwrap();
+        assert!(channel.wait_ready().is_ok());
+        channel.send_continue().unwrap();
+        assert!(channel.wait_continue().is_ok());
+
+        // Closing the child's end is what a successful execve looks like
+        channel.close_child_end();
+        assert!(channel.wait_exec().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_channel_created_handshake() {
+        let mut channel = InitChannel::new().unwrap();
+        channel.send_created().unwrap();
+        assert!(channel.wait_created().unwrap().is_none());
+
+        channel.send_error(&ContainerError::ConfigError("bad workdir".into()));
+        assert!(channel.wait_created().unwrap().is_some());
+
+        channel.close_child_end();
+        assert!(channel.wait_created().is_err());
+    }
+
+    #[test]
+    fn test_wait_ready_fails_if_child_never_runs() {
+        let mut channel = InitChannel::new().unwrap();
+        channel.close_child_end();
+        assert!(matches!(
+            channel.wait_ready(),
+            Err(ContainerError::ProcessError(_))
+        ));
+    }
+
+    #[test]
+    fn test_channel_reports_child_error() {
+        let channel = InitChannel::new().unwrap();
+        channel.send_error(&ContainerError::Init {
+            stage: InitStage::Exec,
+            source: Box::new(ContainerError::ProcessError("execve: errno 2".into())),
+        });
+        let err = channel.wait_exec().unwrap().expect("error reported");
+        assert!(err.to_string().contains("exec"));
+        assert!(err.to_string().contains("errno 2"));
+    }
+}
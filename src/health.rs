@@ -0,0 +1,441 @@
+//! Container Health Checks
+//!
+//! Periodically probes a running container and derives a health status
+//! from the results.
+//!
+//! ## Probes
+//!
+//! | Probe | Healthy when |
+//! |-------|--------------|
+//! | `Exec` | the command, run inside the container with its streams on `/dev/null`, exits 0 within the timeout |
+//! | `File` | the path exists inside the container root, without following links out of it |
+//! | `Tcp` | a TCP connection to the address succeeds (from the host) |
+//!
+//! ## Status
+//!
+//! ```text
+//!            success                 `retries` failures in a row
+//! Starting ──────────→ Healthy ←──────────────────────→ Unhealthy
+//!     │                                   success
+//!     └───────────────────────────────────────────────────→ Unhealthy
+//!          `retries` failures in a row after `start_period`
+//! ```
+//!
+//! Failures during `start_period` do not count, so slow starters are not
+//! marked unhealthy while they initialize. The status is reset to
+//! `Starting` whenever the container is (re)started.
+
+use core::fmt;
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::pidfd::PidFd;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// What a health check probes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthProbe {
+    /// Run a command inside the container; exit code 0 is healthy
+    Exec(Vec<String>),
+    /// Check that a file exists inside the container root
+    File(PathBuf),
+    /// Connect to a TCP address from the host
+    Tcp(SocketAddr),
+}
+
+/// Health check configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Probe to run
+    pub probe: HealthProbe,
+    /// Time between probes
+    pub interval: Duration,
+    /// Time after which a probe counts as failed
+    pub timeout: Duration,
+    /// Consecutive failures before the container is unhealthy
+    pub retries: u32,
+    /// Grace period after start during which failures are ignored
+    pub start_period: Duration,
+}
+
+impl HealthCheck {
+    /// Check with default timing (30s interval and timeout, 3 retries)
+    #[must_use]
+    pub const fn new(probe: HealthProbe) -> Self {
+        Self {
+            probe,
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(30),
+            retries: 3,
+            start_period: Duration::ZERO,
+        }
+    }
+
+    /// Check that runs a command inside the container
+    #[must_use]
+    pub fn exec<I, S>(cmd: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(HealthProbe::Exec(cmd.into_iter().map(Into::into).collect()))
+    }
+
+    /// Check that a file exists inside the container
+    #[must_use]
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::new(HealthProbe::File(path.into()))
+    }
+
+    /// Check that a TCP address accepts connections
+    #[must_use]
+    pub const fn tcp(addr: SocketAddr) -> Self {
+        Self::new(HealthProbe::Tcp(addr))
+    }
+
+    /// Set the time between probes
+    #[must_use]
+    pub const fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the probe timeout
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the number of consecutive failures tolerated
+    #[must_use]
+    pub const fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the start grace period
+    #[must_use]
+    pub const fn start_period(mut self, period: Duration) -> Self {
+        self.start_period = period;
+        self
+    }
+}
+
+// ============================================================================
+// Status
+// ============================================================================
+
+/// Health of a running container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// No successful probe yet
+    Starting,
+    /// The last probe succeeded
+    Healthy,
+    /// `retries` consecutive probes failed
+    Unhealthy,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Starting => write!(f, "starting"),
+            Self::Healthy => write!(f, "healthy"),
+            Self::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
+/// Probe results of one container run
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HealthState {
+    /// Current status
+    pub(crate) status: HealthStatus,
+    /// Consecutive counted failures
+    failures: u32,
+    /// When the container was started
+    started: Instant,
+    /// When the last probe started
+    last_probe: Option<Instant>,
+    /// Whether a probe is still running
+    probing: bool,
+}
+
+impl HealthState {
+    /// State of a container started at `started`
+    pub(crate) const fn new(started: Instant) -> Self {
+        Self {
+            status: HealthStatus::Starting,
+            failures: 0,
+            started,
+            last_probe: None,
+            probing: false,
+        }
+    }
+
+    /// Whether the next probe is due at `now`
+    pub(crate) fn due(&self, check: &HealthCheck, now: Instant) -> bool {
+        !self.probing
+            && self
+                .last_probe
+                .is_none_or(|last| now.duration_since(last) >= check.interval)
+    }
+
+    /// Note that a probe started at `now`
+    pub(crate) fn begin(&mut self, now: Instant) {
+        self.last_probe = Some(now);
+        self.probing = true;
+    }
+
+    /// Whether `probe` was started during this run
+    pub(crate) fn owns(&self, probe: &PendingProbe) -> bool {
+        self.started == probe.run
+    }
+
+    /// Record a probe result and return the new status
+    pub(crate) fn record(
+        &mut self,
+        check: &HealthCheck,
+        now: Instant,
+        healthy: bool,
+    ) -> HealthStatus {
+        self.last_probe = Some(now);
+        self.probing = false;
+
+        if healthy {
+            self.failures = 0;
+            self.status = HealthStatus::Healthy;
+        } else if now.duration_since(self.started) >= check.start_period {
+            self.failures = self.failures.saturating_add(1);
+            if self.failures >= check.retries.max(1) {
+                self.status = HealthStatus::Unhealthy;
+            }
+        }
+        self.status
+    }
+}
+
+// ============================================================================
+// Probes
+// ============================================================================
+
+/// A probe started under the container's lock
+///
+/// `run()` does the waiting without the container, so a slow probe never
+/// blocks other users of it.
+#[derive(Debug)]
+pub(crate) struct PendingProbe {
+    /// Start of the container run the probe belongs to
+    run: Instant,
+    /// When the probe started
+    pub(crate) at: Instant,
+    target: ProbeTarget,
+}
+
+/// What a pending probe waits for
+#[derive(Debug)]
+pub(crate) enum ProbeTarget {
+    /// Exec'd probe command, killed after the timeout
+    Exec(PidFd, Duration),
+    /// Probe command that could not be set up or exec'd
+    Failed,
+    /// Path below the root of the init with this PID
+    File(u32, PathBuf),
+    /// Address to connect to within the timeout
+    Tcp(SocketAddr, Duration),
+}
+
+impl PendingProbe {
+    /// Probe of `target` for the container run in `state`, started at `at`
+    pub(crate) const fn new(state: &HealthState, at: Instant, target: ProbeTarget) -> Self {
+        Self {
+            run: state.started,
+            at,
+            target,
+        }
+    }
+
+    /// Wait for the probe and return whether it succeeded
+    pub(crate) fn run(&self) -> bool {
+        match &self.target {
+            ProbeTarget::Exec(handle, timeout) => match handle.wait_timeout(*timeout) {
+                Ok(Some(status)) => status.code() == 0,
+                _ => {
+                    self.cancel();
+                    false
+                }
+            },
+            ProbeTarget::Failed => false,
+            ProbeTarget::File(pid, path) => probe_file(*pid, path),
+            ProbeTarget::Tcp(addr, timeout) => probe_tcp(addr, *timeout),
+        }
+    }
+
+    /// Kill and reap the probe command, if any
+    pub(crate) fn cancel(&self) {
+        if let ProbeTarget::Exec(handle, _) = &self.target {
+            // The command gets SIGKILL through its parent-death signal
+            let _ = handle.send_signal(libc::SIGKILL);
+            let _ = handle.wait();
+        }
+    }
+}
+
+/// Whether `path` exists inside the container whose init is `init_pid`
+///
+/// Resolved through `/proc/<pid>/root`, so it sees the container's mounts.
+pub(crate) fn probe_file(init_pid: u32, path: &Path) -> bool {
+    exists_in_root(Path::new(&format!("/proc/{init_pid}/root")), path)
+}
+
+/// Whether `path` exists as if `root` were `/`
+///
+/// Symlinks and `..` in the container cannot lead the lookup to the host.
+#[cfg(target_os = "linux")]
+fn exists_in_root(root: &Path, path: &Path) -> bool {
+    crate::rootfs::open_path_in_root(root, path).is_ok()
+}
+
+/// Whether `path` exists below `root` (non-Linux stub)
+#[cfg(not(target_os = "linux"))]
+fn exists_in_root(_root: &Path, _path: &Path) -> bool {
+    false
+}
+
+/// Whether `addr` accepts a TCP connection within `timeout`
+pub(crate) fn probe_tcp(addr: &SocketAddr, timeout: Duration) -> bool {
+    TcpStream::connect_timeout(addr, timeout.max(Duration::from_millis(1))).is_ok()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check() -> HealthCheck {
+        HealthCheck::file("/ready")
+            .interval(Duration::from_secs(5))
+            .retries(2)
+            .start_period(Duration::from_secs(10))
+    }
+
+    #[test]
+    fn test_health_check_builder() {
+        let check = HealthCheck::exec(["pg_isready", "-q"])
+            .interval(Duration::from_secs(1))
+            .timeout(Duration::from_millis(500));
+        assert_eq!(
+            check.probe,
+            HealthProbe::Exec(vec!["pg_isready".into(), "-q".into()])
+        );
+        assert_eq!(check.interval, Duration::from_secs(1));
+        assert_eq!(check.timeout, Duration::from_millis(500));
+        assert_eq!(check.retries, 3);
+        assert_eq!(check.start_period, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let check = check();
+        let t0 = Instant::now();
+        let mut state = HealthState::new(t0);
+        assert_eq!(state.status, HealthStatus::Starting);
+        assert!(state.due(&check, t0));
+
+        // Failures in the start period are ignored
+        let at = |secs| t0 + Duration::from_secs(secs);
+        assert_eq!(state.record(&check, at(0), false), HealthStatus::Starting);
+        assert!(!state.due(&check, at(4)));
+        assert!(state.due(&check, at(5)));
+        assert_eq!(state.record(&check, at(5), false), HealthStatus::Starting);
+
+        assert_eq!(state.record(&check, at(10), true), HealthStatus::Healthy);
+        assert_eq!(state.record(&check, at(15), false), HealthStatus::Healthy);
+        assert_eq!(state.record(&check, at(20), false), HealthStatus::Unhealthy);
+        assert_eq!(state.record(&check, at(25), true), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_unhealthy_without_ever_succeeding() {
+        let check = check();
+        let t0 = Instant::now();
+        let mut state = HealthState::new(t0);
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert_eq!(state.record(&check, at(10), false), HealthStatus::Starting);
+        assert_eq!(state.record(&check, at(15), false), HealthStatus::Unhealthy);
+    }
+
+    #[test]
+    fn test_no_probe_due_while_one_runs() {
+        let check = check();
+        let t0 = Instant::now();
+        let mut state = HealthState::new(t0);
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        state.begin(at(0));
+        let probe = PendingProbe::new(&state, at(0), ProbeTarget::Failed);
+        assert!(!state.due(&check, at(60)));
+        assert!(state.owns(&probe));
+        assert!(!probe.run());
+
+        assert_eq!(state.record(&check, probe.at, true), HealthStatus::Healthy);
+        assert!(!state.due(&check, at(4)));
+        assert!(state.due(&check, at(5)));
+
+        // A restarted container does not take results of older probes
+        let restarted = HealthState::new(at(1));
+        assert!(!restarted.owns(&probe));
+    }
+
+    #[test]
+    fn test_probe_file_stays_below_root() {
+        let pid = std::process::id();
+        // Our own root is "/", so absolute and traversing paths both resolve inside it
+        assert!(probe_file(pid, Path::new("/proc")));
+        assert!(probe_file(pid, Path::new("../../proc")));
+        assert!(!probe_file(pid, Path::new("/nonexistent-alice-health")));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_probe_file_ignores_symlinks_to_the_host() {
+        let root =
+            std::env::temp_dir().join(format!("alice-container-health-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc/ready"), b"").unwrap();
+        std::os::unix::fs::symlink("/proc/self", root.join("escape")).unwrap();
+
+        assert!(exists_in_root(&root, Path::new("/etc/ready")));
+        assert!(exists_in_root(&root, Path::new("/../etc/ready")));
+        // Resolved inside the root, where /proc does not exist
+        assert!(!exists_in_root(&root, Path::new("/escape/status")));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_probe_tcp() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(probe_tcp(&addr, Duration::from_secs(1)));
+
+        drop(listener);
+        assert!(!probe_tcp(&addr, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_health_status_display() {
+        assert_eq!(HealthStatus::Starting.to_string(), "starting");
+        assert_eq!(HealthStatus::Healthy.to_string(), "healthy");
+        assert_eq!(HealthStatus::Unhealthy.to_string(), "unhealthy");
+    }
+}
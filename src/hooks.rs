@@ -0,0 +1,482 @@
+//! OCI Lifecycle Hooks
+//!
+//! Programs run by the runtime around container start and stop, as defined
+//! by the OCI runtime specification. Each hook receives the container state
+//! as JSON on stdin.
+//!
+//! ## Phases
+//!
+//! | Phase | When | Namespaces | Failure |
+//! |-------|------|------------|---------|
+//! | `prestart` | init created, before `pivot_root` | runtime | start fails |
+//! | `createRuntime` | after `prestart` | runtime | start fails |
+//! | `createContainer` | after `createRuntime` | container (host root) | start fails |
+//! | `startContainer` | after `pivot_root`, before `execve` | container | start fails |
+//! | `poststart` | after `start()` succeeded | runtime | recorded |
+//! | `poststop` | after the container stopped | runtime | recorded |
+//!
+//! Hooks run in the container namespaces join them with `setns(2)` and, for
+//! `startContainer`, `chroot(2)` to the container root. Joining the PID
+//! namespace only applies to processes the hook itself spawns.
+//!
+//! Failures of `poststart` and `poststop` hooks do not fail the lifecycle
+//! operation; they are available from `Container::hook_errors()`.
+
+use core::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::container::ContainerError;
+use crate::json::Value;
+
+/// OCI version reported in the hook state
+const OCI_VERSION: &str = "1.0.2";
+
+/// How often a running hook is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Lifecycle phase a hook runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPhase {
+    /// Deprecated alias of `CreateRuntime`, run before it
+    Prestart,
+    /// After the init process and its namespaces were created
+    CreateRuntime,
+    /// Like `CreateRuntime`, but inside the container namespaces
+    CreateContainer,
+    /// Inside the container, just before the workload is executed
+    StartContainer,
+    /// After the workload was started
+    Poststart,
+    /// After the container was stopped
+    Poststop,
+}
+
+impl fmt::Display for HookPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Prestart => write!(f, "prestart"),
+            Self::CreateRuntime => write!(f, "createRuntime"),
+            Self::CreateContainer => write!(f, "createContainer"),
+            Self::StartContainer => write!(f, "startContainer"),
+            Self::Poststart => write!(f, "poststart"),
+            Self::Poststop => write!(f, "poststop"),
+        }
+    }
+}
+
+/// A program run at a lifecycle phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    /// Absolute path of the program
+    pub path: PathBuf,
+    /// Arguments including `argv[0]` (defaults to `path`)
+    pub args: Vec<String>,
+    /// Environment, replacing the runtime's
+    pub env: Vec<(String, String)>,
+    /// Time after which the hook is killed and fails
+    pub timeout: Option<Duration>,
+}
+
+impl Hook {
+    /// Hook running `path` without arguments or environment
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Set the arguments, including `argv[0]`
+    #[must_use]
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add an environment variable
+    #[must_use]
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set the timeout
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Run the hook with `state` on stdin
+    ///
+    /// With `namespaces`, the hook joins the namespaces of that container
+    /// init before it is executed.
+    pub(crate) fn run(
+        &self,
+        phase: HookPhase,
+        state: &str,
+        namespaces: Option<HookNamespaces>,
+    ) -> Result<(), ContainerError> {
+        let fail = |reason: String| ContainerError::Hook {
+            phase,
+            path: self.path.display().to_string(),
+            reason,
+        };
+
+        if !self.path.is_absolute() {
+            return Err(fail("path must be absolute".into()));
+        }
+
+        let mut command = Command::new(&self.path);
+        if let Some((arg0, rest)) = self.args.split_first() {
+            use std::os::unix::process::CommandExt;
+            command.arg0(arg0).args(rest);
+        }
+        command
+            .env_clear()
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::piped());
+
+        // Keep the namespace and root descriptors open until the hook has been spawned
+        let _attach = match namespaces {
+            Some(ns) => Some(ns.attach(&mut command).map_err(|e| fail(e.to_string()))?),
+            None => None,
+        };
+
+        let mut child = command.spawn().map_err(|e| fail(e.to_string()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook may exit without reading its input
+            let _ = stdin.write_all(state.as_bytes());
+        }
+
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|e| fail(e.to_string()))? {
+                break status;
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(fail(format!(
+                    "timed out after {:?}",
+                    self.timeout.unwrap_or_default()
+                )));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(fail(status.to_string()))
+        }
+    }
+}
+
+/// Hooks of every lifecycle phase
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    /// Deprecated, run before `create_runtime`
+    pub prestart: Vec<Hook>,
+    /// After the init process and its namespaces were created
+    pub create_runtime: Vec<Hook>,
+    /// Inside the container namespaces, before `pivot_root`
+    pub create_container: Vec<Hook>,
+    /// Inside the container, before the workload is executed
+    pub start_container: Vec<Hook>,
+    /// After the workload was started
+    pub poststart: Vec<Hook>,
+    /// After the container was stopped
+    pub poststop: Vec<Hook>,
+}
+
+impl Hooks {
+    /// Hooks of `phase`
+    #[must_use]
+    pub fn get(&self, phase: HookPhase) -> &[Hook] {
+        match phase {
+            HookPhase::Prestart => &self.prestart,
+            HookPhase::CreateRuntime => &self.create_runtime,
+            HookPhase::CreateContainer => &self.create_container,
+            HookPhase::StartContainer => &self.start_container,
+            HookPhase::Poststart => &self.poststart,
+            HookPhase::Poststop => &self.poststop,
+        }
+    }
+
+    /// Mutable hooks of `phase`
+    pub fn get_mut(&mut self, phase: HookPhase) -> &mut Vec<Hook> {
+        match phase {
+            HookPhase::Prestart => &mut self.prestart,
+            HookPhase::CreateRuntime => &mut self.create_runtime,
+            HookPhase::CreateContainer => &mut self.create_container,
+            HookPhase::StartContainer => &mut self.start_container,
+            HookPhase::Poststart => &mut self.poststart,
+            HookPhase::Poststop => &mut self.poststop,
+        }
+    }
+
+    /// Whether no hooks are configured
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        HookPhase::ALL
+            .iter()
+            .all(|&phase| self.get(phase).is_empty())
+    }
+}
+
+impl HookPhase {
+    /// All phases in lifecycle order
+    pub const ALL: [Self; 6] = [
+        Self::Prestart,
+        Self::CreateRuntime,
+        Self::CreateContainer,
+        Self::StartContainer,
+        Self::Poststart,
+        Self::Poststop,
+    ];
+}
+
+// ============================================================================
+// Execution
+// ============================================================================
+
+/// Container namespaces a hook joins
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HookNamespaces {
+    /// Container init PID
+    pub(crate) pid: u32,
+    /// Also `chroot(2)` to the container root
+    pub(crate) chroot: bool,
+}
+
+impl HookNamespaces {
+    /// Make `command` join the namespaces before exec
+    ///
+    /// Returns the descriptors used by the child, which must stay open
+    /// until the command has been spawned.
+    #[cfg(target_os = "linux")]
+    fn attach(
+        self,
+        command: &mut Command,
+    ) -> Result<std::sync::Arc<(crate::namespace::NamespaceFds, std::fs::File)>, ContainerError>
+    {
+        use std::os::unix::io::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let namespaces = crate::namespace::NamespaceFds::open(self.pid)?;
+        let root = std::fs::File::open(format!("/proc/{}/root", self.pid))
+            .map_err(|e| ContainerError::IoError(format!("open container root: {e}")))?;
+        let fds = std::sync::Arc::new((namespaces, root));
+
+        let chroot = self.chroot;
+        let child_fds = std::sync::Arc::clone(&fds);
+        // SAFETY: the closure runs in the forked child before exec and only makes
+        // setns(2), fchdir(2), chroot(2) and chdir(2) calls on descriptors kept open by `fds`.
+        unsafe {
+            command.pre_exec(move || {
+                let (namespaces, root) = &*child_fds;
+                namespaces
+                    .enter()
+                    .map_err(|_| std::io::Error::last_os_error())?;
+                if chroot
+                    && (libc::fchdir(root.as_raw_fd()) < 0
+                        || libc::chroot(c".".as_ptr()) < 0
+                        || libc::chdir(c"/".as_ptr()) < 0)
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(fds)
+    }
+
+    /// Join namespaces (non-Linux stub)
+    #[cfg(not(target_os = "linux"))]
+    #[allow(clippy::unused_self)]
+    fn attach(self, _command: &mut Command) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+}
+
+/// Run `hooks` in order, stopping at the first failure
+pub(crate) fn run_hooks(
+    phase: HookPhase,
+    hooks: &[Hook],
+    state: &str,
+    namespaces: Option<HookNamespaces>,
+) -> Result<(), ContainerError> {
+    hooks
+        .iter()
+        .try_for_each(|hook| hook.run(phase, state, namespaces))
+}
+
+/// OCI state document passed to hooks on stdin
+#[must_use]
+pub(crate) fn state_json(
+    id: &str,
+    status: &str,
+    pid: Option<u32>,
+    bundle: &Path,
+    annotations: &[(String, String)],
+) -> String {
+    let annotations = annotations
+        .iter()
+        .fold(Value::object(), |obj, (k, v)| obj.with(k, v.as_str()));
+
+    let mut state = Value::object()
+        .with("ociVersion", OCI_VERSION)
+        .with("id", id)
+        .with("status", status);
+    if let Some(pid) = pid {
+        state = state.with("pid", pid);
+    }
+    state
+        .with("bundle", bundle.to_string_lossy().into_owned())
+        .with("annotations", annotations)
+        .to_string()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "alice-container-hook-{name}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos()
+        ))
+    }
+
+    #[test]
+    fn test_state_json() {
+        let json = state_json(
+            "web",
+            "creating",
+            Some(42),
+            Path::new("/var/lib/alice/web"),
+            &[("app".into(), "web".into())],
+        );
+        let state = Value::parse(&json).unwrap();
+        assert_eq!(
+            state.get("ociVersion").and_then(Value::as_str),
+            Some("1.0.2")
+        );
+        assert_eq!(
+            state.get("status").and_then(Value::as_str),
+            Some("creating")
+        );
+        assert_eq!(state.get("pid").and_then(Value::as_u64), Some(42));
+        assert_eq!(
+            state
+                .get("annotations")
+                .and_then(|a| a.get("app"))
+                .and_then(Value::as_str),
+            Some("web")
+        );
+
+        let stopped = state_json("web", "stopped", None, Path::new("/"), &[]);
+        assert!(Value::parse(&stopped).unwrap().get("pid").is_none());
+    }
+
+    #[test]
+    fn test_hook_receives_state_args_and_env() {
+        let out = temp_file("out");
+        let hook = Hook::new("/bin/sh")
+            .args([
+                "sh",
+                "-c",
+                "read -r state; echo \"$0 $1 $GREETING $state\" > \"$OUT\"",
+                "zero",
+                "first",
+            ])
+            .env("GREETING", "hello")
+            .env("OUT", out.to_string_lossy());
+
+        hook.run(HookPhase::CreateRuntime, "{\"id\":\"web\"}\n", None)
+            .unwrap();
+        let written = std::fs::read_to_string(&out).unwrap();
+        let _ = std::fs::remove_file(&out);
+        assert_eq!(written.trim(), "zero first hello {\"id\":\"web\"}");
+    }
+
+    #[test]
+    fn test_hook_failure_maps_to_error() {
+        let hook = Hook::new("/bin/sh").args(["sh", "-c", "exit 3"]);
+        let err = hook.run(HookPhase::Prestart, "{}", None).unwrap_err();
+        assert!(matches!(
+            &err,
+            ContainerError::Hook {
+                phase: HookPhase::Prestart,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("exit status: 3"));
+
+        let missing = Hook::new("/nonexistent/alice-hook");
+        assert!(missing.run(HookPhase::Poststop, "{}", None).is_err());
+
+        let relative = Hook::new("bin/true");
+        assert!(relative.run(HookPhase::Poststop, "{}", None).is_err());
+    }
+
+    #[test]
+    fn test_hook_timeout() {
+        let hook = Hook::new("/bin/sh")
+            .args(["sh", "-c", "sleep 10"])
+            .timeout(Duration::from_millis(50));
+        let start = Instant::now();
+        let err = hook.run(HookPhase::Poststart, "{}", None).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn test_run_hooks_stops_at_first_failure() {
+        let marker = temp_file("marker");
+        let hooks = [
+            Hook::new("/bin/false"),
+            Hook::new("/bin/sh").args(["sh", "-c", &format!("touch {}", marker.display())]),
+        ];
+        assert!(run_hooks(HookPhase::CreateRuntime, &hooks, "{}", None).is_err());
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_hooks_by_phase() {
+        let mut hooks = Hooks::default();
+        assert!(hooks.is_empty());
+        hooks
+            .get_mut(HookPhase::Poststop)
+            .push(Hook::new("/usr/bin/deregister"));
+        assert!(!hooks.is_empty());
+        assert_eq!(hooks.get(HookPhase::Poststop).len(), 1);
+        assert!(hooks.get(HookPhase::Poststart).is_empty());
+        assert_eq!(HookPhase::CreateRuntime.to_string(), "createRuntime");
+    }
+}
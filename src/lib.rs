@@ -94,16 +94,69 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 // Core modules
+pub mod capability;
 pub mod cgroup;
 pub mod container;
 pub mod namespace;
 pub mod network;
 pub mod oci;
+pub mod rlimit;
 pub mod rootfs;
 pub mod scheduler;
 pub mod seccomp;
 pub mod signed_state_change;
 
+#[cfg(feature = "std")]
+pub mod pidfd;
+
+#[cfg(feature = "std")]
+pub mod console;
+
+#[cfg(feature = "std")]
+pub mod events;
+
+#[cfg(feature = "std")]
+pub mod exec;
+
+#[cfg(feature = "std")]
+pub mod health;
+
+#[cfg(feature = "std")]
+pub mod hooks;
+
+#[cfg(feature = "std")]
+pub mod logs;
+
+#[cfg(feature = "std")]
+pub mod manager;
+
+#[cfg(feature = "std")]
+pub mod state;
+
+#[cfg(feature = "std")]
+pub mod stats;
+
+#[cfg(feature = "std")]
+pub mod supervisor;
+
+#[cfg(feature = "std")]
+pub mod top;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod archive;
+
+#[cfg(feature = "std")]
+mod json;
+
+#[cfg(feature = "std")]
+mod passwd;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod handshake;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod mini_init;
+
 pub use signed_state_change::{
     ContainerEventKind, SignedStateChange, StateChangeRecord, StateChangeTrail,
 };
@@ -136,14 +189,43 @@ pub mod sync_bridge;
 
 /// Prelude for convenient imports
 pub mod prelude {
-    pub use crate::cgroup::{CgroupController, CgroupError, CpuConfig, IoConfig, MemoryConfig};
-    pub use crate::container::{Container, ContainerConfig, ContainerError, ContainerState};
+    pub use crate::capability::{Capabilities, Capability, CapabilitySet};
+    pub use crate::cgroup::{
+        CgroupController, CgroupError, CpuConfig, CpusetConfig, IoConfig, MemoryConfig, PidsConfig,
+        Resources,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::console::{Console, ConsoleSocket, WindowSize};
+    pub use crate::container::{
+        Container, ContainerConfig, ContainerError, ContainerState, ExitStatus, RestartPolicy,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::events::{ContainerEvent, EventMonitor};
+    #[cfg(feature = "std")]
+    pub use crate::exec::{ExecOptions, ExecProcess};
+    #[cfg(feature = "std")]
+    pub use crate::health::{HealthCheck, HealthProbe, HealthStatus};
+    #[cfg(feature = "std")]
+    pub use crate::hooks::{Hook, HookPhase, Hooks};
+    #[cfg(feature = "std")]
+    pub use crate::logs::{LogConfig, LogRecord, LogStream, Logs};
+    #[cfg(feature = "std")]
+    pub use crate::manager::{ContainerFilter, ContainerManager};
     pub use crate::namespace::{pivot_root, NamespaceFlags, Namespaces};
     pub use crate::network::{Bridge, NetworkConfig, NetworkError, VethPair};
     pub use crate::oci::{OciLinux, OciProcess, OciRoot, OciSpec};
+    pub use crate::rlimit::{Rlimit, RlimitResource, RLIM_INFINITY};
     pub use crate::rootfs::{mount_dev, mount_proc, RootFs};
     pub use crate::scheduler::{DynamicScheduler, SchedulerConfig};
     pub use crate::seccomp::{AppArmorProfile, SeccompAction, SeccompProfile, SeccompRule};
+    #[cfg(feature = "std")]
+    pub use crate::stats::{
+        ContainerStats, IoDeviceStats, MemoryEvents, MemoryStats, NetworkStats, PidsStats,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::supervisor::{Backoff, RestartStats, Supervisor};
+    #[cfg(feature = "std")]
+    pub use crate::top::ProcessInfo;
 
     // io_uring exports
     #[cfg(feature = "io_uring")]
@@ -182,6 +264,9 @@ pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
 /// ALICE cgroup subtree
 pub const ALICE_CGROUP: &str = "/sys/fs/cgroup/alice";
 
+/// Default container state directory
+pub const STATE_ROOT: &str = "/run/alice-container";
+
 /// Default CPU period (100ms)
 pub const DEFAULT_CPU_PERIOD_US: u64 = 100_000;
 
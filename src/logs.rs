@@ -0,0 +1,823 @@
+//! Container Logs
+//!
+//! Captures the stdout and stderr of a container's init process and stores
+//! them as JSON lines, in the format of docker's `json-file` driver:
+//!
+//! ```text
+//! {"log":"listening on :7777\n","stream":"stdout","time":"2026-10-16T09:41:07.123456789Z"}
+//! ```
+//!
+//! ## Layout
+//!
+//! ```text
+//! <state_root>/<id>/
+//! ├── container.log        current file
+//! ├── container.log.1      most recent rotated file
+//! └── container.log.<n>    oldest kept file (n = max_files - 1)
+//! ```
+//!
+//! A file is rotated before a record would grow it beyond `max_size`.
+//!
+//! ## Capture
+//!
+//! The init process's stdout and stderr are pipes read by a thread of the
+//! runtime. Each line becomes one record; lines longer than
+//! `MAX_RECORD` bytes are split between characters. The thread ends once
+//! every process holding the pipes has exited, so output is only collected
+//! while the runtime that started the container is alive.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::container::ContainerError;
+use crate::json::Value;
+
+/// File name of the current log inside a container's state directory
+pub const LOG_FILE: &str = "container.log";
+
+/// Longest line stored in a single record
+const MAX_RECORD: usize = 16 * 1024;
+
+/// How often `follow` checks the log for new records
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Size-based rotation of a container log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogConfig {
+    /// Size at which the current file is rotated, in bytes
+    pub max_size: u64,
+    /// Files kept, including the current one (at least 1)
+    pub max_files: u32,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Rotate at `max_size` bytes and keep `max_files` files
+    #[must_use]
+    pub const fn new(max_size: u64, max_files: u32) -> Self {
+        Self {
+            max_size,
+            max_files,
+        }
+    }
+}
+
+// ============================================================================
+// Records
+// ============================================================================
+
+/// Output stream of a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    /// Standard output
+    Stdout,
+    /// Standard error
+    Stderr,
+}
+
+impl LogStream {
+    /// Name used in the log file
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// One line of container output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// When the line was read by the runtime
+    pub time: SystemTime,
+    /// Stream the line was written to
+    pub stream: LogStream,
+    /// Line content, including its trailing newline if it had one
+    pub line: String,
+}
+
+impl LogRecord {
+    /// Serialize as one JSON line, without the newline
+    fn to_json(&self) -> String {
+        Value::object()
+            .with("log", self.line.as_str())
+            .with("stream", self.stream.as_str())
+            .with("time", format_timestamp(self.time))
+            .to_string()
+    }
+
+    /// Parse a line written by `to_json()`
+    fn from_json(text: &str) -> Option<Self> {
+        let doc = Value::parse(text).ok()?;
+        let stream = match doc.get("stream")?.as_str()? {
+            "stdout" => LogStream::Stdout,
+            "stderr" => LogStream::Stderr,
+            _ => return None,
+        };
+        Some(Self {
+            time: parse_timestamp(doc.get("time")?.as_str()?)?,
+            stream,
+            line: doc.get("log")?.as_str()?.to_string(),
+        })
+    }
+}
+
+// ============================================================================
+// Timestamps
+// ============================================================================
+
+/// Format as RFC 3339 in UTC with nanoseconds, e.g. `2026-10-16T09:41:07.123456789Z`
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let day_secs = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:09}Z",
+        day_secs / 3600,
+        day_secs / 60 % 60,
+        day_secs % 60,
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Parse a timestamp written by `format_timestamp()`
+fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let text = text.strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (hms, nanos) = time.split_once('.').unwrap_or((time, "0"));
+    let mut hms = hms.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (hms.next()?.ok()?, hms.next()?.ok()?, hms.next()?.ok()?);
+    // Fractions shorter than nanoseconds are scaled up
+    let nanos = format!("{nanos:0<9}").get(..9)?.parse::<u32>().ok()?;
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+const fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// Path of rotated file `n` (0 is the current file)
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+/// Appends records to a log, rotating by size
+#[derive(Debug)]
+pub(crate) struct LogWriter {
+    path: PathBuf,
+    config: LogConfig,
+    file: File,
+    size: u64,
+}
+
+impl LogWriter {
+    /// Open `path` for appending, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub(crate) fn open(path: PathBuf, config: LogConfig) -> Result<Self, ContainerError> {
+        let file = open_append(&path)?;
+        let size = file.metadata().map_err(|e| log_error(&path, &e))?.len();
+        Ok(Self {
+            path,
+            config,
+            file,
+            size,
+        })
+    }
+
+    /// Append one record, rotating first if it would not fit
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log cannot be written or rotated.
+    pub(crate) fn write(&mut self, record: &LogRecord) -> Result<(), ContainerError> {
+        let mut line = record.to_json();
+        line.push('\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.config.max_size {
+            self.rotate()?;
+        }
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(|e| log_error(&self.path, &e))?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `container.log.<n>` to `<n+1>`, dropping the oldest, and start a new file
+    fn rotate(&mut self) -> Result<(), ContainerError> {
+        let kept = self.config.max_files.max(1) - 1;
+        if kept == 0 {
+            self.file
+                .set_len(0)
+                .map_err(|e| log_error(&self.path, &e))?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        for n in (0..kept).rev() {
+            match std::fs::rename(rotated_path(&self.path, n), rotated_path(&self.path, n + 1)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(log_error(&self.path, &e));
+                }
+                _ => {}
+            }
+        }
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Open a log file for appending, readable only by the owner
+fn open_append(path: &Path) -> Result<File, ContainerError> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| log_error(path, &e))
+}
+
+fn log_error(path: &Path, e: &std::io::Error) -> ContainerError {
+    ContainerError::IoError(format!("{}: {e}", path.display()))
+}
+
+// ============================================================================
+// Capture
+// ============================================================================
+
+/// Splits the output of one stream into records
+#[derive(Debug)]
+struct LineBuffer {
+    stream: LogStream,
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    const fn new(stream: LogStream) -> Self {
+        Self {
+            stream,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add output, returning the completed lines
+    fn push(&mut self, data: &[u8], time: SystemTime) -> Vec<LogRecord> {
+        self.pending.extend_from_slice(data);
+        let mut records = Vec::new();
+        loop {
+            let end = match self.pending.iter().position(|&b| b == b'\n') {
+                Some(newline) if newline < MAX_RECORD => newline + 1,
+                _ if self.pending.len() >= MAX_RECORD => split_point(&self.pending),
+                _ => break,
+            };
+            let line: Vec<u8> = self.pending.drain(..end).collect();
+            records.push(self.record(&line, time));
+        }
+        records
+    }
+
+    /// Output left without a newline at end of stream
+    fn finish(&mut self, time: SystemTime) -> Option<LogRecord> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let line = std::mem::take(&mut self.pending);
+        Some(self.record(&line, time))
+    }
+
+    fn record(&self, line: &[u8], time: SystemTime) -> LogRecord {
+        LogRecord {
+            time,
+            stream: self.stream,
+            line: String::from_utf8_lossy(line).into_owned(),
+        }
+    }
+}
+
+/// Where to split an overlong line: at `MAX_RECORD`, or before the
+/// UTF-8 sequence that spans it
+fn split_point(pending: &[u8]) -> usize {
+    // A sequence is at most 4 bytes, so at most 3 continuation bytes precede the cut
+    (MAX_RECORD - 3..=MAX_RECORD)
+        .rev()
+        .find(|&i| pending[i] & 0xC0 != 0x80)
+        .unwrap_or(MAX_RECORD)
+}
+
+/// Log shared by the capture threads of successive runs of a container
+pub(crate) type SharedLogWriter = Arc<Mutex<LogWriter>>;
+
+/// Thread copying one run's output into the log
+#[derive(Debug)]
+pub(crate) struct LogCapture {
+    thread: Option<std::thread::JoinHandle<()>>,
+    /// Set once both pipes reached end-of-file
+    done: Arc<AtomicBool>,
+}
+
+impl LogCapture {
+    /// Read `stdout` and `stderr` on a new thread until both are closed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread cannot be spawned.
+    pub(crate) fn spawn(
+        writer: SharedLogWriter,
+        stdout: OwnedFd,
+        stderr: OwnedFd,
+    ) -> Result<Self, ContainerError> {
+        let done = Arc::new(AtomicBool::new(false));
+        let finished = Arc::clone(&done);
+        let thread = std::thread::Builder::new()
+            .name("alice-container-log".into())
+            .spawn(move || {
+                capture(&writer, [stdout, stderr]);
+                finished.store(true, Ordering::Release);
+            })
+            .map_err(|e| ContainerError::ProcessError(format!("spawn log thread: {e}")))?;
+        Ok(Self {
+            thread: Some(thread),
+            done,
+        })
+    }
+
+    /// Flag set once all output of the run has been written
+    pub(crate) fn done(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.done)
+    }
+
+    /// Wait until all output of the run has been written
+    pub(crate) fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Copy both pipes into the log until they are closed
+///
+/// Write failures drop records rather than stalling the workload on a
+/// full pipe.
+fn capture(writer: &SharedLogWriter, pipes: [OwnedFd; 2]) {
+    let mut buffers = [
+        LineBuffer::new(LogStream::Stdout),
+        LineBuffer::new(LogStream::Stderr),
+    ];
+    let mut open = [true, true];
+    let mut chunk = vec![0u8; 64 * 1024];
+
+    while open.iter().any(|&o| o) {
+        let mut fds: Vec<libc::pollfd> = pipes
+            .iter()
+            .map(|pipe| libc::pollfd {
+                fd: pipe.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        for (pfd, &o) in fds.iter_mut().zip(&open) {
+            // poll(2) ignores negative descriptors
+            if !o {
+                pfd.fd = -1;
+            }
+        }
+        // SAFETY: fds is a valid array of pollfd.
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            break;
+        }
+
+        for i in 0..2 {
+            if !open[i] || fds[i].revents == 0 {
+                continue;
+            }
+            // SAFETY: the pipe is open and chunk is valid for its length.
+            let n = unsafe { libc::read(fds[i].fd, chunk.as_mut_ptr().cast(), chunk.len()) };
+            let now = SystemTime::now();
+            let records = match n {
+                n if n > 0 => buffers[i].push(&chunk[..n as usize], now),
+                n if n < 0
+                    && std::io::Error::last_os_error().kind()
+                        == std::io::ErrorKind::Interrupted =>
+                {
+                    continue;
+                }
+                _ => {
+                    open[i] = false;
+                    buffers[i].finish(now).into_iter().collect()
+                }
+            };
+            if !records.is_empty() {
+                let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+                for record in &records {
+                    let _ = writer.write(record);
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// Records of a container log, see `Container::logs()`
+///
+/// Iterates over the selected existing records, then, when following, waits
+/// for new ones until the container's output has ended.
+#[derive(Debug)]
+pub struct Logs {
+    records: VecDeque<LogRecord>,
+    follow: Option<Follow>,
+    since: Option<SystemTime>,
+}
+
+/// Position in the current log file while following
+#[derive(Debug)]
+struct Follow {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// Inode of the open file, to notice rotation
+    inode: u64,
+    /// Text of a record being written
+    partial: String,
+    /// Set once the output has ended
+    done: Arc<AtomicBool>,
+}
+
+impl Logs {
+    /// Read the log at `path`
+    ///
+    /// Keeps records newer than `since` and of those the last `tail`. With
+    /// `done`, the iterator then follows the log until the flag is set.
+    pub(crate) fn open(
+        path: &Path,
+        max_files: u32,
+        since: Option<SystemTime>,
+        tail: Option<usize>,
+        done: Option<Arc<AtomicBool>>,
+    ) -> Result<Self, ContainerError> {
+        let mut records = VecDeque::new();
+        let mut keep = |record: LogRecord| {
+            if since.is_none_or(|since| record.time > since) {
+                records.push_back(record);
+                if tail.is_some_and(|tail| records.len() > tail) {
+                    records.pop_front();
+                }
+            }
+        };
+
+        // Oldest first
+        for n in (1..max_files.max(1)).rev() {
+            let rotated = rotated_path(path, n);
+            match File::open(&rotated) {
+                Ok(file) => {
+                    for line in BufReader::new(file).lines() {
+                        let line = line.map_err(|e| log_error(&rotated, &e))?;
+                        if let Some(record) = LogRecord::from_json(&line) {
+                            keep(record);
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(log_error(&rotated, &e)),
+            }
+        }
+
+        // The current file is read through the reader that follows it
+        let mut follow = Follow {
+            path: path.to_path_buf(),
+            reader: None,
+            inode: 0,
+            partial: String::new(),
+            done: done.unwrap_or_else(|| Arc::new(AtomicBool::new(true))),
+        };
+        follow.reopen();
+        while let Some(record) = follow.read_record()? {
+            keep(record);
+        }
+
+        let following = !follow.done.load(Ordering::Acquire);
+        Ok(Self {
+            records,
+            follow: following.then_some(follow),
+            since,
+        })
+    }
+}
+
+impl Follow {
+    /// Open the current file from its start
+    fn reopen(&mut self) {
+        use std::os::unix::fs::MetadataExt;
+
+        self.partial.clear();
+        self.reader = File::open(&self.path).ok().map(BufReader::new);
+        self.inode = self
+            .reader
+            .as_ref()
+            .and_then(|r| r.get_ref().metadata().ok())
+            .map_or(0, |m| m.ino());
+    }
+
+    /// Read the next complete record, `None` at end of file
+    fn read_record(&mut self) -> Result<Option<LogRecord>, ContainerError> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(None);
+        };
+        loop {
+            let n = reader
+                .read_line(&mut self.partial)
+                .map_err(|e| log_error(&self.path, &e))?;
+            if n == 0 || !self.partial.ends_with('\n') {
+                // End of file, possibly in the middle of a record
+                return Ok(None);
+            }
+            let record = LogRecord::from_json(self.partial.trim_end());
+            self.partial.clear();
+            if record.is_some() {
+                return Ok(record);
+            }
+        }
+    }
+
+    /// Whether the current file was rotated away
+    fn rotated(&self) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        std::fs::metadata(&self.path).is_ok_and(|m| m.ino() != self.inode)
+    }
+}
+
+impl Iterator for Logs {
+    type Item = Result<LogRecord, ContainerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.records.pop_front() {
+            return Some(Ok(record));
+        }
+        let follow = self.follow.as_mut()?;
+        loop {
+            // Checked before reading, so records written before the end are not missed
+            let done = follow.done.load(Ordering::Acquire);
+            match follow.read_record() {
+                Ok(Some(record)) => {
+                    if self.since.is_none_or(|since| record.time > since) {
+                        return Some(Ok(record));
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            if follow.rotated() {
+                // The rest of the old file was read above
+                follow.reopen();
+                continue;
+            }
+            if done {
+                self.follow = None;
+                return None;
+            }
+            std::thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "alice-container-logs-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(secs: u64, line: &str) -> LogRecord {
+        LogRecord {
+            time: UNIX_EPOCH + Duration::new(secs, 5),
+            stream: LogStream::Stdout,
+            line: format!("{line}\n"),
+        }
+    }
+
+    #[test]
+    fn test_timestamp_roundtrip() {
+        let time = UNIX_EPOCH + Duration::new(1_792_143_667, 123_456_789);
+        assert_eq!(format_timestamp(time), "2026-10-16T09:41:07.123456789Z");
+        assert_eq!(
+            parse_timestamp("2026-10-16T09:41:07.123456789Z"),
+            Some(time)
+        );
+
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH),
+            "1970-01-01T00:00:00.000000000Z"
+        );
+        // Leap day, and fractions as written by other tools
+        assert_eq!(
+            parse_timestamp("2024-02-29T23:59:59.5Z"),
+            Some(UNIX_EPOCH + Duration::new(1_709_251_199, 500_000_000))
+        );
+        assert_eq!(parse_timestamp("2024-02-29 23:59:59Z"), None);
+    }
+
+    #[test]
+    fn test_record_json_roundtrip() {
+        let record = LogRecord {
+            time: UNIX_EPOCH + Duration::new(1_792_143_667, 1),
+            stream: LogStream::Stderr,
+            line: "error: \"quoted\"\ttab\n".into(),
+        };
+        let json = record.to_json();
+        assert!(json.starts_with(r#"{"log":"error: \"quoted\"\ttab\n","stream":"stderr","time":"#));
+        assert_eq!(LogRecord::from_json(&json), Some(record));
+        assert_eq!(LogRecord::from_json(r#"{"log":"x","stream":"tty"}"#), None);
+    }
+
+    #[test]
+    fn test_line_buffer_splits_lines() {
+        let mut buffer = LineBuffer::new(LogStream::Stdout);
+        let now = SystemTime::now();
+
+        let records = buffer.push(b"one\ntw", now);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].line, "one\n");
+        let records = buffer.push(b"o\nthree", now);
+        assert_eq!(records[0].line, "two\n");
+        assert_eq!(buffer.finish(now).unwrap().line, "three");
+        assert!(buffer.finish(now).is_none());
+
+        // Overlong lines are split at MAX_RECORD
+        let records = buffer.push(&vec![b'x'; MAX_RECORD + 10], now);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].line.len(), MAX_RECORD);
+        assert_eq!(buffer.finish(now).unwrap().line.len(), 10);
+
+        // ... or before a character that would be cut there
+        let mut line = vec![b'x'; MAX_RECORD - 1];
+        line.extend_from_slice("ü€".as_bytes());
+        let records = buffer.push(&line, now);
+        assert_eq!(records[0].line.len(), MAX_RECORD - 1);
+        assert_eq!(buffer.finish(now).unwrap().line, "ü€");
+
+        let mut line = vec![b'x'; MAX_RECORD - 2];
+        line.extend_from_slice("€".as_bytes());
+        let records = buffer.push(&line, now);
+        assert_eq!(records[0].line.len(), MAX_RECORD - 2);
+        assert_eq!(buffer.finish(now).unwrap().line, "€");
+
+        // Invalid UTF-8 is still split at MAX_RECORD
+        let records = buffer.push(&vec![0x80; MAX_RECORD + 1], now);
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn test_rotation_and_tail() {
+        let dir = temp_dir("rotate");
+        let path = dir.join(LOG_FILE);
+        let line_len = record(0, "line 0").to_json().len() as u64 + 1;
+        // Two records per file, three files
+        let mut writer = LogWriter::open(path.clone(), LogConfig::new(line_len * 2, 3)).unwrap();
+        for i in 0..7 {
+            writer.write(&record(i, &format!("line {i}"))).unwrap();
+        }
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+
+        let lines = |since, tail| -> Vec<String> {
+            Logs::open(&path, 3, since, tail, None)
+                .unwrap()
+                .map(|r| r.unwrap().line)
+                .collect()
+        };
+        // The oldest file holding lines 0 and 1 was dropped
+        assert_eq!(
+            lines(None, None),
+            ["line 2\n", "line 3\n", "line 4\n", "line 5\n", "line 6\n"]
+        );
+        assert_eq!(lines(None, Some(2)), ["line 5\n", "line 6\n"]);
+        assert_eq!(
+            lines(Some(UNIX_EPOCH + Duration::from_secs(4)), None),
+            ["line 4\n", "line 5\n", "line 6\n"]
+        );
+        assert_eq!(
+            lines(Some(UNIX_EPOCH + Duration::from_secs(3)), Some(1)),
+            ["line 6\n"]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_capture_and_follow() {
+        use crate::container::pipe;
+
+        let dir = temp_dir("follow");
+        let path = dir.join(LOG_FILE);
+        let writer = Arc::new(Mutex::new(
+            LogWriter::open(path.clone(), LogConfig::default()).unwrap(),
+        ));
+
+        let (out_read, out_write) = pipe().unwrap();
+        let (err_read, err_write) = pipe().unwrap();
+        let (mut out_write, mut err_write) = (File::from(out_write), File::from(err_write));
+
+        let capture = LogCapture::spawn(writer, out_read, err_read).unwrap();
+        let mut logs = Logs::open(&path, 1, None, None, Some(capture.done())).unwrap();
+
+        out_write.write_all(b"hello\n").unwrap();
+        let first = logs.next().unwrap().unwrap();
+        assert_eq!(
+            (first.stream, first.line.as_str()),
+            (LogStream::Stdout, "hello\n")
+        );
+
+        err_write.write_all(b"no newline").unwrap();
+        drop((out_write, err_write));
+        let second = logs.next().unwrap().unwrap();
+        assert_eq!(
+            (second.stream, second.line.as_str()),
+            (LogStream::Stderr, "no newline")
+        );
+        // Following ends with the output
+        assert!(logs.next().is_none());
+        capture.join();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
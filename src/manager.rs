@@ -0,0 +1,490 @@
+//! Container Manager
+//!
+//! Registry that owns the containers of a host by ID and can be shared
+//! between threads.
+//!
+//! ## Locking
+//!
+//! ```text
+//! ContainerManager
+//! └── RwLock<HashMap<id, Slot>>     held only to look up or insert slots
+//!     └── Slot = Arc<Mutex<Option<Container>>>
+//!                                   held for the duration of one operation
+//! ```
+//!
+//! Operations on different containers run in parallel; operations on the
+//! same container are serialized. A slot is `None` while its container is
+//! being created or after it has been destroyed.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use alice_container::manager::{ContainerFilter, ContainerManager};
+//!
+//! let manager = ContainerManager::new();
+//! manager.create("web-1", ContainerConfig::builder().label("app", "web").build())?;
+//! manager.start("web-1")?;
+//!
+//! let web = ContainerFilter::new().label("app", "web");
+//! for (id, e) in manager.stop_all(&web) {
+//!     eprintln!("{id}: {e}");
+//! }
+//! ```
+//!
+//! ## Events
+//!
+//! `subscribe()` returns an [`EventMonitor`] that watches every managed
+//! container, including those created or inserted later.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use crate::container::{validate_id, Container, ContainerConfig, ContainerError, ContainerState};
+use crate::events::{ContainerEvent, EventMonitor, EventSender, Notice};
+
+/// Shared, individually locked container entry
+type Slot = Arc<Mutex<Option<Container>>>;
+
+/// Lock a slot, recovering from a panic in another holder
+fn lock(slot: &Slot) -> MutexGuard<'_, Option<Container>> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// ============================================================================
+// Filter
+// ============================================================================
+
+/// Selects containers by state and labels
+///
+/// An empty filter matches every container. All conditions must match.
+#[derive(Debug, Clone, Default)]
+pub struct ContainerFilter {
+    /// Accepted states (any if empty)
+    states: Vec<ContainerState>,
+    /// Required labels; `None` only requires the key to be present
+    labels: Vec<(String, Option<String>)>,
+}
+
+impl ContainerFilter {
+    /// Create a filter matching every container
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also accept containers in `state`
+    ///
+    /// `Exited` matches regardless of its exit code.
+    #[must_use]
+    pub fn state(mut self, state: ContainerState) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Require label `key` to equal `value`
+    #[must_use]
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((key.into(), Some(value.into())));
+        self
+    }
+
+    /// Require label `key` to be set
+    #[must_use]
+    pub fn has_label(mut self, key: impl Into<String>) -> Self {
+        self.labels.push((key.into(), None));
+        self
+    }
+
+    /// Whether `container` matches
+    #[must_use]
+    pub fn matches(&self, container: &Container) -> bool {
+        self.matches_parts(container.state(), container.config())
+    }
+
+    fn matches_parts(&self, state: ContainerState, config: &ContainerConfig) -> bool {
+        let state_ok = self.states.is_empty()
+            || self
+                .states
+                .iter()
+                .any(|s| core::mem::discriminant(s) == core::mem::discriminant(&state));
+
+        state_ok
+            && self
+                .labels
+                .iter()
+                .all(|(key, value)| match (config.label(key), value) {
+                    (Some(actual), Some(expected)) => actual == expected,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                })
+    }
+}
+
+// ============================================================================
+// Manager
+// ============================================================================
+
+/// Thread-safe registry of containers keyed by ID
+#[derive(Debug, Default)]
+pub struct ContainerManager {
+    containers: RwLock<HashMap<String, Slot>>,
+    /// Monitors returned by `subscribe()`
+    subscribers: Mutex<Vec<EventSender>>,
+}
+
+impl ContainerManager {
+    /// Create an empty manager
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a container and take ownership of it
+    ///
+    /// The ID is reserved before the container is created, so concurrent
+    /// calls with the same ID cannot both succeed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError` for an invalid ID, `AlreadyExists` if the ID is
+    /// taken, or the error from `Container::create()`.
+    pub fn create(&self, id: &str, config: ContainerConfig) -> Result<(), ContainerError> {
+        validate_id(id)?;
+
+        let slot = Slot::default();
+        // Hold the slot until it is filled so other callers wait for the outcome
+        let mut entry = lock(&slot);
+        {
+            let mut containers = self.write();
+            if containers.contains_key(id) {
+                return Err(ContainerError::AlreadyExists(id.to_string()));
+            }
+            containers.insert(id.to_string(), Arc::clone(&slot));
+        }
+
+        match Container::create(id, config) {
+            Ok(mut container) => {
+                self.announce(&mut container, true);
+                *entry = Some(container);
+                Ok(())
+            }
+            Err(e) => {
+                drop(entry);
+                self.write().remove(id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Take ownership of an existing container, e.g. one from `Container::load()`
+    ///
+    /// # Errors
+    ///
+    /// Returns `AlreadyExists` if a container with the same ID is managed.
+    pub fn insert(&self, mut container: Container) -> Result<(), ContainerError> {
+        let mut containers = self.write();
+        if containers.contains_key(container.id()) {
+            return Err(ContainerError::AlreadyExists(container.id().to_string()));
+        }
+        self.announce(&mut container, false);
+        containers.insert(
+            container.id().to_string(),
+            Arc::new(Mutex::new(Some(container))),
+        );
+        Ok(())
+    }
+
+    /// Stop managing a container without destroying it
+    ///
+    /// Waits for any operation in progress on the container to finish.
+    #[must_use]
+    pub fn remove(&self, id: &str) -> Option<Container> {
+        let slot = self.write().remove(id)?;
+        let container = lock(&slot).take();
+        container
+    }
+
+    /// Run `f` with exclusive access to a container
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if no container with `id` is managed.
+    pub fn with<R>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Container) -> R,
+    ) -> Result<R, ContainerError> {
+        let slot = self
+            .read()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        let mut entry = lock(&slot);
+        let container = entry
+            .as_mut()
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+        Ok(f(container))
+    }
+
+    /// Start a container
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` or the error from `Container::start()`.
+    pub fn start(&self, id: &str) -> Result<(), ContainerError> {
+        self.with(id, Container::start)?
+    }
+
+    /// Stop a container
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` or the error from `Container::stop()`.
+    pub fn stop(&self, id: &str) -> Result<(), ContainerError> {
+        self.with(id, Container::stop)?
+    }
+
+    /// Destroy a container and stop managing it
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` or the error from `Container::destroy()`. The
+    /// container is no longer managed either way; its persisted state can
+    /// be reloaded with `Container::load()` after a failure.
+    pub fn destroy(&self, id: &str) -> Result<(), ContainerError> {
+        let slot = self
+            .write()
+            .remove(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        let container = lock(&slot).take();
+        container
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?
+            .destroy()
+    }
+
+    /// Whether a container with `id` is managed
+    #[must_use]
+    pub fn contains(&self, id: &str) -> bool {
+        self.read().contains_key(id)
+    }
+
+    /// Number of managed containers
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether no containers are managed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// IDs of the containers matching `filter`, sorted
+    #[must_use]
+    pub fn list(&self, filter: &ContainerFilter) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .slots()
+            .into_iter()
+            .filter(|(_, slot)| lock(slot).as_ref().is_some_and(|c| filter.matches(c)))
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Stop every running or paused container matching `filter`
+    ///
+    /// Continues past failures and returns them with the container ID.
+    #[must_use]
+    pub fn stop_all(&self, filter: &ContainerFilter) -> Vec<(String, ContainerError)> {
+        let mut failed = Vec::new();
+        for (id, slot) in self.slots() {
+            let mut entry = lock(&slot);
+            let Some(container) = entry.as_mut().filter(|c| filter.matches(c)) else {
+                continue;
+            };
+            if !matches!(
+                container.state(),
+                ContainerState::Running | ContainerState::Paused
+            ) {
+                continue;
+            }
+            if let Err(e) = container.stop() {
+                failed.push((id, e));
+            }
+        }
+        failed
+    }
+
+    /// Destroy every container matching `filter` and stop managing it
+    ///
+    /// Continues past failures and returns them with the container ID.
+    #[must_use]
+    pub fn destroy_all(&self, filter: &ContainerFilter) -> Vec<(String, ContainerError)> {
+        let mut failed = Vec::new();
+        for id in self.list(filter) {
+            match self.destroy(&id) {
+                // Removed concurrently
+                Ok(()) | Err(ContainerError::NotFound(_)) => {}
+                Err(e) => failed.push((id, e)),
+            }
+        }
+        failed
+    }
+
+    /// Watch every managed container, and those added later, for events
+    ///
+    /// `Created` is reported for containers created through `create()`
+    /// after the call; inserted containers are watched silently.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the monitor cannot be created or the cgroup
+    /// files of a container cannot be watched.
+    pub fn subscribe(&self) -> Result<EventMonitor, ContainerError> {
+        let mut monitor = EventMonitor::new()?;
+        // Registered first, so a container created meanwhile is not missed
+        self.subscribers().push(monitor.sender());
+
+        for id in self.list(&ContainerFilter::new()) {
+            match self.with(&id, |c| monitor.watch(c)) {
+                Ok(Ok(())) | Err(ContainerError::NotFound(_)) => {}
+                Ok(Err(e)) | Err(e) => return Err(e),
+            }
+        }
+        Ok(monitor)
+    }
+
+    /// Attach a new container to every subscribed monitor
+    fn announce(&self, container: &mut Container, created: bool) {
+        // Monitors that have been dropped are forgotten
+        self.subscribers().retain(|sender| {
+            if !sender.send(Notice::watch(container)) {
+                return false;
+            }
+            container.add_event_sender(sender.clone());
+            !created
+                || sender.send(Notice::Event {
+                    id: container.id().to_string(),
+                    event: ContainerEvent::Created,
+                    init: None,
+                })
+        });
+    }
+
+    fn subscribers(&self) -> MutexGuard<'_, Vec<EventSender>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Snapshot of all slots, so no container is locked under the map lock
+    fn slots(&self) -> Vec<(String, Slot)> {
+        self.read()
+            .iter()
+            .map(|(id, slot)| (id.clone(), Arc::clone(slot)))
+            .collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Slot>> {
+        self.containers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Slot>> {
+        self.containers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manager_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ContainerManager>();
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_ids() {
+        let manager = ContainerManager::new();
+        for id in ["../x", "", "a/b", ".."] {
+            assert!(matches!(
+                manager.create(id, ContainerConfig::default()),
+                Err(ContainerError::ConfigError(_))
+            ));
+        }
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_failed_create_releases_id() {
+        let manager = ContainerManager::new();
+        let config = ContainerConfig::builder()
+            .rootfs("/nonexistent/alice-container-rootfs")
+            .build();
+
+        assert!(manager.create("web", config.clone()).is_err());
+        assert!(!manager.contains("web"));
+        // The ID can be retried after a failure
+        assert!(!matches!(
+            manager.create("web", config),
+            Err(ContainerError::AlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_container() {
+        let manager = ContainerManager::new();
+        assert!(matches!(
+            manager.start("nope"),
+            Err(ContainerError::NotFound(_))
+        ));
+        assert!(matches!(
+            manager.destroy("nope"),
+            Err(ContainerError::NotFound(_))
+        ));
+        assert!(manager.remove("nope").is_none());
+        assert!(manager.list(&ContainerFilter::new()).is_empty());
+        assert!(manager.stop_all(&ContainerFilter::new()).is_empty());
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let config = ContainerConfig::builder()
+            .label("app", "web")
+            .label("tier", "front")
+            .build();
+        let running = ContainerState::Running;
+
+        assert!(ContainerFilter::new().matches_parts(running, &config));
+        assert!(ContainerFilter::new()
+            .label("app", "web")
+            .has_label("tier")
+            .matches_parts(running, &config));
+        assert!(!ContainerFilter::new()
+            .label("app", "db")
+            .matches_parts(running, &config));
+        assert!(!ContainerFilter::new()
+            .has_label("zone")
+            .matches_parts(running, &config));
+
+        let exited = ContainerFilter::new()
+            .state(ContainerState::Exited { code: 0 })
+            .state(ContainerState::Stopped);
+        assert!(exited.matches_parts(ContainerState::Exited { code: 137 }, &config));
+        assert!(exited.matches_parts(ContainerState::Stopped, &config));
+        assert!(!exited.matches_parts(running, &config));
+    }
+}
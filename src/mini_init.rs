@@ -0,0 +1,210 @@
+//! Minimal Init
+//!
+//! With `mini_init` set, PID 1 of the container is not the workload but a
+//! small supervisor, in the spirit of tini. The init process forks the
+//! workload once the container is fully set up, right before `execve(2)`:
+//!
+//! ```text
+//! init (PID 1)                              workload
+//!   block all signals
+//!   fork ─────────────────────────────────▶ own process group, foreground on a terminal
+//!   close the init channel                  restore signal mask
+//!   loop on sigwaitinfo(2)                  execve(2)
+//!     SIGCHLD → reap every exited child
+//!     other   → forward to the workload
+//!   exit with the workload's exit code
+//! ```
+//!
+//! Without it, PID 1 of a PID namespace ignores every signal it has no
+//! handler for, and orphans reparented to it are never reaped. The
+//! supervisor runs with the workload's user, capabilities and limits.
+//!
+//! The exit code is the workload's, or `128 + signal` if it was killed.
+//! Once PID 1 exits, the kernel kills whatever is left in the namespace.
+//! Outside a new PID namespace the supervisor becomes a child subreaper, so
+//! it still collects the workload's orphans.
+
+use crate::container::{exit_code_from_status, ContainerError, SYS_CLOSE_RANGE};
+use crate::handshake::InitChannel;
+
+/// Fork the workload and supervise it from the calling process
+///
+/// Returns `Ok(())` in the forked workload, which is expected to exec; the
+/// calling process stays in the supervisor loop until the workload exits.
+///
+/// # Errors
+///
+/// Returns an error if the workload cannot be forked.
+pub(crate) fn fork_workload(channel: &InitChannel) -> Result<(), ContainerError> {
+    // SAFETY: sigset_t is plain data; an all-zero value is valid before sigfillset(3).
+    let mut all: libc::sigset_t = unsafe { core::mem::zeroed() };
+    // SAFETY: as above.
+    let mut previous: libc::sigset_t = unsafe { core::mem::zeroed() };
+    // SAFETY: both sets are valid for reads and writes. Blocking first leaves no window in
+    // which a signal meant for the workload hits the supervisor's default disposition.
+    unsafe {
+        libc::sigfillset(&mut all);
+        libc::sigprocmask(libc::SIG_SETMASK, &all, &mut previous);
+        // Only has an effect outside a new PID namespace
+        libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1 as libc::c_ulong);
+    }
+
+    // SAFETY: the init process is single-threaded; the child only makes syscalls before exec.
+    match unsafe { libc::fork() } {
+        -1 => {
+            let e = std::io::Error::last_os_error();
+            // SAFETY: previous holds the mask saved above.
+            unsafe { libc::sigprocmask(libc::SIG_SETMASK, &previous, core::ptr::null_mut()) };
+            Err(ContainerError::ProcessError(format!("fork workload: {e}")))
+        }
+        0 => {
+            // SAFETY: setpgid(2), isatty(3) and tcsetpgrp(3) take plain values; SIGTTOU is
+            // still blocked, so taking the terminal's foreground does not stop us.
+            unsafe {
+                libc::setpgid(0, 0);
+                if libc::isatty(libc::STDIN_FILENO) == 1 {
+                    libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+                }
+                libc::sigprocmask(libc::SIG_SETMASK, &previous, core::ptr::null_mut());
+            }
+            Ok(())
+        }
+        workload => {
+            // Set on both sides, so signals cannot reach the old group in between
+            // SAFETY: workload is our child, which has not exec'd yet.
+            unsafe { libc::setpgid(workload, workload) };
+            // The workload's exec is now the only thing that ends the handshake
+            channel.close_child_end_in_supervisor();
+            // The workload has its own copies; the supervisor needs none
+            // SAFETY: close_range(2) takes plain integers and does not touch memory.
+            unsafe { libc::syscall(SYS_CLOSE_RANGE, 3u32, u32::MAX, 0u32) };
+            supervise(workload, &all)
+        }
+    }
+}
+
+/// Forward signals to the workload and reap children until it exits
+fn supervise(workload: libc::pid_t, signals: &libc::sigset_t) -> ! {
+    loop {
+        // SAFETY: signals is a valid set; siginfo is not needed.
+        let signal = unsafe { libc::sigwaitinfo(signals, core::ptr::null_mut()) };
+        if signal < 0 {
+            continue;
+        }
+        if signal != libc::SIGCHLD {
+            // SAFETY: the workload is our child and is only reaped below, so its PID is ours.
+            unsafe { libc::kill(workload, signal) };
+            continue;
+        }
+        if let Some(code) = reap(workload) {
+            // SAFETY: _exit(2) terminates without running the runtime's atexit handlers.
+            unsafe { libc::_exit(code) };
+        }
+    }
+}
+
+/// Reap every exited child, returning the workload's exit code if it was one
+fn reap(workload: libc::pid_t) -> Option<i32> {
+    let mut code = None;
+    loop {
+        let mut status: libc::c_int = 0;
+        // SAFETY: status is a valid out-pointer.
+        let pid = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+        if pid <= 0 {
+            return code;
+        }
+        if pid == workload {
+            code = Some(exit_code_from_status(status));
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    /// Wait up to `timeout` for `pid` to exit, killing it if it does not
+    fn wait_with_deadline(pid: libc::pid_t, timeout: Duration) -> Option<i32> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            let mut status: libc::c_int = 0;
+            // SAFETY: status is a valid out-pointer.
+            if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid {
+                return Some(exit_code_from_status(status));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // SAFETY: pid is our unreaped child.
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+            libc::waitpid(pid, core::ptr::null_mut(), 0);
+        }
+        None
+    }
+
+    #[test]
+    fn test_supervise_forwards_signals_and_reaps_orphans() {
+        let (report, report_end) = crate::container::pipe().unwrap();
+
+        // SAFETY: the children only make syscalls and leave with _exit(2).
+        let supervisor = unsafe { libc::fork() };
+        assert!(supervisor >= 0);
+        if supervisor == 0 {
+            // SAFETY: as in fork_workload(); every child leaves with _exit(2).
+            unsafe {
+                let mut all: libc::sigset_t = core::mem::zeroed();
+                libc::sigfillset(&mut all);
+                libc::sigprocmask(libc::SIG_SETMASK, &all, core::ptr::null_mut());
+                libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1 as libc::c_ulong);
+                let workload = libc::fork();
+                if workload == 0 {
+                    // Orphan a grandchild: its parent exits at once
+                    let parent = libc::fork();
+                    if parent == 0 {
+                        if libc::fork() == 0 {
+                            let pid = libc::getpid().to_ne_bytes();
+                            libc::write(report_end.as_raw_fd(), pid.as_ptr().cast(), 4);
+                            libc::_exit(0);
+                        }
+                        libc::_exit(0);
+                    }
+                    libc::waitpid(parent, core::ptr::null_mut(), 0);
+                    // Exit 3 once the supervisor forwards SIGUSR1
+                    let mut usr1: libc::sigset_t = core::mem::zeroed();
+                    libc::sigemptyset(&mut usr1);
+                    libc::sigaddset(&mut usr1, libc::SIGUSR1);
+                    while libc::sigwaitinfo(&usr1, core::ptr::null_mut()) != libc::SIGUSR1 {}
+                    libc::_exit(3);
+                }
+                supervise(workload, &all)
+            }
+        }
+        drop(report_end);
+
+        let mut pid = [0u8; 4];
+        let reported = std::fs::File::from(report).read_exact(&mut pid);
+        let orphan = i32::from_ne_bytes(pid);
+        // Only the supervisor can reap the orphan, and it is still running
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let proc_path = format!("/proc/{orphan}");
+        while std::path::Path::new(&proc_path).exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let reaped = !std::path::Path::new(&proc_path).exists();
+        // SAFETY: supervisor is our unreaped child.
+        unsafe { libc::kill(supervisor, libc::SIGUSR1) };
+        let code = wait_with_deadline(supervisor, Duration::from_secs(10));
+
+        reported.unwrap();
+        assert!(reaped, "orphan {orphan} was not reaped");
+        assert_eq!(code, Some(3));
+    }
+}
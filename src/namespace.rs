@@ -96,6 +96,38 @@ impl NamespaceFlags {
     }
 }
 
+impl NamespaceFlags {
+    /// Single-namespace flags in the order they must be joined with `setns(2)`
+    ///
+    /// The user namespace comes first so that the caller gains the capabilities
+    /// needed to enter the others; the mount namespace comes last because it
+    /// changes the caller's root directory.
+    pub const JOIN_ORDER: [Self; 7] = [
+        Self::NEWUSER,
+        Self::NEWIPC,
+        Self::NEWUTS,
+        Self::NEWNET,
+        Self::NEWPID,
+        Self::NEWCGROUP,
+        Self::NEWNS,
+    ];
+
+    /// Name of the namespace file under `/proc/<pid>/ns/` for a single-namespace flag
+    #[must_use]
+    pub const fn proc_name(&self) -> Option<&'static str> {
+        match self.0 {
+            Self::CLONE_NEWNS_VAL => Some("mnt"),
+            Self::CLONE_NEWPID_VAL => Some("pid"),
+            Self::CLONE_NEWNET_VAL => Some("net"),
+            Self::CLONE_NEWUTS_VAL => Some("uts"),
+            Self::CLONE_NEWIPC_VAL => Some("ipc"),
+            Self::CLONE_NEWUSER_VAL => Some("user"),
+            Self::CLONE_NEWCGROUP_VAL => Some("cgroup"),
+            _ => None,
+        }
+    }
+}
+
 impl core::ops::BitOr for NamespaceFlags {
     type Output = Self;
 
@@ -193,6 +225,109 @@ impl Namespaces {
     }
 }
 
+// ============================================================================
+// Joining Namespaces (setns)
+// ============================================================================
+
+/// Join the namespace referred to by `fd` (Linux only)
+#[cfg(target_os = "linux")]
+pub fn setns(fd: c_int, nstype: NamespaceFlags) -> Result<(), NamespaceError> {
+    // SAFETY: setns(2) takes a file descriptor and a CLONE_NEW* flag; the kernel validates
+    // both and returns -1 on error. No pointers are involved.
+    let ret = unsafe { libc::setns(fd, nstype.bits()) };
+    if ret < 0 {
+        Err(NamespaceError::from_errno())
+    } else {
+        Ok(())
+    }
+}
+
+/// Join namespace (non-Linux stub)
+///
+/// # Errors
+///
+/// Returns an error if the operation fails.
+#[cfg(not(target_os = "linux"))]
+pub const fn setns(_fd: c_int, _nstype: NamespaceFlags) -> Result<(), NamespaceError> {
+    Err(NamespaceError::NotSupported)
+}
+
+/// Open namespace files of another process, ready to be joined with `setns(2)`
+///
+/// All files are opened up front so that joining the mount namespace does not
+/// change what `/proc/<pid>/ns/*` resolves to. Namespaces the target shares
+/// with the calling process are skipped, since re-entering the current user
+/// namespace is rejected by the kernel.
+#[cfg(all(feature = "std", target_os = "linux"))]
+#[derive(Debug)]
+pub struct NamespaceFds {
+    /// Opened namespace files in join order
+    fds: Vec<(NamespaceFlags, std::fs::File)>,
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl NamespaceFds {
+    /// Open every namespace of `pid` that differs from the caller's
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a namespace file cannot be opened.
+    pub fn open(pid: u32) -> Result<Self, NamespaceError> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut fds = Vec::new();
+        for flag in NamespaceFlags::JOIN_ORDER {
+            let Some(name) = flag.proc_name() else {
+                continue;
+            };
+
+            let target = format!("/proc/{pid}/ns/{name}");
+            let file = match std::fs::File::open(&target) {
+                Ok(file) => file,
+                // Kernel built without this namespace type
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(NamespaceError::from_io(&e)),
+            };
+
+            let theirs = file.metadata().map_err(|e| NamespaceError::from_io(&e))?;
+            if let Ok(ours) = std::fs::metadata(format!("/proc/self/ns/{name}")) {
+                if ours.dev() == theirs.dev() && ours.ino() == theirs.ino() {
+                    continue;
+                }
+            }
+
+            fds.push((flag, file));
+        }
+
+        Ok(Self { fds })
+    }
+
+    /// Namespaces that will be joined
+    #[must_use]
+    pub fn flags(&self) -> NamespaceFlags {
+        self.fds
+            .iter()
+            .fold(NamespaceFlags::from_bits(0), |acc, (flag, _)| acc | *flag)
+    }
+
+    /// Join all opened namespaces in order
+    ///
+    /// Joining a PID namespace only affects children created afterwards, so
+    /// callers must fork once more before exec.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any `setns(2)` call fails.
+    pub fn enter(&self) -> Result<(), NamespaceError> {
+        use std::os::unix::io::AsRawFd;
+
+        for (flag, file) in &self.fds {
+            setns(file.as_raw_fd(), *flag)?;
+        }
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Clone with Namespaces
 // ============================================================================
@@ -295,6 +430,17 @@ where
         return Err(NamespaceError::from_errno());
     }
 
+    // Without CLONE_VM the child runs on its own copy of the stack and closure,
+    // so the parent's copies can be released immediately.
+    if flags.bits() & libc::CLONE_VM == 0 {
+        // SAFETY: fn_ptr was created by Box::into_raw above and was never reclaimed in this
+        // (parent) address space; the child owns an independent copy.
+        drop(Box::from_raw(fn_ptr));
+        // SAFETY: stack and stack_size match the preceding successful mmap call; the child
+        // does not share this mapping because CLONE_VM is not set.
+        libc::munmap(stack, stack_size);
+    }
+
     Ok(pid as u32)
 }
 
@@ -462,6 +608,17 @@ impl NamespaceError {
         }
     }
 
+    /// Create error from an I/O error on a namespace file
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    fn from_io(e: &std::io::Error) -> Self {
+        match e.raw_os_error() {
+            Some(libc::EPERM | libc::EACCES) => NamespaceError::PermissionDenied,
+            Some(libc::ENOENT | libc::ESRCH) => NamespaceError::InvalidPath,
+            Some(errno) => NamespaceError::OsError(errno),
+            None => NamespaceError::InvalidArgument,
+        }
+    }
+
     /// Create error from errno (non-Linux stub)
     #[cfg(not(target_os = "linux"))]
     #[allow(dead_code)]
@@ -593,6 +750,89 @@ pub const fn write_gid_map(_pid: u32, _mapping: &IdMapping) -> Result<(), Namesp
     Err(NamespaceError::NotSupported)
 }
 
+/// ID shown for users and groups without a mapping in a user namespace
+#[cfg(feature = "std")]
+pub(crate) const OVERFLOW_ID: u32 = 65_534;
+
+/// UID or GID mappings of a process, as read from `/proc/<pid>/uid_map`
+///
+/// Translates between host IDs and IDs inside the process's user namespace.
+/// IDs outside every range have no counterpart.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdMap {
+    ranges: Vec<IdMapping>,
+}
+
+#[cfg(feature = "std")]
+impl IdMap {
+    /// Parse `inside outside count` lines, skipping malformed ones
+    pub(crate) fn parse(content: &str) -> Self {
+        let ranges = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().map(str::parse);
+                Some(IdMapping {
+                    inner_id: fields.next()?.ok()?,
+                    outer_id: fields.next()?.ok()?,
+                    count: fields.next()?.ok()?,
+                })
+            })
+            .collect();
+        Self { ranges }
+    }
+
+    /// Read `/proc/<pid>/<file>`, e.g. `uid_map`
+    ///
+    /// Without the file the process is assumed to share our user namespace.
+    pub(crate) fn read(pid: u32, file: &str) -> Self {
+        std::fs::read_to_string(format!("/proc/{pid}/{file}"))
+            .map_or_else(|_| Self::identity(), |content| Self::parse(&content))
+    }
+
+    /// A namespace with one mapped range
+    pub(crate) fn single(mapping: IdMapping) -> Self {
+        Self {
+            ranges: vec![mapping],
+        }
+    }
+
+    /// The mapping of the initial user namespace
+    pub(crate) fn identity() -> Self {
+        Self {
+            ranges: vec![IdMapping {
+                inner_id: 0,
+                outer_id: 0,
+                count: u32::MAX,
+            }],
+        }
+    }
+
+    /// ID inside the namespace of host ID `outer`
+    pub(crate) fn to_inner(&self, outer: u32) -> Option<u32> {
+        self.ranges.iter().find_map(|r| {
+            let offset = outer.checked_sub(r.outer_id)?;
+            if offset < r.count {
+                r.inner_id.checked_add(offset)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Host ID of ID `inner` inside the namespace
+    pub(crate) fn to_outer(&self, inner: u32) -> Option<u32> {
+        self.ranges.iter().find_map(|r| {
+            let offset = inner.checked_sub(r.inner_id)?;
+            if offset < r.count {
+                r.outer_id.checked_add(offset)
+            } else {
+                None
+            }
+        })
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -768,6 +1008,23 @@ mod tests {
 
     // --- IdMapping additional tests ---
 
+    #[test]
+    fn test_id_map_translation() {
+        let map =
+            IdMap::parse("         0     100000      65536\n     65536       1000          1\n");
+        assert_eq!(map.to_inner(100_000), Some(0));
+        assert_eq!(map.to_inner(101_000), Some(1000));
+        assert_eq!(map.to_inner(1000), Some(65_536));
+        assert_eq!(map.to_inner(5), None);
+        assert_eq!(map.to_outer(0), Some(100_000));
+        assert_eq!(map.to_outer(65_536), Some(1000));
+        assert_eq!(map.to_outer(65_537), None);
+
+        let identity = IdMap::parse("0 0 4294967295\n");
+        assert_eq!(identity.to_inner(1000), Some(1000));
+        assert_eq!(IdMap::identity().to_outer(4242), Some(4242));
+    }
+
     #[test]
     fn test_id_mapping_identity() {
         let mapping = IdMapping::identity(500);
@@ -845,6 +1102,39 @@ mod tests {
         assert_ne!(NamespaceError::OsError(1), NamespaceError::OsError(2));
     }
 
+    #[test]
+    fn test_namespace_flags_proc_name() {
+        assert_eq!(NamespaceFlags::NEWNS.proc_name(), Some("mnt"));
+        assert_eq!(NamespaceFlags::NEWPID.proc_name(), Some("pid"));
+        assert_eq!(NamespaceFlags::NEWNET.proc_name(), Some("net"));
+        assert_eq!(NamespaceFlags::NEWUTS.proc_name(), Some("uts"));
+        assert_eq!(NamespaceFlags::NEWIPC.proc_name(), Some("ipc"));
+        assert_eq!(NamespaceFlags::NEWUSER.proc_name(), Some("user"));
+        assert_eq!(NamespaceFlags::NEWCGROUP.proc_name(), Some("cgroup"));
+        assert_eq!(NamespaceFlags::CONTAINER.proc_name(), None);
+    }
+
+    #[test]
+    fn test_namespace_join_order_user_first_mount_last() {
+        let order = NamespaceFlags::JOIN_ORDER;
+        assert_eq!(order[0], NamespaceFlags::NEWUSER);
+        assert_eq!(order[order.len() - 1], NamespaceFlags::NEWNS);
+        let all = order
+            .iter()
+            .fold(NamespaceFlags::from_bits(0), |a, f| a | *f);
+        assert!(all.contains(NamespaceFlags::ALL));
+        assert!(all.contains(NamespaceFlags::NEWUSER));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_namespace_fds_open_self_is_empty() {
+        // Every namespace of our own process is shared with us, so nothing to join
+        let fds = NamespaceFds::open(std::process::id()).unwrap();
+        assert_eq!(fds.flags(), NamespaceFlags::from_bits(0));
+        assert!(fds.enter().is_ok());
+    }
+
     #[test]
     fn test_mnt_detach_value() {
         assert_eq!(MNT_DETACH, 2);
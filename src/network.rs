@@ -299,6 +299,15 @@ impl Bridge {
         Ok(())
     }
 
+    /// ホスト上に同名のインターフェースが既に存在するか。
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn exists(&self) -> bool {
+        std::path::Path::new("/sys/class/net")
+            .join(&self.name)
+            .exists()
+    }
+
     /// ブリッジを作成 (non-Linux stub)。
     ///
     /// # Errors
@@ -408,17 +417,110 @@ pub fn setup_container_network(
     container_pid: u32,
 ) -> Result<(Bridge, VethPair), NetworkError> {
     let mut bridge = Bridge::from_config(config);
-    bridge.create()?;
+    // 既存のブリッジは他コンテナと共有するため created = false のまま
+    if !bridge.exists() {
+        bridge.create()?;
+    }
 
     let mut veth = VethPair::from_config(config);
     veth.create()?;
 
-    bridge.attach_veth(&veth.host_name)?;
-    veth.move_to_netns(container_pid)?;
+    let attached = bridge
+        .attach_veth(&veth.host_name)
+        .and_then(|()| veth.move_to_netns(container_pid));
+    if let Err(e) = attached {
+        let _ = veth.destroy();
+        return Err(e);
+    }
 
     Ok((bridge, veth))
 }
 
+/// コンテナ側 veth をネットワーク名前空間の内側で設定。
+///
+/// `setup_container_network` の後に呼び、以下を行う。
+///
+/// 1. loopback を UP
+/// 2. コンテナ側 veth に IP を付与して UP
+/// 3. ゲートウェイ経由のデフォルトルートを追加
+///
+/// # Errors
+///
+/// netns に入れない場合や `ip` コマンドが失敗した場合にエラー。
+#[cfg(target_os = "linux")]
+pub fn configure_container_interface(
+    config: &NetworkConfig,
+    container_pid: u32,
+) -> Result<(), NetworkError> {
+    let netns = std::fs::File::open(format!("/proc/{container_pid}/ns/net")).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            NetworkError::PermissionDenied
+        } else {
+            NetworkError::CommandFailed(format!("open netns of {container_pid}: {e}"))
+        }
+    })?;
+
+    let dev = config.veth_container.as_str();
+    run_ip_in_netns(&netns, &["link", "set", "lo", "up"])?;
+    run_ip_in_netns(&netns, &["addr", "add", &config.container_ip, "dev", dev])
+        .map_err(|e| NetworkError::AddressError(e.to_string()))?;
+    run_ip_in_netns(&netns, &["link", "set", dev, "up"])?;
+    run_ip_in_netns(
+        &netns,
+        &["route", "add", "default", "via", &config.gateway_ip],
+    )?;
+
+    Ok(())
+}
+
+/// コンテナ側 veth をネットワーク名前空間の内側で設定 (non-Linux stub)。
+///
+/// # Errors
+///
+/// Linux以外ではサポート外エラー。
+#[cfg(not(target_os = "linux"))]
+pub const fn configure_container_interface(
+    _config: &NetworkConfig,
+    _container_pid: u32,
+) -> Result<(), NetworkError> {
+    Err(NetworkError::NotSupported)
+}
+
+/// 指定 netns の中で `ip` コマンドを実行。
+#[cfg(target_os = "linux")]
+fn run_ip_in_netns(netns: &std::fs::File, args: &[&str]) -> Result<(), NetworkError> {
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    let fd = netns.as_raw_fd();
+    let mut command = Command::new("ip");
+    command.args(args);
+    // SAFETY: pre_exec はフォーク後の子プロセスで実行される。setns(2) は
+    // async-signal-safe なシステムコールで、fd は親が開いたまま保持している。
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setns(fd, libc::CLONE_NEWNET) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let output = command
+        .output()
+        .map_err(|e| NetworkError::CommandFailed(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(NetworkError::CommandFailed(format!(
+            "ip {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
 /// コンテナネットワークを一括セットアップ (non-Linux stub)。
 ///
 /// # Errors
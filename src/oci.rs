@@ -3,8 +3,12 @@
 //! Open Container Initiative Runtime Spec v1.0 に準拠した
 //! コンテナ設定の構造体群。既存の `ContainerConfig` との相互変換を提供。
 
-use crate::container::ContainerConfig;
+use crate::capability::{Capabilities, Capability, CapabilitySet};
+use crate::console::WindowSize;
+use crate::container::{ContainerConfig, ProcessUser, RestartPolicy};
+use crate::hooks::{Hook, Hooks};
 use crate::namespace::NamespaceFlags;
+use crate::rlimit::{Rlimit, RlimitResource};
 
 // ============================================================================
 // OCI Spec 構造体
@@ -25,6 +29,8 @@ pub struct OciSpec {
     pub mounts: Vec<OciMount>,
     /// Linux 固有設定。
     pub linux: OciLinux,
+    /// ライフサイクルフック。
+    pub hooks: OciHooks,
 }
 
 impl Default for OciSpec {
@@ -36,6 +42,7 @@ impl Default for OciSpec {
             hostname: "container".to_string(),
             mounts: default_mounts(),
             linux: OciLinux::default(),
+            hooks: OciHooks::default(),
         }
     }
 }
@@ -53,6 +60,12 @@ pub struct OciProcess {
     pub user: OciUser,
     /// ターミナル割り当て。
     pub terminal: bool,
+    /// ターミナルの初期サイズ (None で既定)。
+    pub console_size: Option<OciBox>,
+    /// ケーパビリティ (None でデフォルト)。
+    pub capabilities: Option<OciCapabilities>,
+    /// リソース制限。
+    pub rlimits: Vec<OciRlimit>,
 }
 
 impl Default for OciProcess {
@@ -66,6 +79,99 @@ impl Default for OciProcess {
             cwd: "/".to_string(),
             user: OciUser::default(),
             terminal: false,
+            console_size: None,
+            capabilities: None,
+            rlimits: vec![OciRlimit {
+                rlimit_type: "RLIMIT_CORE".to_string(),
+                hard: 0,
+                soft: 0,
+            }],
+        }
+    }
+}
+
+/// OCI ターミナルサイズ (`consoleSize`)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OciBox {
+    /// 行数。
+    pub height: u32,
+    /// 桁数。
+    pub width: u32,
+}
+
+/// OCI リソース制限 (setrlimit)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OciRlimit {
+    /// 種類 (例: "RLIMIT_NOFILE")。
+    pub rlimit_type: String,
+    /// ハードリミット。
+    pub hard: u64,
+    /// ソフトリミット。
+    pub soft: u64,
+}
+
+impl From<&Rlimit> for OciRlimit {
+    fn from(limit: &Rlimit) -> Self {
+        Self {
+            rlimit_type: limit.resource.name().to_string(),
+            hard: limit.hard,
+            soft: limit.soft,
+        }
+    }
+}
+
+impl OciRlimit {
+    /// `Rlimit` に変換 (未知の種類は None)。
+    #[must_use]
+    pub fn to_rlimit(&self) -> Option<Rlimit> {
+        RlimitResource::from_name(&self.rlimit_type)
+            .map(|resource| Rlimit::new(resource, self.soft, self.hard))
+    }
+}
+
+/// OCI ケーパビリティ (名前は "CAP_XXX" 形式)。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OciCapabilities {
+    /// バウンディングセット。
+    pub bounding: Vec<String>,
+    /// 実効セット。
+    pub effective: Vec<String>,
+    /// 許可セット。
+    pub permitted: Vec<String>,
+    /// 継承可能セット。
+    pub inheritable: Vec<String>,
+    /// アンビエントセット。
+    pub ambient: Vec<String>,
+}
+
+impl From<&Capabilities> for OciCapabilities {
+    fn from(caps: &Capabilities) -> Self {
+        let names = |set: CapabilitySet| set.iter().map(|c| c.name().to_string()).collect();
+        Self {
+            bounding: names(caps.bounding),
+            effective: names(caps.effective),
+            permitted: names(caps.permitted),
+            inheritable: names(caps.inheritable),
+            ambient: names(caps.ambient),
+        }
+    }
+}
+
+impl From<&OciCapabilities> for Capabilities {
+    /// 未知のケーパビリティ名は無視する。
+    fn from(caps: &OciCapabilities) -> Self {
+        let set = |names: &[String]| {
+            names
+                .iter()
+                .filter_map(|n| Capability::from_name(n))
+                .collect()
+        };
+        Self {
+            bounding: set(&caps.bounding),
+            effective: set(&caps.effective),
+            permitted: set(&caps.permitted),
+            inheritable: set(&caps.inheritable),
+            ambient: set(&caps.ambient),
         }
     }
 }
@@ -79,6 +185,8 @@ pub struct OciUser {
     pub gid: u32,
     /// 追加 GID。
     pub additional_gids: Vec<u32>,
+    /// umask (None で継承)。
+    pub umask: Option<u32>,
 }
 
 /// OCI ルートファイルシステム。
@@ -150,6 +258,101 @@ pub fn default_mounts() -> Vec<OciMount> {
     ]
 }
 
+// ============================================================================
+// ライフサイクルフック
+// ============================================================================
+
+/// OCI フック定義。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OciHook {
+    /// 実行ファイルの絶対パス。
+    pub path: String,
+    /// 引数 (argv[0] を含む)。
+    pub args: Vec<String>,
+    /// 環境変数 (KEY=VALUE 形式)。
+    pub env: Vec<String>,
+    /// タイムアウト (秒)。
+    pub timeout: Option<u32>,
+}
+
+/// OCI フック一覧 (フェーズ別)。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OciHooks {
+    /// 非推奨: `create_runtime` の前に実行。
+    pub prestart: Vec<OciHook>,
+    /// 名前空間作成後、ランタイム名前空間で実行。
+    pub create_runtime: Vec<OciHook>,
+    /// 名前空間作成後、コンテナ名前空間で実行。
+    pub create_container: Vec<OciHook>,
+    /// ユーザープロセス実行直前、コンテナ内で実行。
+    pub start_container: Vec<OciHook>,
+    /// ユーザープロセス起動後に実行。
+    pub poststart: Vec<OciHook>,
+    /// コンテナ停止後に実行。
+    pub poststop: Vec<OciHook>,
+}
+
+/// `Hook` を `OciHook` に変換。
+fn hook_to_oci(hook: &Hook) -> OciHook {
+    OciHook {
+        path: hook.path.to_string_lossy().to_string(),
+        args: hook.args.clone(),
+        env: hook.env.iter().map(|(k, v)| format!("{k}={v}")).collect(),
+        // 秒未満は切り上げ
+        timeout: hook.timeout.map(|t| {
+            let secs = t.as_secs() + u64::from(t.subsec_nanos() > 0);
+            u32::try_from(secs).unwrap_or(u32::MAX)
+        }),
+    }
+}
+
+/// `OciHook` を `Hook` に変換。
+fn hook_from_oci(hook: &OciHook) -> Hook {
+    Hook {
+        path: hook.path.clone().into(),
+        args: hook.args.clone(),
+        env: hook
+            .env
+            .iter()
+            .map(|e| match e.split_once('=') {
+                Some((k, v)) => (k.to_string(), v.to_string()),
+                None => (e.clone(), String::new()),
+            })
+            .collect(),
+        timeout: hook
+            .timeout
+            .map(|secs| std::time::Duration::from_secs(u64::from(secs))),
+    }
+}
+
+impl From<&Hooks> for OciHooks {
+    fn from(hooks: &Hooks) -> Self {
+        let convert = |list: &[Hook]| list.iter().map(hook_to_oci).collect();
+        Self {
+            prestart: convert(&hooks.prestart),
+            create_runtime: convert(&hooks.create_runtime),
+            create_container: convert(&hooks.create_container),
+            start_container: convert(&hooks.start_container),
+            poststart: convert(&hooks.poststart),
+            poststop: convert(&hooks.poststop),
+        }
+    }
+}
+
+impl From<&OciHooks> for Hooks {
+    fn from(hooks: &OciHooks) -> Self {
+        let convert = |list: &[OciHook]| list.iter().map(hook_from_oci).collect();
+        Self {
+            prestart: convert(&hooks.prestart),
+            create_runtime: convert(&hooks.create_runtime),
+            create_container: convert(&hooks.create_container),
+            start_container: convert(&hooks.start_container),
+            poststart: convert(&hooks.poststart),
+            poststop: convert(&hooks.poststop),
+        }
+    }
+}
+
 // ============================================================================
 // Linux 固有設定
 // ============================================================================
@@ -282,6 +485,8 @@ pub struct OciLinuxResources {
     pub cpu: OciCpuResources,
     /// メモリ制限。
     pub memory: OciMemoryResources,
+    /// プロセス数制限。
+    pub pids: OciPidsResources,
 }
 
 /// OCI CPU リソース。
@@ -293,6 +498,17 @@ pub struct OciCpuResources {
     pub period: Option<u64>,
     /// CPU シェア。
     pub shares: Option<u64>,
+    /// 使用可能な CPU (例: "0-3")。
+    pub cpus: Option<String>,
+    /// 使用可能なメモリノード。
+    pub mems: Option<String>,
+}
+
+/// OCI プロセス数リソース。
+#[derive(Debug, Clone, Default)]
+pub struct OciPidsResources {
+    /// 最大タスク数 (0 以下で無制限)。
+    pub limit: Option<i64>,
 }
 
 /// OCI メモリリソース。
@@ -355,6 +571,8 @@ pub fn from_container_config(config: &ContainerConfig) -> OciSpec {
         quota: Some(config.cpu.quota_us),
         period: Some(config.cpu.period_us),
         shares: None,
+        cpus: config.cpuset.as_ref().map(|c| c.cpus.clone()),
+        mems: config.cpuset.as_ref().map(|c| c.mems.clone()),
     };
 
     let memory = OciMemoryResources {
@@ -365,11 +583,22 @@ pub fn from_container_config(config: &ContainerConfig) -> OciSpec {
     OciSpec {
         oci_version: "1.0.2".to_string(),
         process: OciProcess {
-            args: vec!["/bin/sh".to_string()],
+            args: config.args.clone(),
             env,
             cwd: config.workdir.to_string_lossy().to_string(),
-            user: OciUser::default(),
-            terminal: false,
+            user: OciUser {
+                uid: config.user.uid,
+                gid: config.user.gid,
+                additional_gids: config.user.additional_gids.clone(),
+                umask: config.user.umask,
+            },
+            terminal: config.terminal,
+            console_size: config.console_size.map(|size| OciBox {
+                height: u32::from(size.rows),
+                width: u32::from(size.cols),
+            }),
+            capabilities: Some(OciCapabilities::from(&config.capabilities)),
+            rlimits: config.rlimits.iter().map(OciRlimit::from).collect(),
         },
         root: OciRoot {
             path: config.rootfs.to_string_lossy().to_string(),
@@ -379,16 +608,24 @@ pub fn from_container_config(config: &ContainerConfig) -> OciSpec {
         mounts: default_mounts(),
         linux: OciLinux {
             namespaces,
-            resources: OciLinuxResources { cpu, memory },
+            resources: OciLinuxResources {
+                cpu,
+                memory,
+                pids: OciPidsResources {
+                    limit: (config.pids.max != u64::MAX)
+                        .then(|| i64::try_from(config.pids.max).unwrap_or(i64::MAX)),
+                },
+            },
             ..OciLinux::default()
         },
+        hooks: OciHooks::from(&config.hooks),
     }
 }
 
 /// `OciSpec` から `ContainerConfig` に変換。
 #[must_use]
 pub fn to_container_config(spec: &OciSpec) -> ContainerConfig {
-    use crate::cgroup::{CpuConfig, MemoryConfig};
+    use crate::cgroup::{CpuConfig, CpusetConfig, MemoryConfig, PidsConfig};
     use std::path::PathBuf;
 
     let env: Vec<(String, String)> = spec
@@ -423,8 +660,40 @@ pub fn to_container_config(spec: &OciSpec) -> ContainerConfig {
         rootfs: PathBuf::from(&spec.root.path),
         hostname: spec.hostname.clone(),
         workdir: PathBuf::from(&spec.process.cwd),
+        args: spec.process.args.clone(),
         env,
+        user: ProcessUser {
+            additional_gids: spec.process.user.additional_gids.clone(),
+            umask: spec.process.user.umask,
+            ..ProcessUser::new(spec.process.user.uid, spec.process.user.gid)
+        },
+        terminal: spec.process.terminal,
+        console_socket: None,
+        console_size: spec.process.console_size.map(|size| {
+            WindowSize::new(
+                u16::try_from(size.height).unwrap_or(u16::MAX),
+                u16::try_from(size.width).unwrap_or(u16::MAX),
+            )
+        }),
+        log: None,
+        mini_init: false,
+        preserve_fds: Vec::new(),
+        parent_death_signal: Some(libc::SIGKILL),
+        capabilities: spec
+            .process
+            .capabilities
+            .as_ref()
+            .map(Capabilities::from)
+            .unwrap_or_default(),
+        rlimits: spec
+            .process
+            .rlimits
+            .iter()
+            .filter_map(OciRlimit::to_rlimit)
+            .collect(),
         namespaces: flags,
+        uid_map: None,
+        gid_map: None,
         cpu: CpuConfig {
             quota_us: cpu_quota,
             period_us: cpu_period,
@@ -432,8 +701,33 @@ pub fn to_container_config(spec: &OciSpec) -> ContainerConfig {
         },
         memory: MemoryConfig::with_limit(memory_max),
         io: None,
+        pids: PidsConfig {
+            max: spec
+                .linux
+                .resources
+                .pids
+                .limit
+                .and_then(|limit| u64::try_from(limit).ok())
+                .filter(|&limit| limit > 0)
+                .unwrap_or(u64::MAX),
+        },
+        cpuset: {
+            let cpu = &spec.linux.resources.cpu;
+            (cpu.cpus.is_some() || cpu.mems.is_some()).then(|| {
+                CpusetConfig::new(
+                    cpu.cpus.as_deref().unwrap_or_default(),
+                    cpu.mems.as_deref().unwrap_or_default(),
+                )
+            })
+        },
         readonly_rootfs: spec.root.readonly,
         network,
+        network_config: None,
+        state_root: PathBuf::from(crate::STATE_ROOT),
+        labels: Vec::new(),
+        restart_policy: RestartPolicy::Never,
+        health_check: None,
+        hooks: Hooks::from(&spec.hooks),
     }
 }
 
@@ -522,6 +816,45 @@ mod tests {
         assert_eq!(config.network, config2.network);
     }
 
+    #[test]
+    fn process_roundtrip_preserves_args_user_terminal() {
+        let config = ContainerConfig::builder()
+            .args(["/usr/bin/server", "--port", "7777"])
+            .user(1000, 100)
+            .additional_gids(vec![20, 30])
+            .umask(0o027)
+            .terminal(true)
+            .console_size(30, 100)
+            .build();
+        let spec = from_container_config(&config);
+        assert_eq!(spec.process.args, vec!["/usr/bin/server", "--port", "7777"]);
+        assert_eq!(spec.process.user.uid, 1000);
+        assert_eq!(spec.process.user.gid, 100);
+        assert_eq!(spec.process.user.umask, Some(0o027));
+        assert!(spec.process.terminal);
+        assert_eq!(
+            spec.process.console_size,
+            Some(OciBox {
+                height: 30,
+                width: 100
+            })
+        );
+
+        let config2 = to_container_config(&spec);
+        assert_eq!(config2.args, config.args);
+        assert_eq!(config2.user, config.user);
+        assert_eq!(config2.terminal, config.terminal);
+        assert_eq!(config2.console_size, config.console_size);
+    }
+
+    #[test]
+    fn to_container_config_keeps_process_args() {
+        let mut spec = OciSpec::default();
+        spec.process.args = vec!["/bin/echo".to_string(), "hello".to_string()];
+        let config = to_container_config(&spec);
+        assert_eq!(config.args, vec!["/bin/echo", "hello"]);
+    }
+
     #[test]
     fn from_container_config_env() {
         let config = ContainerConfig::builder().env("FOO", "bar").build();
@@ -549,6 +882,120 @@ mod tests {
         assert!(config.env.iter().any(|(k, v)| k == "EMPTY" && v.is_empty()));
     }
 
+    #[test]
+    fn capabilities_roundtrip() {
+        let config = ContainerConfig::builder()
+            .cap_add(Capability::NetAdmin)
+            .cap_drop(Capability::Mknod)
+            .build();
+        let spec = from_container_config(&config);
+        let caps = spec.process.capabilities.as_ref().unwrap();
+        assert!(caps.bounding.iter().any(|c| c == "CAP_NET_ADMIN"));
+        assert!(!caps.effective.iter().any(|c| c == "CAP_MKNOD"));
+        assert!(caps.ambient.is_empty());
+        assert_eq!(to_container_config(&spec).capabilities, config.capabilities);
+
+        // 未指定ならデフォルト、未知の名前は無視
+        let mut spec = OciSpec::default();
+        assert_eq!(
+            to_container_config(&spec).capabilities,
+            Capabilities::default()
+        );
+        spec.process.capabilities = Some(OciCapabilities {
+            bounding: vec!["CAP_KILL".into(), "CAP_FLY".into()],
+            ..OciCapabilities::default()
+        });
+        let caps = to_container_config(&spec).capabilities;
+        assert_eq!(
+            caps.bounding.iter().collect::<Vec<_>>(),
+            vec![Capability::Kill]
+        );
+        assert!(caps.effective.is_empty());
+    }
+
+    #[test]
+    fn rlimits_roundtrip() {
+        let config = ContainerConfig::builder()
+            .rlimit(RlimitResource::Nofile, 65_536, 65_536)
+            .build();
+        let spec = from_container_config(&config);
+        assert!(spec
+            .process
+            .rlimits
+            .iter()
+            .any(|l| l.rlimit_type == "RLIMIT_NOFILE" && l.soft == 65_536));
+        assert_eq!(to_container_config(&spec).rlimits, config.rlimits);
+
+        // デフォルトはコアダンプ無効
+        assert_eq!(
+            to_container_config(&OciSpec::default()).rlimits,
+            ContainerConfig::default().rlimits
+        );
+    }
+
+    #[test]
+    fn hooks_roundtrip() {
+        use crate::hooks::HookPhase;
+        use std::time::Duration;
+
+        let config = ContainerConfig::builder()
+            .hook(
+                HookPhase::CreateRuntime,
+                Hook::new("/usr/libexec/net-setup")
+                    .args(["net-setup", "--bridge", "br0"])
+                    .env("MODE", "bridge")
+                    .timeout(Duration::from_millis(1500)),
+            )
+            .hook(HookPhase::Poststop, Hook::new("/usr/bin/deregister"))
+            .build();
+
+        let spec = from_container_config(&config);
+        assert_eq!(spec.hooks.create_runtime.len(), 1);
+        let hook = &spec.hooks.create_runtime[0];
+        assert_eq!(hook.path, "/usr/libexec/net-setup");
+        assert_eq!(hook.env, vec!["MODE=bridge"]);
+        assert_eq!(hook.timeout, Some(2));
+        assert_eq!(spec.hooks.poststop[0].timeout, None);
+
+        let hooks = to_container_config(&spec).hooks;
+        assert_eq!(
+            hooks.create_runtime[0].args,
+            vec!["net-setup", "--bridge", "br0"]
+        );
+        assert_eq!(
+            hooks.create_runtime[0].env,
+            vec![("MODE".into(), "bridge".into())]
+        );
+        assert_eq!(
+            hooks.create_runtime[0].timeout,
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(hooks.poststop, config.hooks.poststop);
+        assert!(hooks.prestart.is_empty());
+    }
+
+    #[test]
+    fn pids_and_cpuset_roundtrip() {
+        let config = ContainerConfig::builder()
+            .pids_max(128)
+            .cpuset("0-3", "0")
+            .build();
+        let spec = from_container_config(&config);
+        assert_eq!(spec.linux.resources.pids.limit, Some(128));
+        assert_eq!(spec.linux.resources.cpu.cpus.as_deref(), Some("0-3"));
+
+        let back = to_container_config(&spec);
+        assert_eq!(back.pids, config.pids);
+        assert_eq!(back.cpuset, config.cpuset);
+
+        let unlimited = from_container_config(&ContainerConfig::default());
+        assert!(unlimited.linux.resources.pids.limit.is_none());
+        let mut spec = unlimited;
+        spec.linux.resources.pids.limit = Some(-1);
+        assert_eq!(to_container_config(&spec).pids.max, u64::MAX);
+        assert!(to_container_config(&spec).cpuset.is_none());
+    }
+
     #[test]
     fn oci_cpu_resources_default() {
         let cpu = OciCpuResources::default();
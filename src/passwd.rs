@@ -0,0 +1,244 @@
+//! Account Database Lookup
+//!
+//! Resolves user and group names against the `/etc/passwd` and `/etc/group`
+//! files of a container root, the way `getpwnam(3)` and `getgrnam(3)` would
+//! inside the container. NSS modules other than `files` are not consulted.
+//!
+//! Both files are read with [`open_in_root`](crate::rootfs::open_in_root),
+//! so symlinks in an untrusted image resolve within the image.
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::container::ContainerError;
+
+/// Files larger than this are rejected rather than read into memory
+const MAX_FILE_SIZE: u64 = 4 << 20;
+
+// ============================================================================
+// Entries
+// ============================================================================
+
+/// One line of `/etc/passwd`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PasswdEntry {
+    pub(crate) name: String,
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+}
+
+/// One line of `/etc/group`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GroupEntry {
+    pub(crate) name: String,
+    pub(crate) gid: u32,
+    pub(crate) members: Vec<String>,
+}
+
+/// Parse `name:password:uid:gid:gecos:home:shell` lines
+///
+/// Comments, blank lines, NIS `+`/`-` entries and malformed lines are skipped.
+fn parse_passwd(content: &str) -> Vec<PasswdEntry> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with(['#', '+', '-']))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next().filter(|n| !n.is_empty())?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            let gid = fields.next()?.parse().ok()?;
+            Some(PasswdEntry {
+                name: name.to_string(),
+                uid,
+                gid,
+            })
+        })
+        .collect()
+}
+
+/// Parse `name:password:gid:member,member` lines
+fn parse_group(content: &str) -> Vec<GroupEntry> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with(['#', '+', '-']))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next().filter(|n| !n.is_empty())?;
+            let gid = fields.nth(1)?.parse().ok()?;
+            let members = fields
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string)
+                .collect();
+            Some(GroupEntry {
+                name: name.to_string(),
+                gid,
+                members,
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Database
+// ============================================================================
+
+/// Users and groups of a root filesystem
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AccountDb {
+    users: Vec<PasswdEntry>,
+    groups: Vec<GroupEntry>,
+}
+
+impl AccountDb {
+    /// Read `/etc/passwd` and `/etc/group` below `root`
+    ///
+    /// A missing file is treated as empty, as in minimal images.
+    pub(crate) fn load(root: &Path) -> Result<Self, ContainerError> {
+        Ok(Self::parse(
+            &read_in_root(root, "/etc/passwd")?,
+            &read_in_root(root, "/etc/group")?,
+        ))
+    }
+
+    /// Build the database from file contents
+    pub(crate) fn parse(passwd: &str, group: &str) -> Self {
+        Self {
+            users: parse_passwd(passwd),
+            groups: parse_group(group),
+        }
+    }
+
+    /// Look up a user by name, or by UID if `name` is numeric
+    pub(crate) fn user(&self, name: &str) -> Option<&PasswdEntry> {
+        self.users.iter().find(|u| u.name == name).or_else(|| {
+            let uid: u32 = name.parse().ok()?;
+            self.users.iter().find(|u| u.uid == uid)
+        })
+    }
+
+    /// Name of the first user with `uid`
+    pub(crate) fn user_name(&self, uid: u32) -> Option<&str> {
+        self.users
+            .iter()
+            .find(|u| u.uid == uid)
+            .map(|u| u.name.as_str())
+    }
+
+    /// Look up a group ID by name, accepting numeric IDs as-is
+    pub(crate) fn group_id(&self, name: &str) -> Option<u32> {
+        self.groups
+            .iter()
+            .find(|g| g.name == name)
+            .map(|g| g.gid)
+            .or_else(|| name.parse().ok())
+    }
+
+    /// IDs of the groups that list `user` as a member, in file order
+    pub(crate) fn member_gids(&self, user: &str) -> Vec<u32> {
+        self.groups
+            .iter()
+            .filter(|g| g.members.iter().any(|m| m == user))
+            .map(|g| g.gid)
+            .collect()
+    }
+}
+
+/// Read a file of the container root, or an empty string if it is missing
+fn read_in_root(root: &Path, path: &str) -> Result<String, ContainerError> {
+    let io_error =
+        |e: std::io::Error| ContainerError::IoError(format!("{}{path}: {e}", root.display()));
+
+    #[cfg(target_os = "linux")]
+    let file = crate::rootfs::open_in_root(root, Path::new(path));
+    #[cfg(not(target_os = "linux"))]
+    let file = std::fs::File::open(root.join(&path[1..]));
+
+    let file = match file {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let mut content = String::new();
+    file.take(MAX_FILE_SIZE + 1)
+        .read_to_string(&mut content)
+        .map_err(io_error)?;
+    if content.len() as u64 > MAX_FILE_SIZE {
+        return Err(ContainerError::ConfigError(format!(
+            "{}{path} exceeds {MAX_FILE_SIZE} bytes",
+            root.display()
+        )));
+    }
+    Ok(content)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "\
+root:x:0:0:root:/root:/bin/bash
+# service accounts
+www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin
+broken:x:notanumber:1::/:/bin/false
+app:x:1000:1000:App,,,:/home/app:/bin/sh
++nisuser::::::
+";
+
+    const GROUP: &str = "\
+root:x:0:
+adm:x:4:syslog,app
+www-data:x:33:
+docker:x:999:app, deploy
+";
+
+    #[test]
+    fn test_parse_skips_comments_and_malformed_lines() {
+        let db = AccountDb::parse(PASSWD, GROUP);
+        assert_eq!(db.users.len(), 3);
+        assert_eq!(db.groups.len(), 4);
+        assert!(db.user("broken").is_none());
+        assert!(db.user("+nisuser").is_none());
+    }
+
+    #[test]
+    fn test_user_lookup() {
+        let db = AccountDb::parse(PASSWD, GROUP);
+        let app = db.user("app").unwrap();
+        assert_eq!((app.uid, app.gid), (1000, 1000));
+        assert_eq!(db.user("33").map(|u| u.name.as_str()), Some("www-data"));
+        assert!(db.user("nobody").is_none());
+        assert_eq!(db.user_name(1000), Some("app"));
+        assert_eq!(db.user_name(4242), None);
+    }
+
+    #[test]
+    fn test_group_lookup_and_membership() {
+        let db = AccountDb::parse(PASSWD, GROUP);
+        assert_eq!(db.group_id("docker"), Some(999));
+        assert_eq!(db.group_id("1234"), Some(1234));
+        assert_eq!(db.group_id("wheel"), None);
+        assert_eq!(db.member_gids("app"), vec![4, 999]);
+        assert_eq!(db.member_gids("deploy"), vec![999]);
+        assert!(db.member_gids("root").is_empty());
+    }
+
+    #[test]
+    fn test_load_missing_files_is_empty() {
+        let dir =
+            std::env::temp_dir().join(format!("alice-container-passwd-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = AccountDb::load(&dir).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(db.user("root").is_none());
+        assert!(db.member_gids("root").is_empty());
+    }
+}
@@ -0,0 +1,552 @@
+//! Process File Descriptors
+//!
+//! Tracks the container init process through a pidfd (Linux 5.3+), so that
+//! waiting and signalling can never hit an unrelated process after PID reuse.
+//!
+//! ## Sources
+//!
+//! | Path | pidfd |
+//! |------|-------|
+//! | `clone3` | `CLONE_PIDFD` (returned atomically by the clone) |
+//! | legacy `clone(2)` | `pidfd_open(2)` right after the clone |
+//! | older kernels | none; falls back to `waitid(P_PID)` |
+//!
+//! The fallback is still safe for our own children: a PID cannot be reused
+//! until its zombie has been reaped, which only happens through this handle.
+//!
+//! ## Reattaching
+//!
+//! After a runtime restart the init process is no longer our child. It is
+//! identified by PID plus start time (`/proc/<pid>/stat` field 22), and
+//! watched through a pidfd that becomes readable when it exits. Its exit
+//! status is collected by its new parent, so it is reported as
+//! [`ExitStatus::Unknown`].
+
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use crate::container::{ContainerError, ExitStatus};
+
+/// `pidfd_open(2)` syscall number
+#[cfg(target_os = "linux")]
+const SYS_PIDFD_OPEN: libc::c_long = 434;
+
+/// `pidfd_send_signal(2)` syscall number
+#[cfg(target_os = "linux")]
+const SYS_PIDFD_SEND_SIGNAL: libc::c_long = 424;
+
+/// Handle to a container init process
+#[derive(Debug)]
+pub struct PidFd {
+    /// Process ID (in the runtime's PID namespace)
+    pid: u32,
+    /// pidfd, if the kernel supports it
+    fd: Option<OwnedFd>,
+    /// Whether the process is our child (and can be reaped)
+    child: bool,
+}
+
+impl PidFd {
+    /// Open a pidfd for a child process
+    ///
+    /// Falls back to PID-based tracking on kernels without `pidfd_open(2)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process does not exist.
+    #[cfg(target_os = "linux")]
+    pub fn open(pid: u32) -> Result<Self, ContainerError> {
+        // SAFETY: pidfd_open(2) takes a PID and flags and returns a new descriptor or -1.
+        let ret = unsafe { libc::syscall(SYS_PIDFD_OPEN, pid as libc::pid_t, 0) };
+        if ret >= 0 {
+            // SAFETY: the syscall succeeded, so ret is a new descriptor owned by us.
+            return Ok(unsafe { Self::from_raw(pid, ret as RawFd) });
+        }
+
+        match std::io::Error::last_os_error().raw_os_error() {
+            Some(libc::ENOSYS) => Ok(Self {
+                pid,
+                fd: None,
+                child: true,
+            }),
+            Some(libc::ESRCH) => Err(ContainerError::NotFound(format!("process {pid}"))),
+            errno => Err(ContainerError::ProcessError(format!(
+                "pidfd_open({pid}): errno {}",
+                errno.unwrap_or(0)
+            ))),
+        }
+    }
+
+    /// Open a pidfd (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn open(_pid: u32) -> Result<Self, ContainerError> {
+        Err(ContainerError::ProcessError("pidfd requires Linux".into()))
+    }
+
+    /// Take ownership of a pidfd returned by `clone3(CLONE_PIDFD)`
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open pidfd referring to `pid` and must not be owned
+    /// by anything else.
+    #[must_use]
+    pub unsafe fn from_raw(pid: u32, fd: RawFd) -> Self {
+        Self {
+            pid,
+            fd: Some(OwnedFd::from_raw_fd(fd)),
+            child: true,
+        }
+    }
+
+    /// Reattach to a process that is not our child
+    ///
+    /// The start time is checked after the pidfd has been opened, so the
+    /// handle is guaranteed to refer to the original process.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if the process has exited or the PID was reused,
+    /// and an error if the kernel lacks `pidfd_open(2)`.
+    #[cfg(target_os = "linux")]
+    pub fn attach(pid: u32, start_time: u64) -> Result<Self, ContainerError> {
+        // SAFETY: pidfd_open(2) takes a PID and flags and returns a new descriptor or -1.
+        let ret = unsafe { libc::syscall(SYS_PIDFD_OPEN, pid as libc::pid_t, 0) };
+        if ret < 0 {
+            return match std::io::Error::last_os_error().raw_os_error() {
+                Some(libc::ESRCH) => Err(ContainerError::NotFound(format!("process {pid}"))),
+                errno => Err(ContainerError::ProcessError(format!(
+                    "pidfd_open({pid}): errno {}",
+                    errno.unwrap_or(0)
+                ))),
+            };
+        }
+        // SAFETY: the syscall succeeded, so ret is a new descriptor owned by us.
+        let fd = unsafe { OwnedFd::from_raw_fd(ret as RawFd) };
+
+        if process_start_time(pid).ok() != Some(start_time) {
+            return Err(ContainerError::NotFound(format!("process {pid}")));
+        }
+
+        Ok(Self {
+            pid,
+            fd: Some(fd),
+            child: false,
+        })
+    }
+
+    /// Reattach to a process (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn attach(_pid: u32, _start_time: u64) -> Result<Self, ContainerError> {
+        Err(ContainerError::ProcessError("pidfd requires Linux".into()))
+    }
+
+    /// Whether the process is our child
+    #[must_use]
+    pub const fn is_child(&self) -> bool {
+        self.child
+    }
+
+    /// Process ID
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Duplicate the handle, e.g. for a monitor polling the same process
+    ///
+    /// Returns `None` if no pidfd is held or it cannot be duplicated.
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        Some(Self {
+            pid: self.pid,
+            fd: Some(self.fd.as_ref()?.try_clone().ok()?),
+            child: self.child,
+        })
+    }
+
+    /// Raw pidfd, if one is held
+    #[must_use]
+    pub fn as_raw_fd(&self) -> Option<RawFd> {
+        self.fd.as_ref().map(AsRawFd::as_raw_fd)
+    }
+
+    /// Send a signal to the process
+    ///
+    /// Uses `pidfd_send_signal(2)` when a pidfd is held, `kill(2)` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal is invalid or the process is gone.
+    #[cfg(target_os = "linux")]
+    pub fn send_signal(&self, signal: i32) -> Result<(), ContainerError> {
+        let ret = match self.as_raw_fd() {
+            // SAFETY: fd is an open pidfd; a NULL siginfo makes the kernel fill in the
+            // default SI_USER info, and flags must be 0.
+            Some(fd) => unsafe {
+                libc::syscall(
+                    SYS_PIDFD_SEND_SIGNAL,
+                    fd,
+                    signal,
+                    core::ptr::null::<libc::siginfo_t>(),
+                    0,
+                )
+            },
+            // SAFETY: the PID is our unreaped child, so it cannot have been reused.
+            None => libc::c_long::from(unsafe { libc::kill(self.pid as libc::pid_t, signal) }),
+        };
+        if ret == 0 {
+            return Ok(());
+        }
+
+        match std::io::Error::last_os_error().raw_os_error() {
+            Some(libc::ESRCH) => Err(ContainerError::NotFound(format!("process {}", self.pid))),
+            Some(libc::EINVAL) => Err(ContainerError::ConfigError(format!(
+                "Invalid signal {signal}"
+            ))),
+            errno => Err(ContainerError::ProcessError(format!(
+                "signal {signal} to {}: errno {}",
+                self.pid,
+                errno.unwrap_or(0)
+            ))),
+        }
+    }
+
+    /// Send a signal (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn send_signal(&self, _signal: i32) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError("pidfd requires Linux".into()))
+    }
+
+    /// Reap the process if it has exited, without blocking
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not a child or was already reaped.
+    pub fn try_wait(&self) -> Result<Option<ExitStatus>, ContainerError> {
+        self.waitid(libc::WEXITED | libc::WNOHANG)
+    }
+
+    /// Block until the process exits and reap it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not a child or was already reaped.
+    pub fn wait(&self) -> Result<ExitStatus, ContainerError> {
+        self.waitid(libc::WEXITED)?
+            .ok_or_else(|| ContainerError::ProcessError(format!("waitid({}): no status", self.pid)))
+    }
+
+    /// Wait up to `timeout` for the process to exit and reap it
+    ///
+    /// Returns `None` if the process is still running after `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not a child or was already reaped.
+    #[cfg(target_os = "linux")]
+    pub fn wait_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<Option<ExitStatus>, ContainerError> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            if left.is_zero() {
+                return Ok(None);
+            }
+
+            if self.fd.is_some() {
+                // Readable once the process has exited
+                let ms = left.as_millis().clamp(1, libc::c_int::MAX as u128);
+                self.poll_exit(ms as libc::c_int)?;
+            } else {
+                std::thread::sleep(left.min(std::time::Duration::from_millis(10)));
+            }
+        }
+    }
+
+    /// Wait with a timeout (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error on non-Linux platforms.
+    #[cfg(not(target_os = "linux"))]
+    pub fn wait_timeout(
+        &self,
+        _timeout: std::time::Duration,
+    ) -> Result<Option<ExitStatus>, ContainerError> {
+        Err(ContainerError::ProcessError("pidfd requires Linux".into()))
+    }
+
+    /// Check whether the process has exited, leaving it reapable
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process is not a child or was already reaped.
+    pub fn peek_exit(&self) -> Result<Option<ExitStatus>, ContainerError> {
+        self.waitid(libc::WEXITED | libc::WNOHANG | libc::WNOWAIT)
+    }
+
+    /// `waitid(2)` on the pidfd, or on the PID as a fallback
+    #[cfg(target_os = "linux")]
+    fn waitid(&self, options: libc::c_int) -> Result<Option<ExitStatus>, ContainerError> {
+        if !self.child {
+            let timeout = if options & libc::WNOHANG != 0 { 0 } else { -1 };
+            return self.poll_exit(timeout);
+        }
+
+        let (idtype, id) = match self.as_raw_fd() {
+            Some(fd) => (libc::P_PIDFD, fd as libc::id_t),
+            None => (libc::P_PID, self.pid as libc::id_t),
+        };
+
+        loop {
+            // SAFETY: siginfo_t is a plain C struct for which all-zero is a valid value.
+            let mut info: libc::siginfo_t = unsafe { core::mem::zeroed() };
+            // SAFETY: info is a valid out-pointer; __WALL also matches children created with
+            // a non-SIGCHLD exit signal.
+            let ret = unsafe { libc::waitid(idtype, id, &mut info, options | libc::__WALL) };
+            if ret < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(ContainerError::ProcessError(format!(
+                    "waitid({}): {err}",
+                    self.pid
+                )));
+            }
+
+            // SAFETY: waitid(2) succeeded and filled in the SIGCHLD fields (or left them zero
+            // with WNOHANG when no child has changed state).
+            let (pid, status) = unsafe { (info.si_pid(), info.si_status()) };
+            if pid == 0 {
+                return Ok(None);
+            }
+            return Ok(Some(if info.si_code == libc::CLD_EXITED {
+                ExitStatus::Exited(status)
+            } else {
+                ExitStatus::Signaled(status)
+            }));
+        }
+    }
+
+    /// Wait for the pidfd of a non-child to become readable
+    #[cfg(target_os = "linux")]
+    fn poll_exit(&self, timeout_ms: libc::c_int) -> Result<Option<ExitStatus>, ContainerError> {
+        let Some(fd) = self.as_raw_fd() else {
+            return Err(ContainerError::ProcessError(format!(
+                "process {} is not a child and has no pidfd",
+                self.pid
+            )));
+        };
+
+        let mut pfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            // SAFETY: pfd is a single valid pollfd for an open pidfd.
+            let ret = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+            if ret >= 0 {
+                return Ok((ret > 0).then_some(ExitStatus::Unknown));
+            }
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                return Err(ContainerError::ProcessError(format!(
+                    "poll pidfd {}: {err}",
+                    self.pid
+                )));
+            }
+        }
+    }
+
+    /// `waitid(2)` (non-Linux stub)
+    #[cfg(not(target_os = "linux"))]
+    #[allow(clippy::unused_self)]
+    fn waitid(&self, _options: libc::c_int) -> Result<Option<ExitStatus>, ContainerError> {
+        Err(ContainerError::ProcessError("pidfd requires Linux".into()))
+    }
+}
+
+/// Start time of a process in clock ticks since boot
+///
+/// Together with the PID this identifies a process across PID reuse.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the process does not exist.
+pub fn process_start_time(pid: u32) -> Result<u64, ContainerError> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .map_err(|_| ContainerError::NotFound(format!("process {pid}")))?;
+    parse_start_time(&stat)
+        .ok_or_else(|| ContainerError::ProcessError(format!("malformed /proc/{pid}/stat")))
+}
+
+/// Extract field 22 (`starttime`) from `/proc/<pid>/stat`
+///
+/// The command name (field 2) may contain spaces and parentheses, so fields
+/// are counted from the last `)`.
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    // Field 3 (state) is the first after the command name
+    rest.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    // The child is reaped through the returned handle
+    #[allow(clippy::zombie_processes)]
+    fn spawn(script: &str) -> PidFd {
+        let child = std::process::Command::new("/bin/sh")
+            .args(["-c", script])
+            .spawn()
+            .expect("spawn sh");
+        PidFd::open(child.id()).expect("pidfd_open")
+    }
+
+    #[test]
+    fn test_wait_exit_code() {
+        let pidfd = spawn("exit 3");
+        assert_eq!(pidfd.wait().unwrap(), ExitStatus::Exited(3));
+    }
+
+    #[test]
+    fn test_wait_signal() {
+        let pidfd = spawn("kill -TERM $$");
+        assert_eq!(pidfd.wait().unwrap(), ExitStatus::Signaled(libc::SIGTERM));
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let pidfd = spawn("sleep 10");
+        assert_eq!(
+            pidfd
+                .wait_timeout(std::time::Duration::from_millis(20))
+                .unwrap(),
+            None
+        );
+
+        pidfd.send_signal(libc::SIGKILL).unwrap();
+        assert_eq!(
+            pidfd
+                .wait_timeout(std::time::Duration::from_secs(5))
+                .unwrap(),
+            Some(ExitStatus::Signaled(libc::SIGKILL))
+        );
+
+        let quick = spawn("exit 4");
+        assert_eq!(
+            quick
+                .wait_timeout(std::time::Duration::from_secs(5))
+                .unwrap(),
+            Some(ExitStatus::Exited(4))
+        );
+    }
+
+    #[test]
+    fn test_try_wait_running_then_exited() {
+        let pidfd = spawn("sleep 10");
+        assert_eq!(pidfd.try_wait().unwrap(), None);
+        assert_eq!(pidfd.peek_exit().unwrap(), None);
+
+        // SAFETY: the PID belongs to our unreaped child.
+        unsafe {
+            libc::kill(pidfd.pid() as libc::pid_t, libc::SIGKILL);
+        }
+
+        // peek leaves the zombie in place for the real wait
+        while pidfd.peek_exit().unwrap().is_none() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(
+            pidfd.peek_exit().unwrap(),
+            Some(ExitStatus::Signaled(libc::SIGKILL))
+        );
+        assert_eq!(
+            pidfd.try_wait().unwrap(),
+            Some(ExitStatus::Signaled(libc::SIGKILL))
+        );
+        assert!(pidfd.try_wait().is_err());
+    }
+
+    #[test]
+    fn test_send_signal() {
+        let pidfd = spawn("sleep 10");
+        pidfd.send_signal(libc::SIGTERM).unwrap();
+        assert_eq!(pidfd.wait().unwrap(), ExitStatus::Signaled(libc::SIGTERM));
+    }
+
+    #[test]
+    fn test_send_invalid_signal() {
+        let pidfd = spawn("sleep 10");
+        assert!(matches!(
+            pidfd.send_signal(1000),
+            Err(ContainerError::ConfigError(_))
+        ));
+        pidfd.send_signal(libc::SIGKILL).unwrap();
+        pidfd.wait().unwrap();
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        let stat = "1234 (a (weird) name) S 1 1234 1234 0 -1 4194560 100 0 0 0 \
+                    1 2 0 0 20 0 1 0 987654 1000000 200";
+        assert_eq!(parse_start_time(stat), Some(987_654));
+        assert_eq!(parse_start_time("1 (x) S 1"), None);
+    }
+
+    #[test]
+    fn test_attach_checks_start_time() {
+        let pid = std::process::id();
+        let start = process_start_time(pid).unwrap();
+
+        let attached = PidFd::attach(pid, start).unwrap();
+        assert!(!attached.is_child());
+        assert_eq!(attached.try_wait().unwrap(), None);
+
+        assert!(matches!(
+            PidFd::attach(pid, start + 1),
+            Err(ContainerError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_attached_process_reports_unknown_exit() {
+        let child = spawn("sleep 10");
+        let start = process_start_time(child.pid()).unwrap();
+        let attached = PidFd::attach(child.pid(), start).unwrap();
+
+        child.send_signal(libc::SIGKILL).unwrap();
+        assert_eq!(attached.wait().unwrap(), ExitStatus::Unknown);
+        assert_eq!(child.wait().unwrap(), ExitStatus::Signaled(libc::SIGKILL));
+    }
+
+    #[test]
+    fn test_open_missing_process() {
+        assert!(matches!(
+            PidFd::open(i32::MAX as u32),
+            Err(ContainerError::NotFound(_))
+        ));
+    }
+}
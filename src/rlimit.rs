@@ -0,0 +1,287 @@
+//! Process Resource Limits
+//!
+//! `setrlimit(2)` limits of the container init process and of commands run
+//! with `Container::exec()`. They are applied with `prlimit(2)` right
+//! before the capability sets, while `CAP_SYS_RESOURCE` still allows
+//! raising hard limits.
+//!
+//! By default only `RLIMIT_CORE` is set, to 0, so crashing workloads do not
+//! write core dumps; every other limit is inherited from the runtime.
+
+use core::fmt;
+
+use crate::container::ContainerError;
+
+/// Value of an unlimited soft or hard limit
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+// ============================================================================
+// Resource
+// ============================================================================
+
+macro_rules! resources {
+    ($($(#[$doc:meta])* $variant:ident = $value:literal, $name:literal;)*) => {
+        /// A resource limited by `setrlimit(2)`
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        pub enum RlimitResource {
+            $($(#[$doc])* $variant = $value,)*
+        }
+
+        impl RlimitResource {
+            /// Every resource, by number
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// Name as used by OCI and `getrlimit(2)`, e.g. `RLIMIT_NOFILE`
+            #[must_use]
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+resources! {
+    /// CPU time in seconds
+    Cpu = 0, "RLIMIT_CPU";
+    /// Size of files the process may create, in bytes
+    Fsize = 1, "RLIMIT_FSIZE";
+    /// Size of the data segment, in bytes
+    Data = 2, "RLIMIT_DATA";
+    /// Size of the main thread stack, in bytes
+    Stack = 3, "RLIMIT_STACK";
+    /// Size of core dumps, in bytes
+    Core = 4, "RLIMIT_CORE";
+    /// Resident set size (no effect on current kernels)
+    Rss = 5, "RLIMIT_RSS";
+    /// Processes of the real user ID
+    Nproc = 6, "RLIMIT_NPROC";
+    /// Open file descriptors
+    Nofile = 7, "RLIMIT_NOFILE";
+    /// Locked memory, in bytes
+    Memlock = 8, "RLIMIT_MEMLOCK";
+    /// Virtual address space, in bytes
+    As = 9, "RLIMIT_AS";
+    /// File locks (no effect on current kernels)
+    Locks = 10, "RLIMIT_LOCKS";
+    /// Queued signals of the real user ID
+    Sigpending = 11, "RLIMIT_SIGPENDING";
+    /// POSIX message queue memory, in bytes
+    Msgqueue = 12, "RLIMIT_MSGQUEUE";
+    /// Ceiling of the nice value, as `20 - nice`
+    Nice = 13, "RLIMIT_NICE";
+    /// Ceiling of the real-time priority
+    Rtprio = 14, "RLIMIT_RTPRIO";
+    /// Real-time CPU time without blocking, in microseconds
+    Rttime = 15, "RLIMIT_RTTIME";
+}
+
+impl RlimitResource {
+    /// Look up a resource by name
+    ///
+    /// Accepts `RLIMIT_NOFILE` as well as `NOFILE` or `nofile`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .get(..7)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RLIMIT_"))
+            .map_or(name, |_| &name[7..]);
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|r| r.name()[7..].eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for RlimitResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// ============================================================================
+// Limit
+// ============================================================================
+
+/// Soft and hard limit of one resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rlimit {
+    /// Limited resource
+    pub resource: RlimitResource,
+    /// Limit enforced by the kernel
+    pub soft: u64,
+    /// Ceiling for the soft limit
+    pub hard: u64,
+}
+
+impl Rlimit {
+    /// Limit `resource` to `soft`, raisable up to `hard`
+    #[must_use]
+    pub const fn new(resource: RlimitResource, soft: u64, hard: u64) -> Self {
+        Self {
+            resource,
+            soft,
+            hard,
+        }
+    }
+
+    /// Apply the limit to the calling process
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `prlimit(2)` fails, e.g. when raising the hard
+    /// limit without `CAP_SYS_RESOURCE` or above `fs.nr_open`.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self) -> Result<(), ContainerError> {
+        let limit = libc::rlimit64 {
+            rlim_cur: self.soft,
+            rlim_max: self.hard,
+        };
+        // SAFETY: limit is a valid rlimit64 for the duration of the call; a NULL old limit
+        // is allowed. pid 0 is the calling process.
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_prlimit64,
+                0,
+                libc::c_int::from(self.resource as u8),
+                &limit,
+                core::ptr::null_mut::<libc::rlimit64>(),
+            )
+        };
+        if ret < 0 {
+            return Err(ContainerError::ProcessError(format!(
+                "prlimit({}): errno {}",
+                self.resource,
+                std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply the limit (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Always returns an error.
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self) -> Result<(), ContainerError> {
+        Err(ContainerError::ProcessError(
+            "Container runtime requires Linux".into(),
+        ))
+    }
+}
+
+/// Check that every soft limit is within its hard limit and that no
+/// resource is limited twice
+///
+/// # Errors
+///
+/// Returns `ConfigError` naming the offending resource.
+pub fn validate(rlimits: &[Rlimit]) -> Result<(), ContainerError> {
+    for (i, limit) in rlimits.iter().enumerate() {
+        if limit.soft > limit.hard {
+            return Err(ContainerError::ConfigError(format!(
+                "{}: soft limit {} exceeds hard limit {}",
+                limit.resource, limit.soft, limit.hard
+            )));
+        }
+        if rlimits[..i].iter().any(|l| l.resource == limit.resource) {
+            return Err(ContainerError::ConfigError(format!(
+                "{} is limited more than once",
+                limit.resource
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Apply `rlimits` in order
+#[cfg(target_os = "linux")]
+pub(crate) fn apply_all(rlimits: &[Rlimit]) -> Result<(), ContainerError> {
+    rlimits.iter().try_for_each(Rlimit::apply)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_names() {
+        for (number, resource) in RlimitResource::ALL.iter().enumerate() {
+            assert_eq!(usize::from(*resource as u8), number);
+            assert_eq!(RlimitResource::from_name(resource.name()), Some(*resource));
+        }
+        assert_eq!(
+            RlimitResource::from_name("nofile"),
+            Some(RlimitResource::Nofile)
+        );
+        assert_eq!(RlimitResource::from_name("RLIMIT_FOO"), None);
+        assert_eq!(RlimitResource::Core.to_string(), "RLIMIT_CORE");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resource_numbers_match_libc() {
+        assert_eq!(
+            i64::from(RlimitResource::Nofile as u8),
+            i64::from(libc::RLIMIT_NOFILE)
+        );
+        assert_eq!(
+            i64::from(RlimitResource::Nproc as u8),
+            i64::from(libc::RLIMIT_NPROC)
+        );
+        assert_eq!(
+            i64::from(RlimitResource::Core as u8),
+            i64::from(libc::RLIMIT_CORE)
+        );
+        assert_eq!(
+            i64::from(RlimitResource::Rttime as u8),
+            i64::from(libc::RLIMIT_RTTIME)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let nofile = Rlimit::new(RlimitResource::Nofile, 1024, 65_536);
+        assert!(validate(&[nofile, Rlimit::new(RlimitResource::Core, 0, 0)]).is_ok());
+        assert!(validate(&[Rlimit::new(
+            RlimitResource::Stack,
+            RLIM_INFINITY,
+            RLIM_INFINITY
+        )])
+        .is_ok());
+
+        let err = validate(&[Rlimit::new(RlimitResource::Nofile, 10, 5)]).unwrap_err();
+        assert!(err.to_string().contains("RLIMIT_NOFILE"));
+        assert!(matches!(
+            validate(&[nofile, nofile]),
+            Err(ContainerError::ConfigError(_))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_apply_before_exec() {
+        use std::os::unix::process::CommandExt;
+
+        let limits = [
+            Rlimit::new(RlimitResource::Core, 0, 0),
+            Rlimit::new(RlimitResource::Nofile, 100, 200),
+        ];
+        let mut command = std::process::Command::new("/bin/sh");
+        command.args(["-c", "ulimit -c; ulimit -Sn; ulimit -Hn"]);
+        // SAFETY: the closure only calls prlimit(2) before exec.
+        unsafe {
+            command
+                .pre_exec(move || apply_all(&limits).map_err(|_| std::io::Error::last_os_error()));
+        }
+        let output = command.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n100\n200\n");
+    }
+}
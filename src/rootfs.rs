@@ -26,9 +26,6 @@
 //! └── .old_root/     (for pivot_root)
 //! ```
 
-#[cfg(target_os = "linux")]
-use core::ffi::c_int;
-
 #[cfg(feature = "std")]
 use std::{
     fs::{self, File},
@@ -39,6 +36,9 @@ use std::{
 #[cfg(all(feature = "std", unix))]
 use std::os::unix::fs::PermissionsExt;
 
+#[cfg(feature = "std")]
+use crate::namespace::IdMapping;
+
 // ============================================================================
 // Mount Flags (Linux values, defined as constants for cross-compilation)
 // ============================================================================
@@ -501,6 +501,87 @@ impl RootFs {
     pub const fn cleanup_old_root() -> Result<(), RootFsError> {
         Err(RootFsError::NotSupported)
     }
+
+    /// Build a root filesystem at `path` from a tar archive
+    ///
+    /// The counterpart of `Container::export()`. `path` must not exist or
+    /// be an empty directory. If extraction fails, a directory created here
+    /// is removed again and an existing one is emptied.
+    /// Ownership, permissions, modification times, extended attributes,
+    /// links, device nodes and FIFOs are restored.
+    ///
+    /// Archive IDs are those seen inside the container. With the user
+    /// namespace mappings of the container that will use the root
+    /// (`ContainerConfig::id_mappings()`), files are owned by the mapped
+    /// host IDs; with `None` the IDs are written unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` is not empty, the archive is malformed or
+    /// a file cannot be created.
+    #[cfg(target_os = "linux")]
+    pub fn import(
+        path: impl Into<PathBuf>,
+        archive: impl std::io::Read,
+        id_mappings: Option<(IdMapping, IdMapping)>,
+    ) -> Result<Self, RootFsError> {
+        use crate::archive::{unpack, Owners, TarReader};
+
+        let path = path.into();
+        let created = match fs::read_dir(&path) {
+            Ok(mut entries) => {
+                if entries.next().is_some() {
+                    return Err(RootFsError::IoError(format!(
+                        "{} is not empty",
+                        path.display()
+                    )));
+                }
+                false
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                fs::create_dir_all(&path)?;
+                true
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let owners = id_mappings.map_or_else(Owners::identity, |(uid, gid)| {
+            Owners::from_mappings(uid, gid)
+        });
+        let unpacked =
+            File::open(&path).and_then(|dir| unpack(&mut TarReader::new(archive), &dir, &owners));
+        if let Err(e) = unpacked {
+            // The directory itself belongs to the caller unless created above
+            let _ = if created {
+                fs::remove_dir_all(&path)
+            } else {
+                empty_dir(&path)
+            };
+            return Err(RootFsError::IoError(format!(
+                "import into {}: {e}",
+                path.display()
+            )));
+        }
+
+        Ok(Self {
+            path,
+            cleanup: false,
+        })
+    }
+
+    /// Import a root filesystem (non-Linux stub)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation fails.
+    #[cfg(not(target_os = "linux"))]
+    pub fn import(
+        _path: impl Into<PathBuf>,
+        _archive: impl std::io::Read,
+        _id_mappings: Option<(IdMapping, IdMapping)>,
+    ) -> Result<Self, RootFsError> {
+        Err(RootFsError::NotSupported)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -708,6 +789,111 @@ fn create_device_node(
     Ok(())
 }
 
+/// Remove everything inside the directory `path`, but not `path` itself
+#[cfg(target_os = "linux")]
+fn empty_dir(path: &Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Path Resolution
+// ============================================================================
+
+/// Open `path` for reading as if `root` were `/`
+///
+/// Uses `openat2(2)` with `RESOLVE_IN_ROOT`, so absolute symlinks and `..`
+/// inside an untrusted root filesystem cannot escape it, and
+/// `RESOLVE_NO_MAGICLINKS`, so neither can `/proc/<pid>/fd` links.
+///
+/// # Errors
+///
+/// Returns the error of the underlying open call, or `Unsupported` on
+/// kernels without `openat2(2)` (before 5.6), where the path cannot be
+/// resolved safely.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub fn open_in_root(root: &Path, path: &Path) -> std::io::Result<File> {
+    resolve_in_root(root, path, libc::O_RDONLY)
+}
+
+/// Open the directory `path` as if `root` were `/`, without read access
+///
+/// Resolved like [`open_in_root`]. The returned `O_PATH` descriptor can be
+/// used as a base through `/proc/self/fd/<fd>`.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub(crate) fn open_dir_in_root(root: &Path, path: &Path) -> std::io::Result<File> {
+    resolve_in_root(root, path, libc::O_PATH | libc::O_DIRECTORY)
+}
+
+/// Open an `O_PATH` descriptor for `path` as if `root` were `/`
+///
+/// Resolved like [`open_in_root`]. Opens any kind of file without reading
+/// it, so FIFOs and devices do not block or see an open.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub(crate) fn open_path_in_root(root: &Path, path: &Path) -> std::io::Result<File> {
+    resolve_in_root(root, path, libc::O_PATH)
+}
+
+/// Open `path` below `root` with `flags`, resolving it as if `root` were `/`
+#[cfg(all(feature = "std", target_os = "linux"))]
+fn resolve_in_root(root: &Path, path: &Path, flags: libc::c_int) -> std::io::Result<File> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    let dir = File::open(root)?;
+    let relative = path.strip_prefix("/").unwrap_or(path);
+    let relative = if relative.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        relative
+    };
+    let path_c = CString::new(relative.as_os_str().as_bytes())
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+
+    // SAFETY: open_how is a plain C struct for which all-zero is a valid value.
+    let mut how: libc::open_how = unsafe { core::mem::zeroed() };
+    how.flags = (flags | libc::O_CLOEXEC) as u64;
+    how.resolve = libc::RESOLVE_IN_ROOT | libc::RESOLVE_NO_MAGICLINKS;
+    // SAFETY: dir is an open directory descriptor, path_c is a valid NUL-terminated string
+    // and how is an initialized open_how whose size is passed along.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            dir.as_raw_fd(),
+            path_c.as_ptr(),
+            &how,
+            core::mem::size_of::<libc::open_how>(),
+        )
+    };
+    if fd >= 0 {
+        // SAFETY: openat2(2) returned a new descriptor owned by nobody else.
+        return Ok(unsafe { File::from_raw_fd(fd as i32) });
+    }
+
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::ENOSYS) {
+        // Opening root.join(relative) instead would let symlinks escape the root
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "cannot resolve {} in {}: openat2(2) is not available",
+                path.display(),
+                root.display()
+            ),
+        ))
+    } else {
+        Err(err)
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -976,4 +1162,185 @@ mod tests {
         assert!(link.exists() || std::fs::symlink_metadata(&link).is_ok());
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_in_root_stays_inside() {
+        use std::io::Read;
+
+        let dir = std::env::temp_dir().join(format!(
+            "alice-container-in-root-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos()
+        ));
+        let rootfs = RootFs::create(&dir).unwrap();
+        rootfs.set_hostname("inside").unwrap();
+        // Resolves to the container's own /etc/hostname, not the host's
+        rootfs.symlink("/etc/hostname", "name").unwrap();
+        rootfs
+            .symlink("../../../../../../etc/hostname", "up")
+            .unwrap();
+
+        for path in ["/etc/hostname", "name", "/up"] {
+            let mut content = String::new();
+            open_in_root(&dir, Path::new(path))
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content.trim(), "inside", "{path}");
+        }
+        assert!(open_in_root(&dir, Path::new("/etc/missing")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_import_round_trip() {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        use crate::archive::{Owners, Packer, TarWriter};
+
+        let base =
+            std::env::temp_dir().join(format!("alice-container-import-{}", std::process::id()));
+        let source = base.join("source");
+        fs::create_dir_all(source.join("usr/bin")).unwrap();
+        fs::write(source.join("usr/bin/game"), "binary").unwrap();
+        fs::set_permissions(
+            source.join("usr/bin/game"),
+            fs::Permissions::from_mode(0o4755),
+        )
+        .unwrap();
+        fs::hard_link(
+            source.join("usr/bin/game"),
+            source.join("usr/bin/game-server"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("usr/bin", source.join("bin")).unwrap();
+        let fifo = std::ffi::CString::new(source.join("ctl").into_os_string().into_encoded_bytes())
+            .unwrap();
+        // SAFETY: fifo is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+        fs::set_permissions(source.join("ctl"), fs::Permissions::from_mode(0o620)).unwrap();
+        let attr = c"user.alice";
+        let game = std::ffi::CString::new(
+            source
+                .join("usr/bin/game")
+                .into_os_string()
+                .into_encoded_bytes(),
+        )
+        .unwrap();
+        // Not every filesystem supports user xattrs
+        // SAFETY: both strings are NUL-terminated and the value is 4 bytes long.
+        let has_xattr =
+            unsafe { libc::setxattr(game.as_ptr(), attr.as_ptr(), b"tune".as_ptr().cast(), 4, 0) }
+                == 0;
+        // Changing the owner needs root; the comparison below holds either way
+        let _ = std::os::unix::fs::chown(&source, Some(4242), Some(4243));
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o750)).unwrap();
+        let source_root = fs::metadata(&source).unwrap();
+
+        let owners = Owners::identity();
+        let mut tar = TarWriter::new(Vec::new());
+        Packer::new(&owners)
+            .pack(
+                &mut tar,
+                &File::open(&source).unwrap(),
+                std::ffi::OsStr::new("."),
+                Path::new("."),
+            )
+            .unwrap();
+        let archive = tar.finish().unwrap();
+
+        let target = base.join("target");
+        let imported = RootFs::import(&target, &archive[..], None).map(|r| r.path().to_path_buf());
+        let not_empty = RootFs::import(&source, &archive[..], None);
+        let game = fs::metadata(target.join("usr/bin/game"));
+        let server = fs::metadata(target.join("usr/bin/game-server"));
+        let link = fs::read_link(target.join("bin"));
+        let ctl = fs::symlink_metadata(target.join("ctl"));
+        let root = fs::metadata(&target);
+        let mut value = [0u8; 16];
+        let target_game = std::ffi::CString::new(
+            target
+                .join("usr/bin/game")
+                .into_os_string()
+                .into_encoded_bytes(),
+        )
+        .unwrap();
+        // SAFETY: both strings are NUL-terminated and value is a writable 16-byte buffer.
+        let len = unsafe {
+            libc::getxattr(
+                target_game.as_ptr(),
+                attr.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        let _ = fs::remove_dir_all(&base);
+
+        assert_eq!(imported.unwrap(), target);
+        assert!(not_empty.is_err());
+        let (game, server) = (game.unwrap(), server.unwrap());
+        assert_eq!(game.mode() & 0o7777, 0o4755);
+        assert_eq!(game.ino(), server.ino());
+        assert_eq!(link.unwrap(), Path::new("usr/bin"));
+        let ctl = ctl.unwrap();
+        assert!(ctl.file_type().is_fifo());
+        assert_eq!(ctl.mode() & 0o7777, 0o620);
+        let root = root.unwrap();
+        assert_eq!(root.mode() & 0o7777, 0o750);
+        assert_eq!(
+            (root.uid(), root.gid()),
+            (source_root.uid(), source_root.gid())
+        );
+        assert_eq!(root.mtime(), source_root.mtime());
+        if has_xattr {
+            assert_eq!(&value[..len as usize], b"tune");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_failed_import_keeps_existing_directory() {
+        use crate::archive::{Owners, Packer, TarWriter};
+
+        let base = std::env::temp_dir().join(format!(
+            "alice-container-import-failed-{}",
+            std::process::id()
+        ));
+        let source = base.join("source");
+        fs::create_dir_all(source.join("etc")).unwrap();
+        fs::write(source.join("etc/hostname"), "game").unwrap();
+
+        let owners = Owners::identity();
+        let mut tar = TarWriter::new(Vec::new());
+        Packer::new(&owners)
+            .pack(
+                &mut tar,
+                &File::open(&source).unwrap(),
+                std::ffi::OsStr::new("."),
+                Path::new("."),
+            )
+            .unwrap();
+        // A corrupt header after the first entries, in place of the end marker
+        let mut archive = tar.finish().unwrap();
+        let end = archive.len() - 1024;
+        archive[end..].fill(b'x');
+
+        let existing = base.join("existing");
+        fs::create_dir(&existing).unwrap();
+        let into_existing = RootFs::import(&existing, &archive[..], None);
+        let existing_entries = fs::read_dir(&existing).map(Iterator::count);
+        let created = base.join("created");
+        let into_created = RootFs::import(&created, &archive[..], None);
+        let created_exists = created.exists();
+        let _ = fs::remove_dir_all(&base);
+
+        assert!(into_existing.is_err());
+        assert_eq!(existing_entries.unwrap(), 0);
+        assert!(into_created.is_err());
+        assert!(!created_exists);
+    }
 }
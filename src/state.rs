@@ -0,0 +1,1042 @@
+//! Persisted Container State
+//!
+//! Every lifecycle transition of a [`Container`](crate::container::Container)
+//! is written to `<state_root>/<id>/state.json`, so that a restarted
+//! supervisor can rebuild its handles with `Container::load()`.
+//!
+//! ## Layout
+//!
+//! ```text
+//! /run/alice-container/
+//! └── <id>/
+//!     └── state.json
+//! ```
+//!
+//! Writes go to a temporary file that is renamed over `state.json`, so a
+//! reader never observes a partially written record.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::capability::{Capabilities, CapabilitySet};
+use crate::cgroup::{CpuConfig, CpusetConfig, IoConfig, MemoryConfig, PidsConfig};
+use crate::console::WindowSize;
+use crate::container::{
+    ContainerConfig, ContainerError, ContainerState, ExitStatus, ProcessUser, RestartPolicy,
+};
+use crate::health::{HealthCheck, HealthProbe};
+use crate::hooks::{Hook, HookPhase, Hooks};
+use crate::json::Value;
+use crate::logs::LogConfig;
+use crate::namespace::{IdMapping, NamespaceFlags};
+use crate::network::NetworkConfig;
+use crate::rlimit::{Rlimit, RlimitResource};
+
+/// State file format version
+const STATE_VERSION: u64 = 1;
+
+/// File name of the state record inside a container's state directory
+const STATE_FILE: &str = "state.json";
+
+// ============================================================================
+// State Record
+// ============================================================================
+
+/// Snapshot of a container as persisted on disk
+#[derive(Debug, Clone)]
+pub struct StateRecord {
+    /// Container ID
+    pub id: String,
+    /// Lifecycle state
+    pub state: ContainerState,
+    /// How the last init process terminated
+    pub exit_status: Option<ExitStatus>,
+    /// Init process PID
+    pub init_pid: Option<u32>,
+    /// Init process start time (clock ticks since boot), identifies the PID
+    pub init_start_time: Option<u64>,
+    /// Creation time (seconds since the Unix epoch)
+    pub created: u64,
+    /// Cgroup directory
+    pub cgroup_path: PathBuf,
+    /// Container configuration
+    pub config: ContainerConfig,
+}
+
+impl StateRecord {
+    /// Directory holding the state of container `id`
+    #[must_use]
+    pub fn dir(root: &Path, id: &str) -> PathBuf {
+        root.join(id)
+    }
+
+    /// Serialize to JSON
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        Value::object()
+            .with("version", STATE_VERSION)
+            .with("id", self.id.as_str())
+            .with("state", state_to_json(self.state))
+            .with("exit_status", self.exit_status.map(exit_status_to_json))
+            .with("init_pid", self.init_pid)
+            .with("init_start_time", self.init_start_time)
+            .with("created", self.created)
+            .with("cgroup_path", path_to_json(&self.cgroup_path))
+            .with("config", config_to_json(&self.config))
+            .to_string()
+    }
+
+    /// Parse a record written by `to_json()`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is malformed or has an unknown version.
+    pub fn from_json(text: &str) -> Result<Self, ContainerError> {
+        let doc = Value::parse(text).map_err(|e| invalid(&e.to_string()))?;
+
+        let version = doc.get("version").and_then(Value::as_u64);
+        if version != Some(STATE_VERSION) {
+            return Err(invalid(&format!("unsupported version {version:?}")));
+        }
+
+        Ok(Self {
+            id: str_field(&doc, "id")?.to_string(),
+            state: state_from_json(field(&doc, "state")?).ok_or_else(|| invalid("state"))?,
+            exit_status: optional(&doc, "exit_status", exit_status_from_json)?,
+            init_pid: optional(&doc, "init_pid", |v| v.as_u64()?.try_into().ok())?,
+            init_start_time: optional(&doc, "init_start_time", Value::as_u64)?,
+            created: u64_field(&doc, "created")?,
+            cgroup_path: PathBuf::from(str_field(&doc, "cgroup_path")?),
+            config: config_from_json(field(&doc, "config")?)?,
+        })
+    }
+
+    /// Write the record to `<root>/<id>/state.json`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state directory cannot be written.
+    pub fn save(&self, root: &Path) -> Result<(), ContainerError> {
+        let dir = Self::dir(root, &self.id);
+        create_private_dir(&dir)?;
+
+        let tmp = dir.join(format!(".{STATE_FILE}.tmp"));
+        let mut file = fs::File::create(&tmp).map_err(|e| io_error(&tmp, &e))?;
+        file.write_all(self.to_json().as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(|e| io_error(&tmp, &e))?;
+        fs::rename(&tmp, dir.join(STATE_FILE)).map_err(|e| io_error(&dir, &e))
+    }
+
+    /// Read the record of container `id`
+    ///
+    /// # Errors
+    ///
+    /// Returns `NotFound` if no state exists for `id`.
+    pub fn load(root: &Path, id: &str) -> Result<Self, ContainerError> {
+        let path = Self::dir(root, id).join(STATE_FILE);
+        let text = fs::read_to_string(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                ContainerError::NotFound(id.to_string())
+            } else {
+                io_error(&path, &e)
+            }
+        })?;
+
+        let record = Self::from_json(&text)?;
+        if record.id != id {
+            return Err(invalid(&format!(
+                "{} holds state of {}",
+                path.display(),
+                record.id
+            )));
+        }
+        Ok(record)
+    }
+
+    /// Whether state exists for container `id`
+    #[must_use]
+    pub fn exists(root: &Path, id: &str) -> bool {
+        Self::dir(root, id).join(STATE_FILE).exists()
+    }
+
+    /// Remove the state directory of container `id`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory exists but cannot be removed.
+    pub fn remove(root: &Path, id: &str) -> Result<(), ContainerError> {
+        let dir = Self::dir(root, id);
+        match fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(&dir, &e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// IDs of all containers with persisted state under `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` exists but cannot be read.
+    pub fn list(root: &Path) -> Result<Vec<String>, ContainerError> {
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(root, &e)),
+        };
+
+        let mut ids: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().join(STATE_FILE).is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Create a directory (and parents) accessible only by the owner
+fn create_private_dir(dir: &Path) -> Result<(), ContainerError> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .map_err(|e| io_error(dir, &e))
+}
+
+/// Current time in seconds since the Unix epoch
+#[must_use]
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// ============================================================================
+// Field Helpers
+// ============================================================================
+
+fn invalid(what: &str) -> ContainerError {
+    ContainerError::ConfigError(format!("Invalid container state: {what}"))
+}
+
+fn io_error(path: &Path, e: &std::io::Error) -> ContainerError {
+    ContainerError::IoError(format!("{}: {e}", path.display()))
+}
+
+fn field<'a>(doc: &'a Value, key: &str) -> Result<&'a Value, ContainerError> {
+    doc.get(key).ok_or_else(|| invalid(key))
+}
+
+fn str_field<'a>(doc: &'a Value, key: &str) -> Result<&'a str, ContainerError> {
+    field(doc, key)?.as_str().ok_or_else(|| invalid(key))
+}
+
+fn u64_field(doc: &Value, key: &str) -> Result<u64, ContainerError> {
+    field(doc, key)?.as_u64().ok_or_else(|| invalid(key))
+}
+
+fn u32_field(doc: &Value, key: &str) -> Result<u32, ContainerError> {
+    u64_field(doc, key)?.try_into().map_err(|_| invalid(key))
+}
+
+fn bool_field(doc: &Value, key: &str) -> Result<bool, ContainerError> {
+    field(doc, key)?.as_bool().ok_or_else(|| invalid(key))
+}
+
+/// A `null` key is `None`; anything else must parse, and the key must exist
+fn nullable<T>(
+    doc: &Value,
+    key: &str,
+    parse: impl FnOnce(&Value) -> Option<T>,
+) -> Result<Option<T>, ContainerError> {
+    field(doc, key)?;
+    optional(doc, key, parse)
+}
+
+/// A missing or `null` key is `None`; anything else must parse
+fn optional<T>(
+    doc: &Value,
+    key: &str,
+    parse: impl FnOnce(&Value) -> Option<T>,
+) -> Result<Option<T>, ContainerError> {
+    match doc.get(key).filter(|v| !v.is_null()) {
+        None => Ok(None),
+        Some(v) => parse(v).map(Some).ok_or_else(|| invalid(key)),
+    }
+}
+
+fn path_to_json(path: &Path) -> Value {
+    Value::from(path.to_string_lossy().into_owned())
+}
+
+// ============================================================================
+// State and Exit Status
+// ============================================================================
+
+fn state_to_json(state: ContainerState) -> Value {
+    match state {
+        ContainerState::Created => "created".into(),
+        ContainerState::Running => "running".into(),
+        ContainerState::Paused => "paused".into(),
+        ContainerState::Stopped => "stopped".into(),
+        ContainerState::Exited { code } => Value::object().with("exited", code),
+    }
+}
+
+fn state_from_json(v: &Value) -> Option<ContainerState> {
+    if let Some(code) = v.get("exited") {
+        return Some(ContainerState::Exited {
+            code: code.as_i64()?.try_into().ok()?,
+        });
+    }
+    match v.as_str()? {
+        "created" => Some(ContainerState::Created),
+        "running" => Some(ContainerState::Running),
+        "paused" => Some(ContainerState::Paused),
+        "stopped" => Some(ContainerState::Stopped),
+        _ => None,
+    }
+}
+
+fn exit_status_to_json(status: ExitStatus) -> Value {
+    match status {
+        ExitStatus::Exited(code) => Value::object().with("exited", code),
+        ExitStatus::Signaled(signal) => Value::object().with("signaled", signal),
+        ExitStatus::Unknown => "unknown".into(),
+    }
+}
+
+fn exit_status_from_json(v: &Value) -> Option<ExitStatus> {
+    if v.as_str() == Some("unknown") {
+        return Some(ExitStatus::Unknown);
+    }
+    if let Some(code) = v.get("exited") {
+        return Some(ExitStatus::Exited(code.as_i64()?.try_into().ok()?));
+    }
+    Some(ExitStatus::Signaled(
+        v.get("signaled")?.as_i64()?.try_into().ok()?,
+    ))
+}
+
+// ============================================================================
+// Container Configuration
+// ============================================================================
+
+fn config_to_json(config: &ContainerConfig) -> Value {
+    let env: Vec<String> = config.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    let labels = config
+        .labels
+        .iter()
+        .fold(Value::object(), |obj, (k, v)| obj.with(k, v.as_str()));
+
+    Value::object()
+        .with("rootfs", path_to_json(&config.rootfs))
+        .with("hostname", config.hostname.as_str())
+        .with("workdir", path_to_json(&config.workdir))
+        .with("args", config.args.clone())
+        .with("env", env)
+        .with(
+            "user",
+            Value::object()
+                .with("uid", config.user.uid)
+                .with("gid", config.user.gid)
+                .with("additional_gids", config.user.additional_gids.clone())
+                .with("name", config.user.name.clone())
+                .with("group", config.user.group.clone())
+                .with("additional_groups", config.user.additional_groups.clone())
+                .with("umask", config.user.umask),
+        )
+        .with("terminal", config.terminal)
+        .with(
+            "console_socket",
+            config.console_socket.as_deref().map(path_to_json),
+        )
+        .with(
+            "console_size",
+            config.console_size.map(|size| {
+                Value::object()
+                    .with("rows", u64::from(size.rows))
+                    .with("cols", u64::from(size.cols))
+            }),
+        )
+        .with(
+            "log",
+            config.log.map(|log| {
+                Value::object()
+                    .with("max_size", log.max_size)
+                    .with("max_files", log.max_files)
+            }),
+        )
+        .with("mini_init", config.mini_init)
+        .with("preserve_fds", config.preserve_fds.clone())
+        .with("parent_death_signal", config.parent_death_signal)
+        .with("capabilities", capabilities_to_json(&config.capabilities))
+        .with(
+            "rlimits",
+            config
+                .rlimits
+                .iter()
+                .map(|l| {
+                    Value::object()
+                        .with("type", l.resource.name())
+                        .with("soft", l.soft)
+                        .with("hard", l.hard)
+                })
+                .collect::<Vec<_>>(),
+        )
+        .with("namespaces", config.namespaces.bits())
+        .with("uid_map", config.uid_map.as_ref().map(id_mapping_to_json))
+        .with("gid_map", config.gid_map.as_ref().map(id_mapping_to_json))
+        .with(
+            "cpu",
+            Value::object()
+                .with("quota_us", config.cpu.quota_us)
+                .with("period_us", config.cpu.period_us)
+                .with("weight", config.cpu.weight),
+        )
+        .with(
+            "memory",
+            Value::object()
+                .with("max", config.memory.max)
+                .with("high", config.memory.high)
+                .with("min", config.memory.min)
+                .with("oom_kill", config.memory.oom_kill),
+        )
+        .with(
+            "io",
+            config.io.as_ref().map(|io| {
+                Value::object()
+                    .with("device", io.device.as_str())
+                    .with("rbps", io.rbps)
+                    .with("wbps", io.wbps)
+                    .with("riops", io.riops)
+                    .with("wiops", io.wiops)
+            }),
+        )
+        .with("pids_max", config.pids.max)
+        .with(
+            "cpuset",
+            config.cpuset.as_ref().map(|cpuset| {
+                Value::object()
+                    .with("cpus", cpuset.cpus.as_str())
+                    .with("mems", cpuset.mems.as_str())
+            }),
+        )
+        .with("readonly_rootfs", config.readonly_rootfs)
+        .with("network", config.network)
+        .with(
+            "network_config",
+            config.network_config.as_ref().map(network_config_to_json),
+        )
+        .with("state_root", path_to_json(&config.state_root))
+        .with("labels", labels)
+        .with(
+            "restart_policy",
+            restart_policy_to_json(config.restart_policy),
+        )
+        .with(
+            "health_check",
+            config.health_check.as_ref().map(health_check_to_json),
+        )
+        .with("hooks", hooks_to_json(&config.hooks))
+}
+
+fn config_from_json(v: &Value) -> Result<ContainerConfig, ContainerError> {
+    let strings = |key: &str| -> Result<Vec<String>, ContainerError> {
+        field(v, key)?
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|s| s.as_str().map(str::to_string))
+                    .collect()
+            })
+            .ok_or_else(|| invalid(key))
+    };
+
+    let env = strings("env")?
+        .into_iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (pair, String::new()),
+        })
+        .collect();
+
+    let user = field(v, "user")?;
+    let additional_gids = field(user, "additional_gids")?
+        .as_array()
+        .and_then(|gids| gids.iter().map(|g| g.as_u64()?.try_into().ok()).collect())
+        .ok_or_else(|| invalid("additional_gids"))?;
+    let additional_groups = field(user, "additional_groups")?
+        .as_array()
+        .and_then(|names| {
+            names
+                .iter()
+                .map(|n| n.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| invalid("additional_groups"))?;
+
+    let cpu = field(v, "cpu")?;
+    let memory = field(v, "memory")?;
+
+    let io = match v.get("io").filter(|io| !io.is_null()) {
+        None => None,
+        Some(io) => Some(IoConfig {
+            device: str_field(io, "device")?.to_string(),
+            rbps: u64_field(io, "rbps")?,
+            wbps: u64_field(io, "wbps")?,
+            riops: u64_field(io, "riops")?,
+            wiops: u64_field(io, "wiops")?,
+        }),
+    };
+
+    let network_config = match v.get("network_config").filter(|n| !n.is_null()) {
+        None => None,
+        Some(n) => Some(network_config_from_json(n)?),
+    };
+
+    let labels = field(v, "labels")?
+        .as_object()
+        .and_then(|entries| {
+            entries
+                .iter()
+                .map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .ok_or_else(|| invalid("labels"))?;
+
+    let namespaces = field(v, "namespaces")?
+        .as_i64()
+        .and_then(|bits| i32::try_from(bits).ok())
+        .ok_or_else(|| invalid("namespaces"))?;
+
+    Ok(ContainerConfig {
+        rootfs: PathBuf::from(str_field(v, "rootfs")?),
+        hostname: str_field(v, "hostname")?.to_string(),
+        workdir: PathBuf::from(str_field(v, "workdir")?),
+        args: strings("args")?,
+        env,
+        user: ProcessUser {
+            uid: u32_field(user, "uid")?,
+            gid: u32_field(user, "gid")?,
+            additional_gids,
+            name: optional(user, "name", |n| n.as_str().map(str::to_string))?,
+            group: optional(user, "group", |g| g.as_str().map(str::to_string))?,
+            additional_groups,
+            umask: optional(user, "umask", |m| m.as_u64()?.try_into().ok())?,
+        },
+        terminal: bool_field(v, "terminal")?,
+        console_socket: optional(v, "console_socket", |p| p.as_str().map(PathBuf::from))?,
+        console_size: optional(v, "console_size", |size| {
+            Some(WindowSize::new(
+                size.get("rows")?.as_u64()?.try_into().ok()?,
+                size.get("cols")?.as_u64()?.try_into().ok()?,
+            ))
+        })?,
+        log: optional(v, "log", |log| {
+            Some(LogConfig::new(
+                log.get("max_size")?.as_u64()?,
+                log.get("max_files")?.as_u64()?.try_into().ok()?,
+            ))
+        })?,
+        mini_init: bool_field(v, "mini_init")?,
+        preserve_fds: field(v, "preserve_fds")?
+            .as_array()
+            .and_then(|fds| fds.iter().map(|fd| fd.as_i64()?.try_into().ok()).collect())
+            .ok_or_else(|| invalid("preserve_fds"))?,
+        parent_death_signal: nullable(v, "parent_death_signal", |s| s.as_i64()?.try_into().ok())?,
+        capabilities: capabilities_from_json(field(v, "capabilities")?)
+            .ok_or_else(|| invalid("capabilities"))?,
+        rlimits: rlimits_from_json(field(v, "rlimits")?).ok_or_else(|| invalid("rlimits"))?,
+        namespaces: NamespaceFlags::from_bits(namespaces),
+        uid_map: optional(v, "uid_map", id_mapping_from_json)?,
+        gid_map: optional(v, "gid_map", id_mapping_from_json)?,
+        cpu: CpuConfig {
+            quota_us: u64_field(cpu, "quota_us")?,
+            period_us: u64_field(cpu, "period_us")?,
+            weight: u32_field(cpu, "weight")?
+                .try_into()
+                .map_err(|_| invalid("weight"))?,
+        },
+        memory: MemoryConfig {
+            max: u64_field(memory, "max")?,
+            high: u64_field(memory, "high")?,
+            min: u64_field(memory, "min")?,
+            oom_kill: bool_field(memory, "oom_kill")?,
+        },
+        io,
+        pids: PidsConfig {
+            max: u64_field(v, "pids_max")?,
+        },
+        cpuset: nullable(v, "cpuset", |c| {
+            Some(CpusetConfig::new(
+                c.get("cpus")?.as_str()?,
+                c.get("mems")?.as_str()?,
+            ))
+        })?,
+        readonly_rootfs: bool_field(v, "readonly_rootfs")?,
+        network: bool_field(v, "network")?,
+        network_config,
+        state_root: PathBuf::from(str_field(v, "state_root")?),
+        labels,
+        restart_policy: restart_policy_from_json(field(v, "restart_policy")?)
+            .ok_or_else(|| invalid("restart_policy"))?,
+        health_check: optional(v, "health_check", health_check_from_json)?,
+        hooks: hooks_from_json(field(v, "hooks")?).ok_or_else(|| invalid("hooks"))?,
+    })
+}
+
+fn restart_policy_to_json(policy: RestartPolicy) -> Value {
+    match policy {
+        RestartPolicy::Never => "never".into(),
+        RestartPolicy::Always => "always".into(),
+        RestartPolicy::UnlessStopped => "unless-stopped".into(),
+        RestartPolicy::OnFailure { max_retries } => Value::object().with("on_failure", max_retries),
+    }
+}
+
+fn restart_policy_from_json(v: &Value) -> Option<RestartPolicy> {
+    if let Some(max_retries) = v.get("on_failure") {
+        return Some(RestartPolicy::OnFailure {
+            max_retries: max_retries.as_u64()?.try_into().ok()?,
+        });
+    }
+    match v.as_str()? {
+        "never" => Some(RestartPolicy::Never),
+        "always" => Some(RestartPolicy::Always),
+        "unless-stopped" => Some(RestartPolicy::UnlessStopped),
+        _ => None,
+    }
+}
+
+fn health_check_to_json(check: &HealthCheck) -> Value {
+    let probe = match &check.probe {
+        HealthProbe::Exec(cmd) => Value::object().with("exec", cmd.clone()),
+        HealthProbe::File(path) => Value::object().with("file", path_to_json(path)),
+        HealthProbe::Tcp(addr) => Value::object().with("tcp", addr.to_string()),
+    };
+
+    Value::object()
+        .with("probe", probe)
+        .with("interval_ms", duration_ms(check.interval))
+        .with("timeout_ms", duration_ms(check.timeout))
+        .with("retries", check.retries)
+        .with("start_period_ms", duration_ms(check.start_period))
+}
+
+fn health_check_from_json(v: &Value) -> Option<HealthCheck> {
+    let probe = v.get("probe")?;
+    let probe = if let Some(cmd) = probe.get("exec") {
+        HealthProbe::Exec(
+            cmd.as_array()?
+                .iter()
+                .map(|s| s.as_str().map(str::to_string))
+                .collect::<Option<_>>()?,
+        )
+    } else if let Some(path) = probe.get("file") {
+        HealthProbe::File(PathBuf::from(path.as_str()?))
+    } else {
+        HealthProbe::Tcp(probe.get("tcp")?.as_str()?.parse().ok()?)
+    };
+    let ms = |key: &str| v.get(key)?.as_u64().map(Duration::from_millis);
+
+    Some(HealthCheck {
+        probe,
+        interval: ms("interval_ms")?,
+        timeout: ms("timeout_ms")?,
+        retries: v.get("retries")?.as_u64()?.try_into().ok()?,
+        start_period: ms("start_period_ms")?,
+    })
+}
+
+/// Capability sets as raw masks
+fn capabilities_to_json(caps: &Capabilities) -> Value {
+    Value::object()
+        .with("bounding", caps.bounding.bits())
+        .with("effective", caps.effective.bits())
+        .with("permitted", caps.permitted.bits())
+        .with("inheritable", caps.inheritable.bits())
+        .with("ambient", caps.ambient.bits())
+}
+
+fn capabilities_from_json(v: &Value) -> Option<Capabilities> {
+    let set = |key: &str| v.get(key)?.as_u64().map(CapabilitySet::from_bits);
+    Some(Capabilities {
+        bounding: set("bounding")?,
+        effective: set("effective")?,
+        permitted: set("permitted")?,
+        inheritable: set("inheritable")?,
+        ambient: set("ambient")?,
+    })
+}
+
+fn rlimits_from_json(v: &Value) -> Option<Vec<Rlimit>> {
+    v.as_array()?
+        .iter()
+        .map(|l| {
+            Some(Rlimit::new(
+                RlimitResource::from_name(l.get("type")?.as_str()?)?,
+                l.get("soft")?.as_u64()?,
+                l.get("hard")?.as_u64()?,
+            ))
+        })
+        .collect()
+}
+
+/// Hooks keyed by OCI phase name; phases without hooks are omitted
+fn hooks_to_json(hooks: &Hooks) -> Value {
+    HookPhase::ALL
+        .into_iter()
+        .filter(|&phase| !hooks.get(phase).is_empty())
+        .fold(Value::object(), |obj, phase| {
+            let list: Vec<Value> = hooks
+                .get(phase)
+                .iter()
+                .map(|hook| {
+                    let env: Vec<String> =
+                        hook.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                    Value::object()
+                        .with("path", path_to_json(&hook.path))
+                        .with("args", hook.args.clone())
+                        .with("env", env)
+                        .with("timeout_ms", hook.timeout.map(duration_ms))
+                })
+                .collect();
+            obj.with(&phase.to_string(), list)
+        })
+}
+
+fn hooks_from_json(v: &Value) -> Option<Hooks> {
+    let strings = |v: &Value| -> Option<Vec<String>> {
+        v.as_array()?
+            .iter()
+            .map(|s| s.as_str().map(str::to_string))
+            .collect()
+    };
+
+    let mut hooks = Hooks::default();
+    for phase in HookPhase::ALL {
+        let Some(list) = v.get(&phase.to_string()) else {
+            continue;
+        };
+        for hook in list.as_array()? {
+            let env = strings(hook.get("env")?)?
+                .into_iter()
+                .map(|pair| match pair.split_once('=') {
+                    Some((k, v)) => (k.to_string(), v.to_string()),
+                    None => (pair, String::new()),
+                })
+                .collect();
+            let timeout = match hook.get("timeout_ms").filter(|t| !t.is_null()) {
+                None => None,
+                Some(ms) => Some(Duration::from_millis(ms.as_u64()?)),
+            };
+            hooks.get_mut(phase).push(Hook {
+                path: PathBuf::from(hook.get("path")?.as_str()?),
+                args: strings(hook.get("args")?)?,
+                env,
+                timeout,
+            });
+        }
+    }
+    Some(hooks)
+}
+
+fn duration_ms(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn id_mapping_to_json(mapping: &IdMapping) -> Value {
+    Value::object()
+        .with("inner_id", mapping.inner_id)
+        .with("outer_id", mapping.outer_id)
+        .with("count", mapping.count)
+}
+
+fn id_mapping_from_json(v: &Value) -> Option<IdMapping> {
+    Some(IdMapping {
+        inner_id: v.get("inner_id")?.as_u64()?.try_into().ok()?,
+        outer_id: v.get("outer_id")?.as_u64()?.try_into().ok()?,
+        count: v.get("count")?.as_u64()?.try_into().ok()?,
+    })
+}
+
+fn network_config_to_json(config: &NetworkConfig) -> Value {
+    Value::object()
+        .with("bridge_name", config.bridge_name.as_str())
+        .with("veth_host", config.veth_host.as_str())
+        .with("veth_container", config.veth_container.as_str())
+        .with("container_ip", config.container_ip.as_str())
+        .with("gateway_ip", config.gateway_ip.as_str())
+        .with("subnet_bits", config.subnet_bits)
+        .with("mtu", config.mtu)
+}
+
+fn network_config_from_json(v: &Value) -> Result<NetworkConfig, ContainerError> {
+    Ok(NetworkConfig {
+        bridge_name: str_field(v, "bridge_name")?.to_string(),
+        veth_host: str_field(v, "veth_host")?.to_string(),
+        veth_container: str_field(v, "veth_container")?.to_string(),
+        container_ip: str_field(v, "container_ip")?.to_string(),
+        gateway_ip: str_field(v, "gateway_ip")?.to_string(),
+        subnet_bits: u32_field(v, "subnet_bits")?
+            .try_into()
+            .map_err(|_| invalid("subnet_bits"))?,
+        mtu: u32_field(v, "mtu")?
+            .try_into()
+            .map_err(|_| invalid("mtu"))?,
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "alice-container-state-test-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos()
+        ))
+    }
+
+    fn sample_record() -> StateRecord {
+        let config = ContainerConfig::builder()
+            .rootfs("/var/lib/alice/web")
+            .hostname("web")
+            .args(["/srv/server", "--port", "7777"])
+            .env("MODE", "a=b")
+            .user(1000, 1000)
+            .additional_gids([10, 20])
+            .group_name("web")
+            .additional_groups(["adm"])
+            .umask(0o027)
+            .terminal(true)
+            .console_socket("/run/alice/web-console.sock")
+            .console_size(40, 120)
+            .log(LogConfig::new(1024 * 1024, 3))
+            .mini_init(true)
+            .preserve_fds([3, 5])
+            .parent_death_signal(Some(libc::SIGTERM))
+            .cap_add(Capability::NetAdmin)
+            .rlimit(RlimitResource::Nofile, 4096, crate::rlimit::RLIM_INFINITY)
+            .cpu_percent(50)
+            .memory_max(512 * 1024 * 1024)
+            .pids_max(256)
+            .cpuset("0-1", "0")
+            .user_namespace(IdMapping::root_to_user(1000), IdMapping::root_to_user(1000))
+            .network_config(NetworkConfig::from_container_id("web", 1))
+            .readonly()
+            .label("app", "web")
+            .label("tier", "front")
+            .restart_policy(RestartPolicy::OnFailure { max_retries: 5 })
+            .health_check(
+                HealthCheck::exec(["/srv/healthz"])
+                    .interval(Duration::from_secs(2))
+                    .start_period(Duration::from_millis(1500)),
+            )
+            .hook(
+                HookPhase::CreateRuntime,
+                Hook::new("/usr/libexec/net-setup")
+                    .args(["net-setup", "br0"])
+                    .env("MODE", "a=b")
+                    .timeout(Duration::from_secs(5)),
+            )
+            .hook(HookPhase::Poststop, Hook::new("/usr/bin/deregister"))
+            .build();
+
+        StateRecord {
+            id: "web".into(),
+            state: ContainerState::Running,
+            exit_status: None,
+            init_pid: Some(4242),
+            init_start_time: Some(987_654),
+            created: 1_700_000_000,
+            cgroup_path: PathBuf::from("/sys/fs/cgroup/alice/web"),
+            config,
+        }
+    }
+
+    #[test]
+    fn test_record_json_roundtrip() {
+        let record = sample_record();
+        let parsed = StateRecord::from_json(&record.to_json()).unwrap();
+
+        assert_eq!(parsed.id, "web");
+        assert_eq!(parsed.state, ContainerState::Running);
+        assert_eq!(parsed.init_pid, Some(4242));
+        assert_eq!(parsed.init_start_time, Some(987_654));
+        assert_eq!(parsed.created, 1_700_000_000);
+        assert_eq!(parsed.cgroup_path, record.cgroup_path);
+
+        let (a, b) = (&parsed.config, &record.config);
+        assert_eq!(a.rootfs, b.rootfs);
+        assert_eq!(a.args, b.args);
+        assert_eq!(a.env, b.env);
+        assert_eq!(a.user, b.user);
+        assert!(a.terminal);
+        assert_eq!(a.console_socket, b.console_socket);
+        assert_eq!(a.console_size, b.console_size);
+        assert_eq!(a.log, b.log);
+        assert!(a.mini_init);
+        assert_eq!(a.preserve_fds, [3, 5]);
+        assert_eq!(a.parent_death_signal, Some(libc::SIGTERM));
+        assert_eq!(a.capabilities, b.capabilities);
+        assert_eq!(a.rlimits, b.rlimits);
+        assert_eq!(a.namespaces, b.namespaces);
+        assert_eq!(a.uid_map.unwrap().outer_id, 1000);
+        assert_eq!(a.cpu.quota_us, b.cpu.quota_us);
+        assert_eq!(a.memory.max, b.memory.max);
+        assert_eq!(a.memory.high, b.memory.high);
+        assert_eq!(a.pids, b.pids);
+        assert_eq!(a.cpuset, b.cpuset);
+        assert!(a.readonly_rootfs);
+        assert_eq!(
+            a.network_config.as_ref().unwrap().container_ip,
+            "10.0.0.3/24"
+        );
+        assert_eq!(a.state_root, b.state_root);
+        assert_eq!(a.labels, b.labels);
+        assert_eq!(a.label("tier"), Some("front"));
+        assert_eq!(a.restart_policy, b.restart_policy);
+        assert_eq!(a.health_check, b.health_check);
+        assert_eq!(a.hooks, b.hooks);
+    }
+
+    #[test]
+    fn test_unlimited_values_roundtrip() {
+        let mut record = sample_record();
+        record.config.cpu = CpuConfig::default();
+        record.config.memory = MemoryConfig::default();
+        record.config.io = Some(IoConfig::new("8:0"));
+
+        let parsed = StateRecord::from_json(&record.to_json()).unwrap();
+        assert_eq!(parsed.config.cpu.quota_us, u64::MAX);
+        assert_eq!(parsed.config.memory.max, u64::MAX);
+        assert_eq!(parsed.config.io.unwrap().wiops, u64::MAX);
+    }
+
+    #[test]
+    fn test_health_probes_roundtrip() {
+        for check in [
+            HealthCheck::file("/run/ready").retries(7),
+            HealthCheck::tcp("10.0.0.3:7777".parse().unwrap()).timeout(Duration::from_secs(1)),
+        ] {
+            let mut record = sample_record();
+            record.config.health_check = Some(check.clone());
+            let parsed = StateRecord::from_json(&record.to_json()).unwrap();
+            assert_eq!(parsed.config.health_check, Some(check));
+        }
+    }
+
+    #[test]
+    fn test_exit_states_roundtrip() {
+        for (state, status) in [
+            (
+                ContainerState::Exited { code: 137 },
+                ExitStatus::Signaled(9),
+            ),
+            (ContainerState::Exited { code: 3 }, ExitStatus::Exited(3)),
+            (ContainerState::Exited { code: -1 }, ExitStatus::Unknown),
+            (ContainerState::Stopped, ExitStatus::Signaled(15)),
+        ] {
+            let mut record = sample_record();
+            record.state = state;
+            record.exit_status = Some(status);
+            record.init_pid = None;
+
+            let parsed = StateRecord::from_json(&record.to_json()).unwrap();
+            assert_eq!(parsed.state, state);
+            assert_eq!(parsed.exit_status, Some(status));
+            assert_eq!(parsed.init_pid, None);
+        }
+    }
+
+    #[test]
+    fn test_from_json_rejects_bad_input() {
+        assert!(StateRecord::from_json("{").is_err());
+        assert!(StateRecord::from_json("{\"version\":99}").is_err());
+
+        let text = sample_record()
+            .to_json()
+            .replace("\"running\"", "\"flying\"");
+        assert!(StateRecord::from_json(&text).is_err());
+
+        let without = |key: &str| {
+            let text = sample_record()
+                .to_json()
+                .replace(&format!("\"{key}\":"), &format!("\"no-{key}\":"));
+            StateRecord::from_json(&text)
+        };
+        assert!(without("labels").is_err());
+        assert!(without("restart_policy").is_err());
+        assert!(without("hooks").is_err());
+        assert!(without("capabilities").is_err());
+        assert!(without("rlimits").is_err());
+        assert!(without("additional_groups").is_err());
+        assert!(without("pids_max").is_err());
+        assert!(without("cpuset").is_err());
+        assert!(without("mini_init").is_err());
+        assert!(without("preserve_fds").is_err());
+        assert!(without("parent_death_signal").is_err());
+    }
+
+    #[test]
+    fn test_save_load_list_remove() {
+        let root = temp_root();
+        let record = sample_record();
+
+        assert!(!StateRecord::exists(&root, "web"));
+        assert!(StateRecord::list(&root).unwrap().is_empty());
+
+        record.save(&root).unwrap();
+        assert!(StateRecord::exists(&root, "web"));
+        assert_eq!(StateRecord::list(&root).unwrap(), vec!["web".to_string()]);
+
+        let loaded = StateRecord::load(&root, "web").unwrap();
+        assert_eq!(loaded.init_pid, record.init_pid);
+
+        // Saving again replaces the record atomically
+        let mut stopped = record.clone();
+        stopped.state = ContainerState::Stopped;
+        stopped.save(&root).unwrap();
+        assert_eq!(
+            StateRecord::load(&root, "web").unwrap().state,
+            ContainerState::Stopped
+        );
+
+        StateRecord::remove(&root, "web").unwrap();
+        assert!(matches!(
+            StateRecord::load(&root, "web"),
+            Err(ContainerError::NotFound(_))
+        ));
+        // Removing twice is fine
+        StateRecord::remove(&root, "web").unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_load_rejects_mismatched_id() {
+        let root = temp_root();
+        let record = sample_record();
+        record.save(&root).unwrap();
+        fs::rename(root.join("web"), root.join("other")).unwrap();
+
+        assert!(StateRecord::load(&root, "other").is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,389 @@
+//! Container Resource Statistics
+//!
+//! A typed snapshot of the cgroup v2 accounting files and the container's
+//! network interface, collected with `Container::stats()`.
+//!
+//! ## Sources
+//!
+//! | Field | File |
+//! |-------|------|
+//! | `cpu` | `cpu.stat` |
+//! | `memory` | `memory.current`, `memory.peak`, `memory.max`, `memory.stat`, `memory.events` |
+//! | `io` | `io.stat` (one entry per device) |
+//! | `pids` | `pids.current`, `pids.max` |
+//! | `network` | `/sys/class/net/<host veth>/statistics/*` |
+//!
+//! Only `cpu.stat` is required. Files of controllers that are not enabled
+//! read as zero, so a snapshot can always be taken.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::cgroup::{CgroupController, CgroupError};
+use crate::scheduler::CpuStats;
+
+// ============================================================================
+// Snapshot
+// ============================================================================
+
+/// Resource usage of a container at one point in time
+#[derive(Debug, Clone)]
+pub struct ContainerStats {
+    /// When the snapshot was taken
+    pub timestamp: SystemTime,
+    /// CPU time and throttling
+    pub cpu: CpuStats,
+    /// Memory usage and pressure events
+    pub memory: MemoryStats,
+    /// Block I/O per device
+    pub io: Vec<IoDeviceStats>,
+    /// Task count
+    pub pids: PidsStats,
+    /// Traffic of the container interface, if the container has one
+    pub network: Option<NetworkStats>,
+}
+
+/// Memory usage from `memory.current`, `memory.stat` and `memory.events`
+///
+/// Sizes are in bytes, fault counters in events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Total usage
+    pub current: u64,
+    /// Highest usage recorded (Linux 5.19+)
+    pub peak: Option<u64>,
+    /// Limit from `memory.max` (`None` if unlimited)
+    pub limit: Option<u64>,
+    /// Anonymous memory (heap, stacks)
+    pub anon: u64,
+    /// Page cache
+    pub file: u64,
+    /// Kernel memory (stacks, page tables, slab, ...)
+    pub kernel: u64,
+    /// Slab allocations
+    pub slab: u64,
+    /// Socket buffers
+    pub sock: u64,
+    /// Shared memory and tmpfs
+    pub shmem: u64,
+    /// Page cache mapped into processes
+    pub file_mapped: u64,
+    /// Page cache waiting to be written back
+    pub file_dirty: u64,
+    /// Page cache being written back
+    pub file_writeback: u64,
+    /// Page faults
+    pub pgfault: u64,
+    /// Page faults that required I/O
+    pub pgmajfault: u64,
+    /// Counters from `memory.events`
+    pub events: MemoryEvents,
+}
+
+/// Memory limit events from `memory.events`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEvents {
+    /// Reclaimed while below `memory.low`
+    pub low: u64,
+    /// Throttled above `memory.high`
+    pub high: u64,
+    /// Usage about to exceed `memory.max`
+    pub max: u64,
+    /// Allocations that failed at `memory.max`
+    pub oom: u64,
+    /// Processes killed by the OOM killer
+    pub oom_kill: u64,
+}
+
+/// Block I/O of one device from `io.stat`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoDeviceStats {
+    /// Device major number
+    pub major: u32,
+    /// Device minor number
+    pub minor: u32,
+    /// Bytes read
+    pub rbytes: u64,
+    /// Bytes written
+    pub wbytes: u64,
+    /// Read operations
+    pub rios: u64,
+    /// Write operations
+    pub wios: u64,
+    /// Bytes discarded
+    pub dbytes: u64,
+    /// Discard operations
+    pub dios: u64,
+}
+
+/// Task count from the pids controller
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PidsStats {
+    /// Tasks in the cgroup
+    pub current: u64,
+    /// Limit from `pids.max` (`None` if unlimited)
+    pub limit: Option<u64>,
+}
+
+/// Interface counters, from the container's point of view
+///
+/// Read from the host end of the veth pair, with receive and transmit
+/// swapped: what the host end transmits, the container receives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Host-side veth the counters were read from
+    pub interface: String,
+    /// Bytes received by the container
+    pub rx_bytes: u64,
+    /// Packets received by the container
+    pub rx_packets: u64,
+    /// Receive errors
+    pub rx_errors: u64,
+    /// Received packets dropped
+    pub rx_dropped: u64,
+    /// Bytes sent by the container
+    pub tx_bytes: u64,
+    /// Packets sent by the container
+    pub tx_packets: u64,
+    /// Transmit errors
+    pub tx_errors: u64,
+    /// Sent packets dropped
+    pub tx_dropped: u64,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// `key value` pairs of a flat-keyed cgroup file, skipping unparsable values
+fn flat_keyed(content: &str) -> impl Iterator<Item = (&str, u64)> {
+    content.lines().filter_map(|line| {
+        let mut parts = line.split_whitespace();
+        let key = parts.next()?;
+        let value = parts.next()?.parse().ok()?;
+        Some((key, value))
+    })
+}
+
+/// Parse a single-value file such as `memory.max`; `max` is `None`
+fn parse_limit(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
+}
+
+impl MemoryStats {
+    /// Fill the breakdown from `memory.stat` content
+    fn apply_stat(&mut self, content: &str) {
+        for (key, value) in flat_keyed(content) {
+            match key {
+                "anon" => self.anon = value,
+                "file" => self.file = value,
+                "kernel" => self.kernel = value,
+                "slab" => self.slab = value,
+                "sock" => self.sock = value,
+                "shmem" => self.shmem = value,
+                "file_mapped" => self.file_mapped = value,
+                "file_dirty" => self.file_dirty = value,
+                "file_writeback" => self.file_writeback = value,
+                "pgfault" => self.pgfault = value,
+                "pgmajfault" => self.pgmajfault = value,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl MemoryEvents {
+    /// Parse `memory.events` content
+    #[must_use]
+    pub fn from_memory_events(content: &str) -> Self {
+        let mut events = Self::default();
+        for (key, value) in flat_keyed(content) {
+            match key {
+                "low" => events.low = value,
+                "high" => events.high = value,
+                "max" => events.max = value,
+                "oom" => events.oom = value,
+                "oom_kill" => events.oom_kill = value,
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+impl IoDeviceStats {
+    /// Parse `io.stat` content, one entry per `MAJ:MIN key=value ...` line
+    #[must_use]
+    pub fn from_io_stat(content: &str) -> Vec<Self> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let (major, minor) = parts.next()?.split_once(':')?;
+                let mut stats = Self {
+                    major: major.parse().ok()?,
+                    minor: minor.parse().ok()?,
+                    ..Self::default()
+                };
+                for (key, value) in parts.filter_map(|kv| kv.split_once('=')) {
+                    let Ok(value) = value.parse() else { continue };
+                    match key {
+                        "rbytes" => stats.rbytes = value,
+                        "wbytes" => stats.wbytes = value,
+                        "rios" => stats.rios = value,
+                        "wios" => stats.wios = value,
+                        "dbytes" => stats.dbytes = value,
+                        "dios" => stats.dios = value,
+                        _ => {}
+                    }
+                }
+                Some(stats)
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Collection
+// ============================================================================
+
+/// Read an interface file, treating a missing file as empty
+fn read_optional(cgroup: &CgroupController, file: &str) -> Result<String, CgroupError> {
+    match cgroup.read_interface(file) {
+        Err(CgroupError::NotFound(_)) => Ok(String::new()),
+        other => other,
+    }
+}
+
+/// Take a snapshot of `cgroup`, and of `veth_host` if given
+///
+/// # Errors
+///
+/// Returns an error if `cpu.stat` cannot be read, or another accounting
+/// file exists but cannot be read.
+pub(crate) fn collect(
+    cgroup: &CgroupController,
+    veth_host: Option<&str>,
+) -> Result<ContainerStats, CgroupError> {
+    let timestamp = SystemTime::now();
+    let cpu = CpuStats::from_cpu_stat(&cgroup.read_interface("cpu.stat")?);
+
+    let mut memory = MemoryStats {
+        current: parse_limit(&read_optional(cgroup, "memory.current")?).unwrap_or(0),
+        peak: parse_limit(&read_optional(cgroup, "memory.peak")?),
+        limit: parse_limit(&read_optional(cgroup, "memory.max")?),
+        events: MemoryEvents::from_memory_events(&read_optional(cgroup, "memory.events")?),
+        ..MemoryStats::default()
+    };
+    memory.apply_stat(&read_optional(cgroup, "memory.stat")?);
+
+    let pids = PidsStats {
+        current: parse_limit(&read_optional(cgroup, "pids.current")?).unwrap_or(0),
+        limit: parse_limit(&read_optional(cgroup, "pids.max")?),
+    };
+
+    Ok(ContainerStats {
+        timestamp,
+        cpu,
+        memory,
+        io: IoDeviceStats::from_io_stat(&read_optional(cgroup, "io.stat")?),
+        pids,
+        network: veth_host.and_then(|veth| network_stats(Path::new("/sys/class/net"), veth)),
+    })
+}
+
+/// Counters of `interface` below `class_net`, `None` if it does not exist
+fn network_stats(class_net: &Path, interface: &str) -> Option<NetworkStats> {
+    let dir = class_net.join(interface).join("statistics");
+    if !dir.is_dir() {
+        return None;
+    }
+    let counter = |name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    Some(NetworkStats {
+        interface: interface.to_string(),
+        rx_bytes: counter("tx_bytes"),
+        rx_packets: counter("tx_packets"),
+        rx_errors: counter("tx_errors"),
+        rx_dropped: counter("tx_dropped"),
+        tx_bytes: counter("rx_bytes"),
+        tx_packets: counter("rx_packets"),
+        tx_errors: counter("rx_errors"),
+        tx_dropped: counter("rx_dropped"),
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_stat_and_events() {
+        let mut memory = MemoryStats::default();
+        memory.apply_stat(
+            "anon 4096\nfile 8192\nkernel 1024\nslab 512\nsock 0\nshmem 64\n\
+             file_mapped 128\nfile_dirty 16\nfile_writeback 0\npgfault 300\npgmajfault 2\n\
+             workingset_refault_anon 7\n",
+        );
+        assert_eq!(memory.anon, 4096);
+        assert_eq!(memory.file, 8192);
+        assert_eq!(memory.kernel, 1024);
+        assert_eq!(memory.shmem, 64);
+        assert_eq!(memory.pgfault, 300);
+        assert_eq!(memory.pgmajfault, 2);
+
+        let events = MemoryEvents::from_memory_events("low 0\nhigh 12\nmax 3\noom 1\noom_kill 1\n");
+        assert_eq!(events.high, 12);
+        assert_eq!(events.oom_kill, 1);
+    }
+
+    #[test]
+    fn test_io_stat_per_device() {
+        let devices = IoDeviceStats::from_io_stat(
+            "8:0 rbytes=1048576 wbytes=4096 rios=10 wios=1 dbytes=0 dios=0\n\
+             259:1 rbytes=0 wbytes=512 rios=0 wios=2 dbytes=8 dios=1\n",
+        );
+        assert_eq!(devices.len(), 2);
+        assert_eq!((devices[0].major, devices[0].minor), (8, 0));
+        assert_eq!(devices[0].rbytes, 1_048_576);
+        assert_eq!(devices[1].wios, 2);
+        assert_eq!(devices[1].dios, 1);
+        assert!(IoDeviceStats::from_io_stat("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("max\n"), None);
+        assert_eq!(parse_limit("536870912\n"), Some(536_870_912));
+    }
+
+    #[test]
+    fn test_network_stats_swap_direction() {
+        let class_net =
+            std::env::temp_dir().join(format!("alice-container-stats-net-{}", std::process::id()));
+        let dir = class_net.join("veth-h-web/statistics");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("rx_bytes"), "100\n").unwrap();
+        std::fs::write(dir.join("tx_bytes"), "2500\n").unwrap();
+        std::fs::write(dir.join("tx_packets"), "3\n").unwrap();
+
+        let stats = network_stats(&class_net, "veth-h-web");
+        let missing = network_stats(&class_net, "veth-h-gone");
+        let _ = std::fs::remove_dir_all(&class_net);
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.rx_bytes, 2500);
+        assert_eq!(stats.rx_packets, 3);
+        assert_eq!(stats.tx_bytes, 100);
+        assert_eq!(stats.tx_errors, 0);
+        assert!(missing.is_none());
+    }
+}
@@ -0,0 +1,394 @@
+//! Restart Supervisor
+//!
+//! Restarts the containers of a [`ContainerManager`] whose init process
+//! exited, according to each container's
+//! [`RestartPolicy`](crate::container::RestartPolicy).
+//!
+//! ## Backoff
+//!
+//! ```text
+//! attempt:  0      1      2      3            n
+//! delay:    100ms  200ms  400ms  800ms  ...   min(initial × 2ⁿ, max) ± jitter
+//! ```
+//!
+//! A container that stays up for `Backoff::reset_after` is considered
+//! healthy again: its next failure starts over at attempt 0, and
+//! `OnFailure { max_retries }` counts from there.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! let manager = Arc::new(ContainerManager::new());
+//! let supervisor = Supervisor::new(Arc::clone(&manager));
+//!
+//! std::thread::spawn(move || {
+//!     supervisor.run(Duration::from_millis(100), |id, e| eprintln!("{id}: {e}"));
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::container::{Container, ContainerError, ContainerState, ExitStatus};
+use crate::health::PendingProbe;
+use crate::manager::{ContainerFilter, ContainerManager};
+
+// ============================================================================
+// Backoff
+// ============================================================================
+
+/// Exponential backoff between restart attempts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay before the first restart
+    pub initial: Duration,
+    /// Upper bound for the delay
+    pub max: Duration,
+    /// Random spread applied to each delay, in percent (0-100)
+    pub jitter_percent: u32,
+    /// Uptime after which the attempt counter is reset
+    pub reset_after: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(60),
+            jitter_percent: 20,
+            reset_after: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before restart `attempt` (0-based), without jitter
+    #[must_use]
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+
+    /// Delay before restart `attempt`, spread by `random`
+    ///
+    /// The result lies within `base_delay(attempt) ± jitter_percent`.
+    #[must_use]
+    pub fn delay(&self, attempt: u32, random: u64) -> Duration {
+        let base = self.base_delay(attempt);
+        let jitter = u64::from(self.jitter_percent.min(100));
+        // Scale in [100 - jitter, 100 + jitter] percent
+        let percent = 100 - jitter + random % (2 * jitter + 1);
+        let nanos = base.as_nanos().saturating_mul(u128::from(percent)) / 100;
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+}
+
+// ============================================================================
+// Restart Statistics
+// ============================================================================
+
+/// Restart history of one container
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestartStats {
+    /// Successful restarts since the container was first seen
+    pub restart_count: u32,
+    /// Consecutive restart attempts since the container was last healthy
+    pub attempts: u32,
+    /// How the init process last exited
+    pub last_exit: Option<ExitStatus>,
+    /// When the next restart is due, if one is scheduled
+    pub next_restart: Option<Instant>,
+}
+
+/// Per-container bookkeeping
+#[derive(Debug, Default)]
+struct Tracker {
+    stats: RestartStats,
+    /// When the supervisor last started the container
+    started_at: Option<Instant>,
+}
+
+// ============================================================================
+// Supervisor
+// ============================================================================
+
+/// Applies restart policies to the containers of a manager
+#[derive(Debug)]
+pub struct Supervisor {
+    manager: Arc<ContainerManager>,
+    backoff: Backoff,
+    tracked: Mutex<HashMap<String, Tracker>>,
+    shutdown: AtomicBool,
+    /// xorshift64 state for jitter
+    rng: AtomicU64,
+}
+
+impl Supervisor {
+    /// Supervise the containers of `manager` with the default backoff
+    #[must_use]
+    pub fn new(manager: Arc<ContainerManager>) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+            ^ u64::from(std::process::id());
+
+        Self {
+            manager,
+            backoff: Backoff::default(),
+            tracked: Mutex::new(HashMap::new()),
+            shutdown: AtomicBool::new(false),
+            // xorshift must not start at zero
+            rng: AtomicU64::new(seed | 1),
+        }
+    }
+
+    /// Set the backoff between restart attempts
+    #[must_use]
+    pub const fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Managed containers
+    #[must_use]
+    pub fn manager(&self) -> &Arc<ContainerManager> {
+        &self.manager
+    }
+
+    /// Restart history of a container
+    #[must_use]
+    pub fn stats(&self, id: &str) -> Option<RestartStats> {
+        self.tracked().get(id).map(|t| t.stats)
+    }
+
+    /// Start stopped containers whose policy asks for it after a restart
+    ///
+    /// Call once after reloading containers with `Container::load()` and
+    /// adding them to the manager. Exited containers are handled by `poll()`.
+    #[must_use]
+    pub fn recover(&self) -> Vec<(String, ContainerError)> {
+        let stopped = ContainerFilter::new().state(ContainerState::Stopped);
+        let mut failed = Vec::new();
+
+        for id in self.manager.list(&stopped) {
+            let result = self.manager.with(&id, |c| {
+                if c.state() == ContainerState::Stopped
+                    && c.config().restart_policy.start_on_recovery()
+                {
+                    c.start()?;
+                    self.tracked().entry(id.clone()).or_default().started_at = Some(Instant::now());
+                }
+                Ok(())
+            });
+            match result {
+                Ok(Ok(())) | Err(ContainerError::NotFound(_)) => {}
+                Ok(Err(e)) | Err(e) => failed.push((id, e)),
+            }
+        }
+        failed
+    }
+
+    /// Check every container once and restart those that are due
+    ///
+    /// Also starts due health checks, each on its own thread that records
+    /// the result once the probe finishes, so a slow probe delays neither
+    /// this pass nor the next. Returns the containers whose check or restart
+    /// failed; a failed restart is retried with the next backoff step.
+    #[must_use]
+    pub fn poll(&self) -> Vec<(String, ContainerError)> {
+        let ids = self.manager.list(&ContainerFilter::new());
+        self.tracked().retain(|id, _| ids.contains(id));
+
+        let mut failed = Vec::new();
+        for id in ids {
+            let result = self.manager.with(&id, |c| {
+                self.check(c, Instant::now())?;
+                c.start_health_probe()
+            });
+            match result {
+                Ok(Ok(Some(probe))) => {
+                    if let Err(e) = self.probe(&id, probe) {
+                        failed.push((id, e));
+                    }
+                }
+                Ok(Ok(None)) | Err(ContainerError::NotFound(_)) => {}
+                Ok(Err(e)) | Err(e) => failed.push((id, e)),
+            }
+        }
+        failed
+    }
+
+    /// Poll every `interval` until `shutdown()` is called
+    pub fn run(&self, interval: Duration, mut on_error: impl FnMut(&str, &ContainerError)) {
+        while !self.shutdown.load(Ordering::Acquire) {
+            for (id, e) in self.poll() {
+                on_error(&id, &e);
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Make `run()` return after its current pass
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+    }
+
+    /// Apply the restart policy to one container
+    fn check(&self, container: &mut Container, now: Instant) -> Result<(), ContainerError> {
+        let policy = container.config().restart_policy;
+        let state = container.state();
+
+        let mut tracked = self.tracked();
+        let tracker = tracked.entry(container.id().to_string()).or_default();
+
+        if !matches!(state, ContainerState::Exited { .. }) {
+            // Stopped by the user or not started yet: cancel pending restarts
+            if !matches!(state, ContainerState::Running | ContainerState::Paused) {
+                tracker.stats.next_restart = None;
+            }
+            return Ok(());
+        }
+
+        let next_restart = match tracker.stats.next_restart {
+            Some(at) => at,
+            None => {
+                // Newly observed exit
+                let status = container.try_wait()?.unwrap_or(ExitStatus::Unknown);
+                tracker.stats.last_exit = Some(status);
+                if tracker
+                    .started_at
+                    .take()
+                    .is_some_and(|at| now.duration_since(at) >= self.backoff.reset_after)
+                {
+                    tracker.stats.attempts = 0;
+                }
+
+                if !policy.should_restart(status, tracker.stats.attempts) {
+                    return Ok(());
+                }
+                let at = now + self.backoff.delay(tracker.stats.attempts, self.random());
+                tracker.stats.next_restart = Some(at);
+                at
+            }
+        };
+
+        if now < next_restart {
+            return Ok(());
+        }
+
+        tracker.stats.next_restart = None;
+        tracker.stats.attempts = tracker.stats.attempts.saturating_add(1);
+        container.start()?;
+        tracker.stats.restart_count = tracker.stats.restart_count.saturating_add(1);
+        tracker.started_at = Some(now);
+        Ok(())
+    }
+
+    /// Wait for a health probe on a new thread and record its result
+    fn probe(&self, id: &str, probe: PendingProbe) -> Result<(), ContainerError> {
+        let probe = Arc::new(probe);
+        let (manager, pending, owner) = (
+            Arc::clone(&self.manager),
+            Arc::clone(&probe),
+            id.to_string(),
+        );
+
+        let spawned = std::thread::Builder::new()
+            .name("alice-health".into())
+            .spawn(move || {
+                let healthy = pending.run();
+                // Nothing to record for a container removed meanwhile
+                let _ = manager.with(&owner, |c| c.finish_health_probe(&pending, healthy));
+            });
+        spawned.map(drop).map_err(|e| {
+            // Count the probe as failed so the next one can start
+            probe.cancel();
+            let _ = self
+                .manager
+                .with(id, |c| c.finish_health_probe(&probe, false));
+            ContainerError::ProcessError(format!("spawn health probe: {e}"))
+        })
+    }
+
+    /// Next xorshift64 value
+    fn random(&self) -> u64 {
+        let mut x = self.rng.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng.store(x, Ordering::Relaxed);
+        x
+    }
+
+    fn tracked(&self) -> MutexGuard<'_, HashMap<String, Tracker>> {
+        self.tracked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_delay_doubles_up_to_max() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            ..Backoff::default()
+        };
+        assert_eq!(backoff.base_delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.base_delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.base_delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.base_delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.base_delay(200), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_delay_jitter_bounds() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(1),
+            jitter_percent: 20,
+            ..Backoff::default()
+        };
+        assert_eq!(backoff.delay(0, 0), Duration::from_millis(800));
+        assert_eq!(backoff.delay(0, 20), Duration::from_secs(1));
+        assert_eq!(backoff.delay(0, 40), Duration::from_millis(1200));
+        for random in [1, 7, 12_345, u64::MAX] {
+            let d = backoff.delay(0, random);
+            assert!(d >= Duration::from_millis(800) && d <= Duration::from_millis(1200));
+        }
+
+        let exact = Backoff {
+            jitter_percent: 0,
+            ..backoff
+        };
+        assert_eq!(exact.delay(2, 99), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_random_varies() {
+        let supervisor = Supervisor::new(Arc::new(ContainerManager::new()));
+        let a = supervisor.random();
+        let b = supervisor.random();
+        assert_ne!(a, b);
+        assert_ne!(a, 0);
+    }
+
+    #[test]
+    fn test_poll_empty_manager() {
+        let supervisor = Supervisor::new(Arc::new(ContainerManager::new()));
+        assert!(supervisor.poll().is_empty());
+        assert!(supervisor.recover().is_empty());
+        assert_eq!(supervisor.stats("web"), None);
+
+        supervisor.shutdown();
+        // Returns immediately once shut down
+        supervisor.run(Duration::ZERO, |_, _| {});
+    }
+}
@@ -0,0 +1,242 @@
+//! Container Process Listing
+//!
+//! The processes of a container's cgroup as `ps` inside the container would
+//! show them, collected with `Container::top()` without entering any
+//! namespace.
+//!
+//! ## Sources
+//!
+//! | Field | File |
+//! |-------|------|
+//! | `pid` | `cgroup.procs` of the container's cgroup and its descendants |
+//! | `container_pid` | entry of `NSpid` in `/proc/<pid>/status` at the init's namespace depth |
+//! | `command` | `/proc/<pid>/cmdline`, or `[Name]` for kernel threads |
+//! | `uid`, `user` | `Uid` in `/proc/<pid>/status`, mapped through the init's `uid_map` |
+//! | `state`, `rss` | `State` and `VmRSS` in `/proc/<pid>/status` |
+//! | `cpu_time` | `utime` + `stime` in `/proc/<pid>/stat` |
+//!
+//! Processes that exit while the listing is taken are left out.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cgroup::CgroupController;
+use crate::container::ContainerError;
+use crate::namespace::{IdMap, OVERFLOW_ID};
+use crate::passwd::AccountDb;
+
+// ============================================================================
+// Process Info
+// ============================================================================
+
+/// One process of a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    /// PID in the host PID namespace
+    pub pid: u32,
+    /// PID in the container's PID namespace
+    pub container_pid: u32,
+    /// Command line, or the thread name in brackets if it has none
+    pub command: String,
+    /// Effective UID inside the container
+    pub uid: u32,
+    /// Name of `uid` in the container's `/etc/passwd`, if listed there
+    pub user: Option<String>,
+    /// State letter as shown by `ps` (`R`, `S`, `D`, `Z`, `T`, ...)
+    pub state: char,
+    /// Resident set size in bytes
+    pub rss: u64,
+    /// User plus system CPU time
+    pub cpu_time: Duration,
+}
+
+/// Fields of `/proc/<pid>/status` used for the listing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ProcStatus {
+    name: String,
+    state: char,
+    /// Effective UID as seen from the host
+    uid: u32,
+    /// `NSpid` entries, from the host's PID namespace inwards
+    nspid: Vec<u32>,
+    /// `VmRSS` in bytes
+    rss: u64,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse `/proc/<pid>/status` content
+fn parse_status(content: &str) -> ProcStatus {
+    let mut status = ProcStatus::default();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "Name" => status.name = value.to_string(),
+            "State" => status.state = value.chars().next().unwrap_or('?'),
+            // Real, effective, saved and filesystem UID
+            "Uid" => {
+                if let Some(uid) = value.split_whitespace().nth(1).and_then(|v| v.parse().ok()) {
+                    status.uid = uid;
+                }
+            }
+            "NSpid" => {
+                status.nspid = value
+                    .split_whitespace()
+                    .map_while(|v| v.parse().ok())
+                    .collect();
+            }
+            "VmRSS" => {
+                if let Some(kb) = value
+                    .strip_suffix("kB")
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                {
+                    status.rss = kb * 1024;
+                }
+            }
+            _ => {}
+        }
+    }
+    status
+}
+
+/// Extract `utime` + `stime` (fields 14 and 15) from `/proc/<pid>/stat`, in clock ticks
+///
+/// The command name (field 2) may contain spaces and parentheses, so fields
+/// are counted from the last `)`.
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    // Field 3 (state) is the first after the command name
+    let mut fields = rest.split_whitespace().skip(14 - 3);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// PID in the namespace `depth` levels down, counting the host's as 1
+fn pid_at_depth(nspid: &[u32], depth: usize) -> Option<u32> {
+    nspid.get(depth.checked_sub(1)?).copied()
+}
+
+/// Join the NUL-separated arguments of `/proc/<pid>/cmdline`
+fn parse_cmdline(content: &[u8]) -> String {
+    content
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ============================================================================
+// Collection
+// ============================================================================
+
+/// List the processes of `cgroup`, resolving users in the root of `init_pid`
+///
+/// # Errors
+///
+/// Returns an error if `cgroup.procs` cannot be read.
+pub(crate) fn list(
+    cgroup: &CgroupController,
+    init_pid: u32,
+) -> Result<Vec<ProcessInfo>, ContainerError> {
+    let pids = cgroup.all_processes()?;
+    // The container's PID namespace is the init's innermost one; processes
+    // in namespaces nested below it have more entries
+    let depth = std::fs::read_to_string(format!("/proc/{init_pid}/status"))
+        .map(|content| parse_status(&content).nspid.len())
+        .unwrap_or(0);
+    // An unreadable image passwd only costs the user names
+    let accounts =
+        AccountDb::load(Path::new(&format!("/proc/{init_pid}/root"))).unwrap_or_default();
+    let uid_map = IdMap::read(init_pid, "uid_map");
+    // SAFETY: sysconf has no memory-safety preconditions
+    let ticks_per_sec = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        n if n > 0 => n as u64,
+        _ => 100,
+    };
+
+    Ok(pids
+        .into_iter()
+        .filter_map(|pid| {
+            let status =
+                parse_status(&std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?);
+            let ticks =
+                parse_cpu_ticks(&std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)?;
+            let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).unwrap_or_default();
+            let uid = uid_map.to_inner(status.uid).unwrap_or(OVERFLOW_ID);
+
+            let mut command = parse_cmdline(&cmdline);
+            if command.is_empty() {
+                command = format!("[{}]", status.name);
+            }
+            Some(ProcessInfo {
+                pid,
+                container_pid: pid_at_depth(&status.nspid, depth).unwrap_or(pid),
+                command,
+                uid,
+                user: accounts.user_name(uid).map(str::to_string),
+                state: status.state,
+                rss: status.rss,
+                cpu_time: Duration::from_micros(ticks * 1_000_000 / ticks_per_sec),
+            })
+        })
+        .collect())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let status = parse_status(
+            "Name:\tgame-server\nUmask:\t0022\nState:\tS (sleeping)\nTgid:\t4242\n\
+             Uid:\t101000\t101000\t101000\t101000\nGid:\t101000\t101000\t101000\t101000\n\
+             NSpid:\t4242\t7\nVmPeak:\t  20000 kB\nVmRSS:\t   1536 kB\n",
+        );
+        assert_eq!(status.name, "game-server");
+        assert_eq!(status.state, 'S');
+        assert_eq!(status.uid, 101_000);
+        assert_eq!(status.nspid, [4242, 7]);
+        assert_eq!(status.rss, 1536 * 1024);
+
+        // Kernel threads have no memory lines
+        let kthread = parse_status("Name:\tkworker/0:1\nState:\tI (idle)\nNSpid:\t12\n");
+        assert_eq!(kthread.rss, 0);
+        assert_eq!(kthread.nspid, [12]);
+
+        // A process in a PID namespace nested inside the container's
+        let nested = parse_status("Name:\tsh\nNSpid:\t5000\t42\t1\n");
+        assert_eq!(nested.nspid, [5000, 42, 1]);
+        assert_eq!(pid_at_depth(&nested.nspid, 2), Some(42));
+        assert_eq!(pid_at_depth(&nested.nspid, 0), None);
+        assert_eq!(pid_at_depth(&[12], 2), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_ticks() {
+        let stat =
+            "4242 (my (odd) name) S 1 4242 4242 0 -1 4194560 500 0 0 0 150 25 0 0 20 0 1 0 12345 ";
+        assert_eq!(parse_cpu_ticks(stat), Some(175));
+        assert_eq!(parse_cpu_ticks("4242 (truncated) S 1"), None);
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            parse_cmdline(b"/bin/sh\0-c\0sleep 60\0"),
+            "/bin/sh -c sleep 60"
+        );
+        assert_eq!(parse_cmdline(b""), "");
+    }
+}